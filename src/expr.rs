@@ -0,0 +1,544 @@
+use crate::predicate::lookup;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+/// COMPUTE (Op 9) config blob.
+/// Over a list input, `expr` is evaluated per record and stored in the `as` field;
+/// over scalar inputs, the result itself is the output.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ComputeConfig {
+    pub expr: String,
+    #[serde(rename = "as", default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+impl ComputeConfig {
+    pub fn output_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or("value")
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ExprError {
+    #[error("Parse error: {0}")]
+    Parse(String),
+    #[error("Division by zero in '{0}'")]
+    DivisionByZero(String),
+    #[error("Overflow in '{0}'")]
+    Overflow(String),
+    #[error("Type error: {0}")]
+    Type(String),
+    #[error("Input '{0}' is not bound")]
+    Unbound(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Value),
+    Field(String), // Dotted path on the current record
+    Input(usize),  // `$N`: the whole value of input N
+    Param(String), // `{{name}}`: a run's bound input, substituted by `bind`
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, BinOp, Box<Expr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+}
+
+impl BinOp {
+    fn symbol(&self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::And => "and",
+            BinOp::Or => "or",
+        }
+    }
+}
+
+// --- Parsing ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(Value),
+    Str(String),
+    Ident(String),
+    Input(usize),
+    Param(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, ExprError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.' || chars[i] == '_') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().filter(|c| **c != '_').collect();
+            let number = if let Ok(n) = text.parse::<i64>() {
+                Value::from(n)
+            } else {
+                let f = text.parse::<f64>().map_err(|_| ExprError::Parse(format!("bad number '{}'", text)))?;
+                Value::from(f)
+            };
+            tokens.push(Token::Number(number));
+        } else if c == '$' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i].is_ascii_digit() {
+                i += 1;
+            }
+            let index: String = chars[start..i].iter().collect();
+            let index = index.parse::<usize>().map_err(|_| ExprError::Parse("'$' must be followed by an input index".into()))?;
+            tokens.push(Token::Input(index));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_' || chars[i] == '.') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            tokens.push(match word.as_str() {
+                "and" => Token::Op("&&"),
+                "or" => Token::Op("||"),
+                "not" => Token::Op("!"),
+                _ => Token::Ident(word),
+            });
+        } else if c == '"' || c == '\'' {
+            let start = i + 1;
+            i += 1;
+            while i < chars.len() && chars[i] != c {
+                i += 1;
+            }
+            if i >= chars.len() {
+                return Err(ExprError::Parse("unterminated string".into()));
+            }
+            tokens.push(Token::Str(chars[start..i].iter().collect()));
+            i += 1;
+        } else if c == '{' && chars.get(i + 1) == Some(&'{') {
+            // `{{input}}` placeholder: a variable the kernel binds to the run's typed value
+            let end = (i + 2..chars.len().saturating_sub(1)).find(|j| chars[*j] == '}' && chars[j + 1] == '}')
+                .ok_or_else(|| ExprError::Parse("unterminated '{{' placeholder".into()))?;
+            let name: String = chars[i + 2..end].iter().collect();
+            let name = name.trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                return Err(ExprError::Parse(format!("bad placeholder name '{}'", name)));
+            }
+            tokens.push(Token::Param(name.to_string()));
+            i = end + 2;
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let op = ["==", "!=", "<=", ">=", "&&", "||"].into_iter().find(|op| *op == two);
+            if let Some(op) = op {
+                tokens.push(Token::Op(op));
+                i += 2;
+            } else {
+                let op = ["+", "-", "*", "/", "%", "<", ">", "!"].into_iter()
+                    .find(|op| op.starts_with(c))
+                    .ok_or_else(|| ExprError::Parse(format!("unexpected character '{}'", c)))?;
+                tokens.push(Token::Op(op));
+                i += 1;
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Deepest expression tree `Expr::parse` builds; parsing and evaluation recurse once per level
+const MAX_DEPTH: usize = 128;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize, // Levels of the tree above the current token (parentheses, prefixes, chained operators)
+}
+
+impl Parser {
+    fn descend(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExprError::Parse(format!("expression nests deeper than {} levels", MAX_DEPTH)));
+        }
+        Ok(())
+    }
+
+    fn peek_op(&self, ops: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Op(op)) if ops.contains(op) => Some(op),
+            _ => None,
+        }
+    }
+
+    fn binary(&mut self, ops: &[&'static str], next: fn(&mut Self) -> Result<Expr, ExprError>) -> Result<Expr, ExprError> {
+        let mut left = next(self)?;
+        let depth = self.depth;
+        while let Some(op) = self.peek_op(ops) {
+            self.pos += 1;
+            // Each operator in a chain puts the left side one level deeper
+            self.descend()?;
+            let right = next(self)?;
+            let op = match op {
+                "||" => BinOp::Or,
+                "&&" => BinOp::And,
+                "==" => BinOp::Eq,
+                "!=" => BinOp::Ne,
+                "<" => BinOp::Lt,
+                "<=" => BinOp::Le,
+                ">" => BinOp::Gt,
+                ">=" => BinOp::Ge,
+                "+" => BinOp::Add,
+                "-" => BinOp::Sub,
+                "*" => BinOp::Mul,
+                "/" => BinOp::Div,
+                _ => BinOp::Rem,
+            };
+            left = Expr::Binary(Box::new(left), op, Box::new(right));
+        }
+        self.depth = depth;
+        Ok(left)
+    }
+
+    fn or(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&["||"], Self::and)
+    }
+
+    fn and(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&["&&"], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&["==", "!=", "<", "<=", ">", ">="], Self::additive)
+    }
+
+    fn additive(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&["+", "-"], Self::multiplicative)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ExprError> {
+        self.binary(&["*", "/", "%"], Self::unary)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        if let Some(op) = self.peek_op(&["-", "!"]) {
+            self.pos += 1;
+            self.descend()?;
+            let inner = Box::new(self.unary()?);
+            self.depth -= 1;
+            return Ok(if op == "-" { Expr::Neg(inner) } else { Expr::Not(inner) });
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExprError> {
+        let token = self.tokens.get(self.pos).cloned()
+            .ok_or_else(|| ExprError::Parse("unexpected end of expression".into()))?;
+        self.pos += 1;
+        Ok(match token {
+            Token::Number(n) => Expr::Literal(n),
+            Token::Str(s) => Expr::Literal(Value::String(s)),
+            Token::Input(i) => Expr::Input(i),
+            Token::Param(name) => Expr::Param(name),
+            Token::Ident(word) => match word.as_str() {
+                "true" => Expr::Literal(Value::Bool(true)),
+                "false" => Expr::Literal(Value::Bool(false)),
+                "null" => Expr::Literal(Value::Null),
+                _ => Expr::Field(word),
+            },
+            Token::LParen => {
+                self.descend()?;
+                let inner = self.or()?;
+                self.depth -= 1;
+                if self.tokens.get(self.pos) != Some(&Token::RParen) {
+                    return Err(ExprError::Parse("missing ')'".into()));
+                }
+                self.pos += 1;
+                inner
+            },
+            other => return Err(ExprError::Parse(format!("unexpected {:?}", other))),
+        })
+    }
+}
+
+impl Expr {
+    /// Arithmetic (+ - * / %), comparisons, and/or/not over fields, `$N` inputs and literals
+    pub fn parse(source: &str) -> Result<Expr, ExprError> {
+        let mut parser = Parser { tokens: tokenize(source)?, pos: 0, depth: 0 };
+        let expr = parser.or()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(ExprError::Parse(format!("unexpected {:?}", parser.tokens[parser.pos])));
+        }
+        Ok(expr)
+    }
+
+    /// Substitutes the run's bound inputs for `{{name}}` variables, as values (never as source)
+    pub fn bind(self, params: &serde_json::Map<String, Value>) -> Result<Expr, ExprError> {
+        Ok(match self {
+            Expr::Param(name) => Expr::Literal(params.get(&name).cloned().ok_or(ExprError::Unbound(name))?),
+            Expr::Neg(inner) => Expr::Neg(Box::new(inner.bind(params)?)),
+            Expr::Not(inner) => Expr::Not(Box::new(inner.bind(params)?)),
+            Expr::Binary(left, op, right) => Expr::Binary(Box::new(left.bind(params)?), op, Box::new(right.bind(params)?)),
+            other => other,
+        })
+    }
+
+    /// Evaluates against a record (fields) and the node's inputs (`$N`).
+    /// Missing fields are null, and null propagates through arithmetic and comparisons.
+    pub fn eval(&self, record: &Value, inputs: &[Value]) -> Result<Value, ExprError> {
+        match self {
+            Expr::Literal(v) => Ok(v.clone()),
+            Expr::Field(path) => Ok(lookup(record, path).cloned().unwrap_or(Value::Null)),
+            Expr::Input(i) => Ok(inputs.get(*i).cloned().unwrap_or(Value::Null)),
+            Expr::Param(name) => Err(ExprError::Unbound(name.clone())),
+            Expr::Neg(inner) => match inner.eval(record, inputs)? {
+                Value::Null => Ok(Value::Null),
+                v => match Num::of(&v) {
+                    Some(Num::Int(n)) => n.checked_neg().map(Value::from).ok_or_else(|| ExprError::Overflow(format!("-{}", n))),
+                    Some(Num::Float(f)) => Ok(Value::from(-f)),
+                    None => Err(ExprError::Type(format!("cannot negate {}", v))),
+                },
+            },
+            Expr::Not(inner) => match inner.eval(record, inputs)? {
+                Value::Null => Ok(Value::Null),
+                v => Ok(Value::Bool(!truthy(&v))),
+            },
+            Expr::Binary(left, op, right) => {
+                let a = left.eval(record, inputs)?;
+                // Short-circuit logic
+                match op {
+                    BinOp::And if !a.is_null() && !truthy(&a) => return Ok(Value::Bool(false)),
+                    BinOp::Or if truthy(&a) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
+                let b = right.eval(record, inputs)?;
+                binary(&a, *op, &b)
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Num {
+    Int(i64),
+    Float(f64),
+}
+
+impl Num {
+    fn of(value: &Value) -> Option<Num> {
+        match value {
+            Value::Number(n) => n.as_i64().map(Num::Int).or_else(|| n.as_f64().map(Num::Float)),
+            Value::String(s) => s.trim().parse::<i64>().map(Num::Int).ok()
+                .or_else(|| s.trim().parse::<f64>().ok().map(Num::Float)),
+            _ => None,
+        }
+    }
+
+    fn as_f64(self) -> f64 {
+        match self {
+            Num::Int(n) => n as f64,
+            Num::Float(f) => f,
+        }
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64().is_some_and(|f| f != 0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn binary(a: &Value, op: BinOp, b: &Value) -> Result<Value, ExprError> {
+    let describe = || format!("{} {} {}", a, op.symbol(), b);
+    match op {
+        BinOp::And => return Ok(if a.is_null() || b.is_null() { Value::Null } else { Value::Bool(truthy(b)) }),
+        BinOp::Or => return Ok(if b.is_null() { Value::Null } else { Value::Bool(truthy(b)) }),
+        _ => {}
+    }
+    if a.is_null() || b.is_null() {
+        return Ok(Value::Null);
+    }
+
+    if matches!(op, BinOp::Eq | BinOp::Ne) {
+        let equal = crate::predicate::loose_eq(a, b);
+        return Ok(Value::Bool(if op == BinOp::Eq { equal } else { !equal }));
+    }
+
+    let (x, y) = match (Num::of(a), Num::of(b)) {
+        (Some(x), Some(y)) => (x, y),
+        _ => {
+            // Text only supports ordering and concatenation
+            return match (a, b, op) {
+                (Value::String(x), Value::String(y), BinOp::Lt) => Ok(Value::Bool(x < y)),
+                (Value::String(x), Value::String(y), BinOp::Le) => Ok(Value::Bool(x <= y)),
+                (Value::String(x), Value::String(y), BinOp::Gt) => Ok(Value::Bool(x > y)),
+                (Value::String(x), Value::String(y), BinOp::Ge) => Ok(Value::Bool(x >= y)),
+                (Value::String(x), Value::String(y), BinOp::Add) => Ok(Value::String(format!("{}{}", x, y))),
+                _ => Err(ExprError::Type(format!("cannot evaluate {}", describe()))),
+            };
+        }
+    };
+
+    let result = match (x, y) {
+        (Num::Int(x), Num::Int(y)) => match op {
+            BinOp::Add => x.checked_add(y).map(Value::from),
+            BinOp::Sub => x.checked_sub(y).map(Value::from),
+            BinOp::Mul => x.checked_mul(y).map(Value::from),
+            BinOp::Div | BinOp::Rem if y == 0 => return Err(ExprError::DivisionByZero(describe())),
+            // Exact quotients stay integral; anything else becomes a float
+            BinOp::Div => match x.checked_rem(y) {
+                Some(0) => x.checked_div(y).map(Value::from),
+                Some(_) => Some(Value::from(x as f64 / y as f64)),
+                None => None,
+            },
+            BinOp::Rem => x.checked_rem(y).map(Value::from),
+            _ => Some(compare(x as f64, op, y as f64)),
+        },
+        (x, y) => {
+            let (x, y) = (x.as_f64(), y.as_f64());
+            let value = match op {
+                BinOp::Add => x + y,
+                BinOp::Sub => x - y,
+                BinOp::Mul => x * y,
+                BinOp::Div | BinOp::Rem if y == 0.0 => return Err(ExprError::DivisionByZero(describe())),
+                BinOp::Div => x / y,
+                BinOp::Rem => x % y,
+                _ => return Ok(compare(x, op, y)),
+            };
+            value.is_finite().then(|| Value::from(value))
+        },
+    };
+    result.ok_or_else(|| ExprError::Overflow(describe()))
+}
+
+fn compare(x: f64, op: BinOp, y: f64) -> Value {
+    Value::Bool(match op {
+        BinOp::Lt => x < y,
+        BinOp::Le => x <= y,
+        BinOp::Gt => x > y,
+        _ => x >= y,
+    })
+}
+
+/// Runs a COMPUTE config over the node's inputs (see `ComputeConfig`), with `{{name}}`
+/// variables bound to `params`
+pub fn compute(config: &ComputeConfig, inputs: Vec<Value>, params: &serde_json::Map<String, Value>) -> Result<Value, ExprError> {
+    let expr = Expr::parse(&config.expr)?.bind(params)?;
+    match inputs.first() {
+        Some(Value::Array(rows)) => {
+            let mut out = Vec::with_capacity(rows.len());
+            for row in rows {
+                let value = expr.eval(row, &inputs)?;
+                let mut row = row.clone();
+                if let Value::Object(fields) = &mut row {
+                    fields.insert(config.output_name().to_string(), value);
+                }
+                out.push(row);
+            }
+            Ok(Value::Array(out))
+        },
+        Some(record) => expr.eval(record, &inputs),
+        None => expr.eval(&Value::Null, &inputs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn eval(source: &str, record: Value) -> Result<Value, ExprError> {
+        Expr::parse(source)?.eval(&record, &[])
+    }
+
+    #[test]
+    fn precedence_and_parentheses() {
+        assert_eq!(eval("1 + 2 * 3", Value::Null), Ok(json!(7)));
+        assert_eq!(eval("(1 + 2) * 3", Value::Null), Ok(json!(9)));
+        assert_eq!(eval("-2 * 3 > -7 and not false", Value::Null), Ok(json!(true)));
+    }
+
+    #[test]
+    fn fields_inputs_and_null_propagation() {
+        assert_eq!(eval("price * qty", json!({"price": 2.5, "qty": 4})), Ok(json!(10.0)));
+        assert_eq!(eval("item.price + 1", json!({"item": {"price": "4"}})), Ok(json!(5)));
+        assert_eq!(eval("missing * 2", json!({})), Ok(Value::Null));
+        let expr = Expr::parse("$1 - $0").unwrap();
+        assert_eq!(expr.eval(&Value::Null, &[json!(3), json!(10)]), Ok(json!(7)));
+    }
+
+    #[test]
+    fn arithmetic_errors() {
+        assert!(matches!(eval("1 / 0", Value::Null), Err(ExprError::DivisionByZero(_))));
+        assert!(matches!(eval("9223372036854775807 + 1", Value::Null), Err(ExprError::Overflow(_))));
+        assert!(matches!(Expr::parse("1 +"), Err(ExprError::Parse(_))));
+        assert!(matches!(Expr::parse("(1"), Err(ExprError::Parse(_))));
+    }
+
+    #[test]
+    fn nesting_is_bounded() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert_eq!(eval(&nested(MAX_DEPTH), Value::Null), Ok(json!(1)));
+        let too_deep = Err(ExprError::Parse(format!("expression nests deeper than {} levels", MAX_DEPTH)));
+        assert_eq!(Expr::parse(&nested(100_000)), too_deep);
+        assert_eq!(Expr::parse(&"-".repeat(100_000)), too_deep);
+        assert_eq!(Expr::parse(&vec!["1"; 100_000].join(" + ")), too_deep);
+        // Depth is per branch, not per expression
+        let wide = vec![nested(MAX_DEPTH / 2); 8].join(" * ");
+        assert_eq!(eval(&wide, Value::Null), Ok(json!(1)));
+    }
+
+    #[test]
+    fn placeholders_bind_as_values() {
+        let params = json!({"rate": 2, "name": "a + 1"}).as_object().unwrap().clone();
+        let config = ComputeConfig { expr: "price * {{ rate }}".into(), alias: Some("total".into()) };
+        assert_eq!(compute(&config, vec![json!([{"price": 3}])], &params), Ok(json!([{"price": 3, "total": 6}])));
+        // Bound text stays a string literal; it is never parsed as source
+        let config = ComputeConfig { expr: "{{name}}".into(), alias: None };
+        assert_eq!(compute(&config, vec![json!({"a": 1})], &params), Ok(json!("a + 1")));
+        let config = ComputeConfig { expr: "{{other}} + 1".into(), alias: None };
+        assert_eq!(compute(&config, vec![], &params), Err(ExprError::Unbound("other".into())));
+    }
+}
@@ -0,0 +1,264 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// FINANCIAL (Op 100) config blob: the terms of a financing contract. Amounts are exact
+/// decimals (serialized as strings). An empty blob is a plain audit/identity node with no terms.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FinancialContract {
+    pub principal: Decimal,
+    pub schedule: Vec<Payment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fees: Vec<Fee>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profit: Option<Profit>, // None: an interest-free loan (qard hasan)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Payment {
+    pub period: u32, // Installment number
+    pub amount: Decimal,
+    #[serde(default, skip_serializing_if = "Decimal::is_zero")]
+    pub late_charge_per_day: Decimal, // Added for every day the installment is late
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Fee {
+    pub name: String,
+    pub amount: Decimal,
+    /// Recovers a disclosed actual cost (e.g. stamp duty) instead of earning on the principal
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub actual_cost: bool,
+}
+
+/// The profit the financier may earn, disclosed up front
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Profit {
+    /// Murabaha: a fixed markup on the principal
+    Markup { amount: Decimal },
+    /// Mudarabah / musharakah: a share of the venture's realised profit, never of a loss-free return
+    Sharing { financier_ratio: Decimal },
+}
+
+impl FinancialContract {
+    /// The contract in an Op 100 blob; None for an empty blob
+    pub fn from_blob(blob: &[u8]) -> Result<Option<Self>, String> {
+        if blob.iter().all(u8::is_ascii_whitespace) {
+            return Ok(None);
+        }
+        serde_json::from_slice(blob).map(Some).map_err(|e| format!("Invalid financial contract: {}", e))
+    }
+
+    /// Terms the Guard cannot reason about: non-positive principal, negative amounts, no schedule
+    pub fn validate(&self) -> Result<(), String> {
+        if self.principal <= Decimal::ZERO {
+            return Err(format!("principal must be positive, got {}", self.principal));
+        }
+        if self.schedule.is_empty() {
+            return Err("the contract has no payment schedule".to_string());
+        }
+        for payment in &self.schedule {
+            if payment.amount.is_sign_negative() || payment.late_charge_per_day.is_sign_negative() {
+                return Err(format!("installment {} has a negative amount", payment.period));
+            }
+        }
+        for fee in &self.fees {
+            if fee.amount.is_sign_negative() {
+                return Err(format!("fee '{}' is negative", fee.name));
+            }
+        }
+        match &self.profit {
+            Some(Profit::Markup { amount }) if amount.is_sign_negative() => {
+                Err(format!("markup must not be negative, got {}", amount))
+            },
+            Some(Profit::Sharing { financier_ratio }) if *financier_ratio < Decimal::ZERO || *financier_ratio > Decimal::ONE => {
+                Err(format!("profit-sharing ratio must be between 0 and 1, got {}", financier_ratio))
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// Fixed part of what the financier may receive: principal, disclosed markup and actual-cost fees
+    pub fn fixed_limit(&self) -> Decimal {
+        let markup = match &self.profit {
+            Some(Profit::Markup { amount }) => *amount,
+            _ => Decimal::ZERO,
+        };
+        self.fees.iter().filter(|fee| fee.actual_cost).fold(self.principal + markup, |acc, fee| acc + fee.amount)
+    }
+
+    /// The financier's share of `realised` venture profit (none of a loss)
+    pub fn profit_share(&self, realised: Decimal) -> Decimal {
+        match &self.profit {
+            Some(Profit::Sharing { financier_ratio }) if realised > Decimal::ZERO => financier_ratio * realised,
+            _ => Decimal::ZERO,
+        }
+    }
+
+    /// What the borrower repays when installment `i` is `days_late[i]` days late and the venture
+    /// realises `realised` profit
+    pub fn scenario(&self, days_late: &[u64], realised: Decimal) -> Counterexample {
+        let schedule: Vec<ScenarioPayment> = self.schedule.iter().enumerate().map(|(i, payment)| {
+            let days_late = days_late.get(i).copied().unwrap_or(0);
+            ScenarioPayment {
+                period: payment.period,
+                amount: payment.amount,
+                days_late,
+                paid: payment.amount + payment.late_charge_per_day * Decimal::from(days_late),
+            }
+        }).collect();
+        let fees = self.fees.iter().map(|fee| fee.amount).sum();
+        let share = self.profit_share(realised);
+        Counterexample {
+            total_repayment: schedule.iter().map(|p| p.paid).sum::<Decimal>() + fees + share,
+            limit: self.fixed_limit() + share,
+            principal: self.principal,
+            realised_profit: matches!(self.profit, Some(Profit::Sharing { .. })).then_some(realised),
+            schedule,
+            fees,
+        }
+    }
+
+    /// Terms from an intent such as "Verify financing of 1000 repaid in 12 payments of 90 with
+    /// 80 profit" or "... and late fee 5 per day". None when there is no principal or schedule.
+    pub fn parse_intent(text: &str) -> Option<Self> {
+        let words: Vec<String> = text.split_whitespace()
+            .map(|w| w.trim_end_matches([',', '.']).to_lowercase())
+            .collect();
+        let number = |at: usize| words.get(at).and_then(|w| Decimal::from_str(w.trim_start_matches('$')).ok());
+        let after = |word: &str| words.iter().position(|w| w == word);
+
+        let payments = after("payments").or_else(|| after("installments"))?;
+        // The first "of" that doesn't give the installment amount
+        let principal = number(words.iter().enumerate().position(|(at, w)| w == "of" && at != payments + 1)? + 1)?;
+        let count = number(payments.checked_sub(1)?)?.to_string().parse::<u32>().ok()?;
+        let amount = (words.get(payments + 1).map(String::as_str) == Some("of")).then(|| number(payments + 2)).flatten()?;
+        let late_charge_per_day = words.windows(2).position(|w| w[0] == "late" && matches!(w[1].as_str(), "fee" | "charge"))
+            .and_then(|at| number(at + 2))
+            .unwrap_or_default();
+        let profit = words.iter().position(|w| w == "profit" || w == "markup")
+            .and_then(|at| at.checked_sub(1).and_then(number).or_else(|| number(at + 1)))
+            .map(|amount| Profit::Markup { amount });
+
+        Some(Self {
+            principal,
+            schedule: (1..=count).map(|period| Payment { period, amount, late_charge_per_day }).collect(),
+            fees: Vec::new(),
+            profit,
+        })
+    }
+}
+
+/// A repayment scenario; as a Guard rejection, one where the borrower pays more than allowed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Counterexample {
+    pub schedule: Vec<ScenarioPayment>,
+    pub fees: Decimal,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub realised_profit: Option<Decimal>, // Sharing contracts: the venture's profit in this scenario
+    pub principal: Decimal,
+    pub total_repayment: Decimal,
+    pub limit: Decimal, // Principal plus disclosed, permitted profit
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScenarioPayment {
+    pub period: u32,
+    pub amount: Decimal,
+    pub days_late: u64,
+    pub paid: Decimal,
+}
+
+impl Counterexample {
+    pub fn exceeds_limit(&self) -> bool {
+        self.total_repayment > self.limit
+    }
+}
+
+impl fmt::Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "repayment {} exceeds principal {} plus permitted profit ({} in total)",
+            self.total_repayment.normalize(), self.principal.normalize(), self.limit.normalize())?;
+        if let Some(realised) = self.realised_profit {
+            write!(f, " when the venture realises {} profit", realised.normalize())?;
+        }
+        write!(f, ": ")?;
+        for (i, payment) in self.schedule.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            match payment.days_late {
+                0 => write!(f, "#{} pays {}", payment.period, payment.paid.normalize())?,
+                1 => write!(f, "#{} pays {} ({} due, 1 day late)", payment.period, payment.paid.normalize(), payment.amount.normalize())?,
+                days => write!(f, "#{} pays {} ({} due, {} days late)", payment.period, payment.paid.normalize(), payment.amount.normalize(), days)?,
+            }
+        }
+        if !self.fees.is_zero() {
+            write!(f, ", fees {}", self.fees.normalize())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(text: &str) -> Decimal {
+        Decimal::from_str(text).unwrap()
+    }
+
+    fn murabaha() -> FinancialContract {
+        FinancialContract::parse_intent("Verify financing of 1000 repaid in 12 payments of 90 with 80 profit").unwrap()
+    }
+
+    #[test]
+    fn intents_state_principal_schedule_and_profit() {
+        let contract = murabaha();
+        assert_eq!(contract.principal, dec("1000"));
+        assert_eq!(contract.schedule.len(), 12);
+        assert_eq!(contract.schedule[11], Payment { period: 12, amount: dec("90"), late_charge_per_day: Decimal::ZERO });
+        assert_eq!(contract.profit, Some(Profit::Markup { amount: dec("80") }));
+        assert_eq!(contract.fixed_limit(), dec("1080"));
+
+        let late = FinancialContract::parse_intent("Financing of $500, 5 installments of 100, late fee 2.50 per day").unwrap();
+        assert_eq!(late.schedule[0].late_charge_per_day, dec("2.50"));
+        assert_eq!(late.profit, None);
+        assert_eq!(late.scenario(&[3], Decimal::ZERO).total_repayment, dec("507.50"));
+    }
+
+    #[test]
+    fn intents_without_terms_are_not_contracts() {
+        assert_eq!(FinancialContract::parse_intent("Verify financing of 1000"), None);
+        assert_eq!(FinancialContract::parse_intent("12 payments of 90"), None);
+        assert_eq!(FinancialContract::parse_intent("financing of 1000 in 1.5 payments of 90"), None);
+    }
+
+    #[test]
+    fn validation_rejects_terms_the_guard_cannot_reason_about() {
+        assert_eq!(murabaha().validate(), Ok(()));
+        let broken = |change: fn(&mut FinancialContract)| {
+            let mut contract = murabaha();
+            change(&mut contract);
+            contract.validate()
+        };
+        assert!(broken(|c| c.principal = Decimal::ZERO).is_err());
+        assert!(broken(|c| c.schedule.clear()).is_err());
+        assert!(broken(|c| c.schedule[0].late_charge_per_day = dec("-1")).is_err());
+        assert!(broken(|c| c.fees.push(Fee { name: "stamp".into(), amount: dec("-5"), actual_cost: true })).is_err());
+        assert!(broken(|c| c.profit = Some(Profit::Markup { amount: dec("-1") })).is_err());
+        assert_eq!(
+            broken(|c| c.profit = Some(Profit::Sharing { financier_ratio: dec("1.2") })),
+            Err("profit-sharing ratio must be between 0 and 1, got 1.2".to_string())
+        );
+    }
+
+    #[test]
+    fn blobs_may_be_empty() {
+        assert_eq!(FinancialContract::from_blob(b"  \n"), Ok(None));
+        assert_eq!(FinancialContract::from_blob(br#"{"principal": "1000", "schedule": []}"#).unwrap().unwrap().principal, dec("1000"));
+        assert!(FinancialContract::from_blob(b"0x01").is_err());
+    }
+}
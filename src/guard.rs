@@ -1,35 +1,352 @@
-use z3::{Solver, SatResult};
+use z3::{Optimize, Solver, SatResult};
 use anyhow::Result;
+use ipnet::IpNet;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use z3::ast::{Bool, Int, Real};
+use crate::finance::{Counterexample, FinancialContract, Profit};
+use crate::predicate::{CompareOp, Predicate};
+use rust_decimal::Decimal;
 
-pub struct AetherGuard;
+/// Where sovereign (sensitivity >= 2) data may be fetched from. A host under an allowed domain
+/// must also resolve into `cidrs` when it connects, unless its domain is trusted by name only.
+/// Overridable with `AETHER_SOVEREIGN_SUFFIXES`, `AETHER_SOVEREIGN_CIDRS` and `AETHER_NAME_ONLY_SUFFIXES`
+/// (comma separated).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SovereigntyPolicy {
+    pub domain_suffixes: Vec<String>,   // "my" allows "bank.my"; "localhost" allows "localhost"
+    pub cidrs: Vec<IpNet>,              // Allowed literal and resolved addresses
+    pub name_only_suffixes: Vec<String>, // Opt-in: domains trusted by name, wherever they resolve
+}
+
+impl Default for SovereigntyPolicy {
+    fn default() -> Self {
+        Self {
+            domain_suffixes: vec!["localhost".to_string(), "my".to_string()],
+            cidrs: vec!["127.0.0.0/8".parse().unwrap(), "::1/128".parse().unwrap()],
+            name_only_suffixes: vec![],
+        }
+    }
+}
+
+impl SovereigntyPolicy {
+    pub fn from_env() -> Self {
+        let mut policy = Self::default();
+        let list = |key: &str| std::env::var(key).ok().map(|raw| {
+            raw.split(',').map(|item| item.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>()
+        });
+        if let Some(suffixes) = list("AETHER_SOVEREIGN_SUFFIXES") {
+            policy.domain_suffixes = suffixes;
+        }
+        if let Some(cidrs) = list("AETHER_SOVEREIGN_CIDRS") {
+            policy.cidrs = cidrs.iter().filter_map(|c| c.parse().ok()).collect();
+        }
+        if let Some(name_only) = list("AETHER_NAME_ONLY_SUFFIXES") {
+            policy.name_only_suffixes = name_only;
+        }
+        policy
+    }
+
+    /// The allowlisted suffix `host` falls under, matched on whole labels
+    fn matching_suffix(&self, host: &str) -> Option<&str> {
+        self.domain_suffixes.iter()
+            .find(|suffix| host == suffix.as_str() || host.ends_with(&format!(".{}", suffix)))
+            .map(String::as_str)
+    }
+
+    fn allows_ip(&self, ip: &IpAddr) -> bool {
+        self.cidrs.iter().any(|net| net.contains(ip))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    Contradiction, // The filter can never match: the pipeline always returns no rows
+    Redundant,     // The filter always matches: it removes nothing
+}
+
+/// A build-time finding that doesn't stop the build
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub nodes: Vec<String>, // Manifest node names involved, the reported node last
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SovereigntyStage {
+    Persist,  // Build time: the contract's endpoint
+    Connect,  // Run time: the endpoint and its resolved addresses
+    Redirect, // Run time: each redirect target
+}
+
+/// One sovereignty check, as recorded in the audit trail
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SovereigntyDecision {
+    pub endpoint: String,
+    pub host: Option<String>,
+    pub sensitivity: u8,
+    pub stage: SovereigntyStage,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resolved: Vec<IpAddr>,
+    pub allowed: bool,
+    pub reason: String,
+    pub at_us: u64, // Unix epoch, microseconds
+}
+
+/// What a law sees of the atom being persisted
+pub struct LawContext<'a> {
+    pub atom: &'a crate::LogicAtom,
+    pub blob: &'a [u8], // The atom's config, already loaded
+    pub inputs: &'a [crate::LogicAtom],
+    pub guard: &'a AetherGuard,
+    pub vault: &'a crate::AetherVault,
+}
+
+/// A named, versioned rule evaluated by `persist_verified` on every atom it applies to
+pub trait Law: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn version(&self) -> u32;
+    fn description(&self) -> &'static str;
+    fn applies_to(&self, op_code: u16) -> bool;
+    /// The reason the atom is rejected, if it is
+    fn verify(&self, ctx: &LawContext) -> Result<(), String>;
+}
+
+/// Law (v1): no interest on financial ops (Op 100), read as a rate from the blob's first 4 bytes.
+/// Superseded by `NoRibaV2`; kept for manifests pinning `no_riba@1`, which reject JSON contracts.
+pub struct NoRiba;
+
+impl Law for NoRiba {
+    fn name(&self) -> &'static str { "no_riba" }
+    fn version(&self) -> u32 { 1 }
+    fn description(&self) -> &'static str { "Financial ops (Op 100) must carry a 0% interest rate" }
+    fn applies_to(&self, op_code: u16) -> bool { op_code == 100 }
+
+    fn verify(&self, ctx: &LawContext) -> Result<(), String> {
+        // A JSON contract's first bytes are text, not a rate: v1 can't judge it
+        if ctx.blob.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'{') {
+            return Err("no_riba@1 reads a binary rate and cannot check JSON financial contracts; pin no_riba@2".to_string());
+        }
+        let rate = extract_rate(ctx.blob);
+        match ctx.guard.verify_interest_free(rate) {
+            true => Ok(()),
+            false => Err(format!("Riba Detected (rate {})", rate)),
+        }
+    }
+}
+
+/// Law (v2): a financial op's contract (see finance.rs) never repays more than principal plus
+/// disclosed, permitted profit, however late it is paid. An op without terms is a plain audit node.
+pub struct NoRibaV2;
+
+impl Law for NoRibaV2 {
+    fn name(&self) -> &'static str { "no_riba" }
+    fn version(&self) -> u32 { 2 }
+    fn description(&self) -> &'static str { "Financial contracts (Op 100) must repay at most principal plus disclosed profit, with no time-based increments" }
+    fn applies_to(&self, op_code: u16) -> bool { op_code == 100 }
+
+    fn verify(&self, ctx: &LawContext) -> Result<(), String> {
+        let Some(contract) = FinancialContract::from_blob(ctx.blob)? else {
+            return Ok(());
+        };
+        contract.validate().map_err(|e| format!("Invalid financial contract: {}", e))?;
+        match ctx.guard.find_riba(&contract)? {
+            None => Ok(()),
+            Some(counterexample) => Err(format!("Riba Detected: {}", counterexample)),
+        }
+    }
+}
+
+/// Law: sovereign IO (Op 500) only reaches allowlisted hosts; every decision is audited
+pub struct DataSovereignty;
+
+impl Law for DataSovereignty {
+    fn name(&self) -> &'static str { "data_sovereignty" }
+    fn version(&self) -> u32 { 1 }
+    fn description(&self) -> &'static str { "Sovereign data (sensitivity >= 2) must stay on allowlisted domains and address ranges" }
+    fn applies_to(&self, op_code: u16) -> bool { op_code == 500 }
+
+    fn verify(&self, ctx: &LawContext) -> Result<(), String> {
+        let contract: crate::IOContract = serde_json::from_slice(ctx.blob)
+            .map_err(|e| format!("Invalid IO Contract data: {}", e))?;
+        let decision = ctx.guard.decide_sovereignty(&contract.endpoint, contract.sensitivity, &[], SovereigntyStage::Persist);
+        ctx.vault.record_decision(&decision).map_err(|e| e.to_string())?;
+        match decision.allowed {
+            true => Ok(()),
+            false => Err(decision.reason),
+        }
+    }
+}
+
+/// Law: zakat (Op 101) is due at a prescribed rate, on wealth above a positive nisab
+pub struct ZakatRules;
+
+impl Law for ZakatRules {
+    fn name(&self) -> &'static str { "zakat" }
+    fn version(&self) -> u32 { 1 }
+    fn description(&self) -> &'static str { "Zakat (Op 101) must use a prescribed rate and a positive nisab" }
+    fn applies_to(&self, op_code: u16) -> bool { op_code == 101 }
+
+    fn verify(&self, ctx: &LawContext) -> Result<(), String> {
+        let config: crate::zakat::ZakatConfig = serde_json::from_slice(ctx.blob)
+            .map_err(|e| format!("Invalid Zakat config: {}", e))?;
+        if !crate::zakat::allowed_rates().contains(&config.rate.normalize()) {
+            return Err(format!("Zakat rate {} is not a prescribed rate (0.025, 0.05, 0.1, 0.2)", config.rate));
+        }
+        match &config.nisab {
+            crate::zakat::Nisab::Fixed { fixed } if !fixed.is_sign_positive() || fixed.is_zero() => {
+                Err(format!("Zakat nisab must be positive, got {}", fixed))
+            },
+            crate::zakat::Nisab::Metal { grams: Some(grams), .. } if !grams.is_sign_positive() || grams.is_zero() => {
+                Err(format!("Zakat nisab weight must be positive, got {}g", grams))
+            },
+            crate::zakat::Nisab::Metal { price: Some(price), .. } if !price.is_sign_positive() || price.is_zero() => {
+                Err(format!("Zakat gold/silver price must be positive, got {}", price))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Every law the Guard knows, by name and version
+#[derive(Clone)]
+pub struct LawRegistry {
+    laws: Vec<Arc<dyn Law>>,
+}
+
+impl Default for LawRegistry {
+    fn default() -> Self {
+        Self { laws: vec![Arc::new(NoRiba), Arc::new(NoRibaV2), Arc::new(DataSovereignty), Arc::new(ZakatRules)] }
+    }
+}
+
+impl LawRegistry {
+    pub fn register(&mut self, law: Arc<dyn Law>) {
+        self.laws.push(law);
+    }
+
+    /// Newest version of each law
+    pub fn latest(&self) -> Vec<Arc<dyn Law>> {
+        let mut latest: Vec<Arc<dyn Law>> = Vec::new();
+        for law in &self.laws {
+            match latest.iter_mut().find(|known| known.name() == law.name()) {
+                Some(known) if known.version() < law.version() => *known = Arc::clone(law),
+                Some(_) => {},
+                None => latest.push(Arc::clone(law)),
+            }
+        }
+        latest
+    }
+
+    pub fn all(&self) -> &[Arc<dyn Law>] {
+        &self.laws
+    }
+
+    /// A manifest's law: "no_riba" (newest version) or "no_riba@1" (pinned)
+    pub fn resolve(&self, spec: &str) -> Result<Arc<dyn Law>> {
+        let (name, version) = match spec.trim().split_once('@') {
+            Some((name, version)) => {
+                let version = version.trim_start_matches('v').parse::<u32>()
+                    .map_err(|_| anyhow::anyhow!("Law '{}' has an invalid version", spec))?;
+                (name, Some(version))
+            },
+            None => (spec.trim(), None),
+        };
+        let found = match version {
+            Some(version) => self.laws.iter().find(|law| law.name() == name && law.version() == version).cloned(),
+            None => self.latest().into_iter().find(|law| law.name() == name),
+        };
+        found.ok_or_else(|| {
+            let known: Vec<String> = self.laws.iter().map(|law| format!("{}@{}", law.name(), law.version())).collect();
+            anyhow::anyhow!("Unknown law '{}' (known: {})", spec, known.join(", "))
+        })
+    }
+}
+
+#[derive(Clone)]
+pub struct AetherGuard {
+    pub sovereignty: SovereigntyPolicy,
+    pub registry: LawRegistry,
+    laws: Vec<Arc<dyn Law>>, // Evaluated by `persist_verified`
+}
 
 impl AetherGuard {
     pub fn new() -> Self {
-        Self
+        Self::with_policy(SovereigntyPolicy::from_env())
+    }
+
+    /// Enforces the newest version of every registered law
+    pub fn with_policy(sovereignty: SovereigntyPolicy) -> Self {
+        let registry = LawRegistry::default();
+        let laws = registry.latest();
+        Self { sovereignty, registry, laws }
+    }
+
+    /// The same guard enforcing exactly the given laws (see `LawRegistry::resolve`)
+    pub fn with_laws(&self, specs: &[String]) -> Result<Self> {
+        let laws = specs.iter().map(|spec| self.registry.resolve(spec)).collect::<Result<Vec<_>>>()?;
+        Ok(Self { laws, ..self.clone() })
+    }
+
+    pub fn laws(&self) -> &[Arc<dyn Law>] {
+        &self.laws
     }
 
     pub fn verify_compatibility(&self, atom: &crate::LogicAtom, input_atoms: &[crate::LogicAtom]) -> Result<()> {
-        // Static Analysis of OpCode Connections
+        // Parameters (Op 20) are bound at execution time and never count as data inputs
+        let input_atoms: Vec<crate::LogicAtom> = input_atoms.iter()
+            .filter(|input| input.op_code != crate::OP_PARAM)
+            .cloned()
+            .collect();
+
+        // Static Analysis of OpCode Connections: arity here, input types from `types::input_kind`
+        let name = match atom.op_code {
+            2 => "Filter", 3 => "Merge", 4 => "Sort", 5 => "Limit", 6 => "Project", 7 => "Aggregate",
+            8 => "Join", 9 => "Compute",
+            _ => return Ok(()),
+        };
         match atom.op_code {
-            2 => { // FILTER
-                if input_atoms.is_empty() {
-                     return Err(anyhow::anyhow!("Filter (Op 2) requires at least one input (Source List)"));
-                }
-                // Verify Input 0 is compatible (e.g., IO or Audit or Store, not ADD)
-                // Assuming Op 500 (IO) produces List. Op 1 (ADD) produces Int.
-                if input_atoms[0].op_code == 1 {
-                     return Err(anyhow::anyhow!("Type Mismatch: Filter cannot consume integer output of ADD (Op 1)"));
-                }
+            2 if input_atoms.is_empty() => {
+                return Err(anyhow::anyhow!("Filter (Op 2) requires at least one input (Source List)"));
             },
-            1 => { // ADD
-                 // Needs no atoms (uses raw data) or atoms that produce bytes?
-                 // My ADD legacy implementation uses Raw Data.
+            4..=7 if input_atoms.len() != 1 => {
+                return Err(anyhow::anyhow!("{} (Op {}) requires exactly one input (Source List), got {}", name, atom.op_code, input_atoms.len()));
+            },
+            8 if input_atoms.len() != 2 => {
+                return Err(anyhow::anyhow!("Join (Op 8) requires exactly two inputs (Left List, Right List), got {}", input_atoms.len()));
             },
             _ => {}
         }
+        for (index, input) in input_atoms.iter().enumerate() {
+            // COMPUTE takes scalars too, but never a trigger config or an envelope
+            let wants_list = crate::types::input_kind(atom.op_code, index) != crate::types::InputKind::Any
+                || (atom.op_code == 9 && matches!(input.op_code, 50 | 800));
+            if wants_list {
+                Self::require_list(name, input)?;
+            }
+        }
         Ok(())
     }
 
+    /// Rejects inputs whose opcode is known not to produce a list
+    fn require_list(consumer: &str, input: &crate::LogicAtom) -> Result<()> {
+        let produced = match input.op_code {
+            1 => "integer output of ADD (Op 1)",
+            // A trigger with inputs passes its data through
+            50 if input.inputs.is_empty() => "trigger config of REACTIVE_TRIGGER (Op 50)",
+            800 => "envelope of GATEWAY (Op 800)",
+            _ => return Ok(()),
+        };
+        Err(anyhow::anyhow!("Type Mismatch: {} cannot consume {}", consumer, produced))
+    }
+
     pub fn check(&self, atom: &crate::LogicAtom) -> Result<()> {
         // Existing checks
         Ok(())
@@ -51,11 +368,449 @@ impl AetherGuard {
         solver.check() == SatResult::Sat
     }
 
+    /// Looks for a way the borrower ends up repaying more than principal plus disclosed,
+    /// permitted profit: any installment paid any number of days late, any realised venture
+    /// profit. None when Z3 proves there is none; otherwise the scenario with the fewest days late.
+    pub fn find_riba(&self, contract: &FinancialContract) -> Result<Option<Counterexample>, String> {
+        let optimize = Optimize::new();
+        let zero = Real::from_rational(0, 1);
+        let mut repayment = decimal(contract.fees.iter().map(|fee| fee.amount).sum())?;
+        let mut days_late = Vec::new();
+        for (i, payment) in contract.schedule.iter().enumerate() {
+            let days = Int::new_const(format!("days_late_{}", i));
+            optimize.assert(&days.ge(Int::from_i64(0)));
+            repayment = repayment + decimal(payment.amount)? + decimal(payment.late_charge_per_day)? * Real::from_int(&days);
+            days_late.push(days);
+        }
+        let mut limit = decimal(contract.fixed_limit())?;
+        let realised = Real::new_const("realised_profit");
+        if let Some(Profit::Sharing { financier_ratio }) = &contract.profit {
+            let share = decimal(*financier_ratio)? * realised.gt(&zero).ite(&realised, &zero);
+            repayment += &share;
+            limit += share;
+        }
+        optimize.assert(&repayment.gt(&limit));
+        optimize.minimize(&Int::add(&days_late));
+
+        match optimize.check(&[]) {
+            SatResult::Unsat => Ok(None),
+            SatResult::Unknown => Err(format!("Riba check inconclusive: {}", optimize.get_reason_unknown().unwrap_or_default())),
+            SatResult::Sat => {
+                let model = optimize.get_model().ok_or("Riba check produced no model")?;
+                let days: Vec<u64> = days_late.iter()
+                    .map(|days| model.eval(days, true).and_then(|v| v.as_u64()).unwrap_or(0))
+                    .collect();
+                let realised = model.eval(&realised, true)
+                    .and_then(|v| v.as_rational())
+                    .map(|(numerator, denominator)| Decimal::from(numerator) / Decimal::from(denominator))
+                    .unwrap_or_default();
+                Ok(Some(contract.scenario(&days, realised)))
+            },
+        }
+    }
+
+    /// Checks the last FILTER of a chain (upstream first, with node names) against the filters
+    /// before it: one that can never match, or that every arriving row already satisfies.
+    /// Problems further up are reported when their own node is checked.
+    pub fn check_filter_chain(&self, chain: &[(String, Predicate)]) -> Option<Diagnostic> {
+        let ((name, predicate), upstream) = chain.split_last()?;
+        let solver = Solver::new();
+        let mut encoder = FilterEncoder::default();
+        let tracked: Vec<(Bool, &String)> = upstream.iter().enumerate().map(|(i, (upstream_name, upstream_predicate))| {
+            let track = Bool::new_const(format!("filter_{}", i));
+            solver.assert_and_track(encoder.encode(upstream_predicate), &track);
+            (track, upstream_name)
+        }).collect();
+        if solver.check() != SatResult::Sat {
+            return None;
+        }
+        let culprits = |solver: &Solver| -> Vec<String> {
+            let core = solver.get_unsat_core();
+            tracked.iter().filter(|(track, _)| core.contains(track)).map(|(_, name)| (*name).clone()).collect()
+        };
+        let condition = encoder.encode(predicate);
+        let track = Bool::new_const("filter_checked");
+        // And/Or already print in parentheses
+        let shown = match predicate {
+            Predicate::And { .. } | Predicate::Or { .. } => predicate.to_string(),
+            _ => format!("({})", predicate),
+        };
+
+        solver.push();
+        solver.assert_and_track(condition.clone(), &track);
+        if solver.check() == SatResult::Unsat {
+            let before = culprits(&solver);
+            let message = match before.is_empty() {
+                true => format!("Filter '{}' {} can never match", name, shown),
+                false => format!("Filter '{}' {} can never match after {}: the pipeline always returns no rows", name, shown, quoted(&before)),
+            };
+            return Some(Diagnostic { kind: DiagnosticKind::Contradiction, nodes: before.into_iter().chain([name.clone()]).collect(), message });
+        }
+        solver.pop(1);
+
+        solver.push();
+        solver.assert_and_track(condition.not(), &track);
+        if solver.check() == SatResult::Unsat {
+            let before = culprits(&solver);
+            let message = match before.is_empty() {
+                true => format!("Filter '{}' {} matches every row", name, shown),
+                false => format!("Filter '{}' {} is redundant: every row reaching it already passed {}", name, shown, quoted(&before)),
+            };
+            return Some(Diagnostic { kind: DiagnosticKind::Redundant, nodes: before.into_iter().chain([name.clone()]).collect(), message });
+        }
+        solver.pop(1);
+        None
+    }
+
     pub fn verify_sovereignty(&self, endpoint: &str, sensitivity: u8) -> bool {
-        if sensitivity >= 2 {
-            // Law: Sovereign data MUST remain on localhost or .my domains
-            return endpoint.contains("localhost") || endpoint.contains("127.0.0.1") || endpoint.contains(".my");
+        self.decide_sovereignty(endpoint, sensitivity, &[], SovereigntyStage::Persist).allowed
+    }
+
+    /// Law: Sovereign data MUST remain on allowlisted domains and address ranges.
+    /// `resolved` are the addresses the host resolved to; empty checks the name only, as at
+    /// persist time, and every resolved address must be allowed when the host connects.
+    pub fn decide_sovereignty(&self, endpoint: &str, sensitivity: u8, resolved: &[IpAddr], stage: SovereigntyStage) -> SovereigntyDecision {
+        let mut decision = SovereigntyDecision {
+            endpoint: endpoint.to_string(),
+            host: None,
+            sensitivity,
+            stage,
+            resolved: resolved.to_vec(),
+            allowed: false,
+            reason: String::new(),
+            at_us: crate::runs::unix_micros(),
+        };
+        if sensitivity < 2 {
+            decision.allowed = true;
+            decision.reason = "not sovereign data".to_string();
+            return decision;
+        }
+        if crate::source::is_local(endpoint) {
+            decision.allowed = true;
+            decision.reason = "local file source".to_string();
+            return decision;
+        }
+
+        let host = match url::Url::parse(endpoint) {
+            Ok(url) => url.host().map(|h| h.to_owned()),
+            Err(e) => {
+                decision.reason = format!("endpoint is not a valid URL: {}", e);
+                return decision;
+            }
+        };
+        let Some(host) = host else {
+            decision.reason = "endpoint has no host".to_string();
+            return decision;
+        };
+        decision.host = Some(host.to_string());
+
+        let policy = &self.sovereignty;
+        let (allowed, reason) = match host {
+            url::Host::Ipv4(ip) => Self::ip_decision(policy, &IpAddr::V4(ip)),
+            url::Host::Ipv6(ip) => Self::ip_decision(policy, &IpAddr::V6(ip)),
+            url::Host::Domain(name) => {
+                let name = name.trim_end_matches('.').to_ascii_lowercase();
+                match policy.matching_suffix(&name) {
+                    None => (false, format!("host '{}' is not under an allowed domain {:?}", name, policy.domain_suffixes)),
+                    Some(suffix) if policy.name_only_suffixes.iter().any(|p| p == suffix) => {
+                        (true, format!("host '{}' matches name-only domain '{}'", name, suffix))
+                    },
+                    Some(suffix) if resolved.is_empty() => {
+                        (true, format!("host '{}' matches allowed domain '{}' (addresses checked on connect)", name, suffix))
+                    },
+                    Some(suffix) => match resolved.iter().find(|ip| !policy.allows_ip(ip)) {
+                        Some(ip) => (false, format!("host '{}' resolved to {}, outside the allowed ranges", name, ip)),
+                        None => (true, format!("host '{}' matches allowed domain '{}' and resolved into the allowed ranges", name, suffix)),
+                    },
+                }
+            },
+        };
+        decision.allowed = allowed;
+        decision.reason = reason;
+        decision
+    }
+
+    fn ip_decision(policy: &SovereigntyPolicy, ip: &IpAddr) -> (bool, String) {
+        if policy.allows_ip(ip) {
+            (true, format!("address {} is in an allowed range", ip))
+        } else {
+            (false, format!("address {} is outside the allowed ranges", ip))
+        }
+    }
+}
+
+fn quoted(names: &[String]) -> String {
+    names.iter().map(|name| format!("'{}'", name)).collect::<Vec<_>>().join(", ")
+}
+
+/// Z3 variables describing one record field
+struct FieldVars {
+    present: Bool,  // Not missing or null
+    numeric: Bool,  // Reads as a number (numeric strings included)
+    number: Real,
+    text: Int, // Which string constant it equals (when not numeric)
+}
+
+/// Translates FILTER predicates into Z3 constraints over the record's fields, mirroring
+/// `Matcher` (missing fields fail comparisons, numeric strings compare as numbers).
+/// What can't be modelled exactly (regexes, `contains`, text ordering, unbound `{{inputs}}`)
+/// becomes a free variable per distinct clause, so "unsat" is still a proof.
+#[derive(Default)]
+struct FilterEncoder {
+    fields: HashMap<String, FieldVars>,
+    texts: HashMap<String, i64>,
+    opaque: HashMap<String, Bool>,
+}
+
+impl FilterEncoder {
+    fn encode(&mut self, predicate: &Predicate) -> Bool {
+        match predicate {
+            Predicate::And { and } => Bool::and(&and.iter().map(|p| self.encode(p)).collect::<Vec<_>>()),
+            Predicate::Or { or } => Bool::or(&or.iter().map(|p| self.encode(p)).collect::<Vec<_>>()),
+            Predicate::Not { not } => self.encode(not).not(),
+            Predicate::Compare { field, op, val } => {
+                let present = self.field(field).present.clone();
+                let clause = predicate.to_string();
+                match op {
+                    CompareOp::IsNull => present.not(),
+                    CompareOp::IsNotNull => present,
+                    CompareOp::Eq => self.equals(field, val, &clause),
+                    CompareOp::Ne => self.equals(field, val, &clause).not(),
+                    CompareOp::In | CompareOp::NotIn => {
+                        let any = match val.as_array() {
+                            Some(items) => Bool::or(&items.iter().map(|item| self.equals(field, item, &format!("{} == {}", field, item))).collect::<Vec<_>>()),
+                            None => Bool::and(&[present, self.opaque(&clause)]),
+                        };
+                        if *op == CompareOp::In { any } else { any.not() }
+                    },
+                    CompareOp::Gt | CompareOp::Lt | CompareOp::Ge | CompareOp::Le => match rational(val) {
+                        Some(bound) => {
+                            let vars = self.field(field);
+                            let holds = match op {
+                                CompareOp::Gt => vars.number.gt(&bound),
+                                CompareOp::Lt => vars.number.lt(&bound),
+                                CompareOp::Ge => vars.number.ge(&bound),
+                                _ => vars.number.le(&bound),
+                            };
+                            Bool::and(&[vars.present.clone(), vars.numeric.clone(), holds])
+                        },
+                        // Text ordering (e.g. ISO dates)
+                        None => Bool::and(&[present, self.opaque(&clause)]),
+                    },
+                    CompareOp::Contains | CompareOp::Matches => Bool::and(&[present, self.opaque(&clause)]),
+                    CompareOp::NotContains => {
+                        let contains = Predicate::Compare { field: field.clone(), op: CompareOp::Contains, val: val.clone() }.to_string();
+                        Bool::and(&[present, self.opaque(&contains)]).not()
+                    },
+                }
+            },
         }
-        true
+    }
+
+    /// `field == val` as `loose_eq` decides it
+    fn equals(&mut self, field: &str, val: &serde_json::Value, clause: &str) -> Bool {
+        let next_text = self.texts.len() as i64;
+        match val {
+            serde_json::Value::Number(_) => match rational(val) {
+                Some(number) => {
+                    let vars = self.field(field);
+                    Bool::and(&[vars.present.clone(), vars.numeric.clone(), vars.number.eq(&number)])
+                },
+                None => Bool::and(&[self.field(field).present.clone(), self.opaque(clause)]),
+            },
+            // Plain text; numeric, boolean-like and placeholder strings compare loosely
+            serde_json::Value::String(text) if crate::predicate::as_number(val).is_none()
+                && !text.eq_ignore_ascii_case("true") && !text.eq_ignore_ascii_case("false") && !text.contains("{{") => {
+                let id = *self.texts.entry(text.clone()).or_insert(next_text);
+                let vars = self.field(field);
+                Bool::and(&[vars.present.clone(), vars.numeric.not(), vars.text.eq(Int::from_i64(id))])
+            },
+            _ => Bool::and(&[self.field(field).present.clone(), self.opaque(clause)]),
+        }
+    }
+
+    fn field(&mut self, name: &str) -> &FieldVars {
+        let index = self.fields.len();
+        self.fields.entry(name.to_string()).or_insert_with(|| FieldVars {
+            present: Bool::new_const(format!("field_{}_present", index)),
+            numeric: Bool::new_const(format!("field_{}_numeric", index)),
+            number: Real::new_const(format!("field_{}_number", index)),
+            text: Int::new_const(format!("field_{}_text", index)),
+        })
+    }
+
+    fn opaque(&mut self, clause: &str) -> Bool {
+        let index = self.opaque.len();
+        self.opaque.entry(clause.to_string())
+            .or_insert_with(|| Bool::new_const(format!("clause_{}", index)))
+            .clone()
+    }
+}
+
+/// Exact value of a JSON number as a Z3 rational (None for placeholders or huge/exponent forms)
+fn rational(val: &serde_json::Value) -> Option<Real> {
+    let serde_json::Value::Number(number) = val else {
+        return None;
+    };
+    let text = number.to_string();
+    let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+    if fraction.len() > 15 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let numerator = format!("{}{}", whole, fraction).parse::<i64>().ok()?;
+    Some(Real::from_rational(numerator, 10i64.checked_pow(fraction.len() as u32)?))
+}
+
+/// Exact Z3 rational for a decimal amount
+fn decimal(value: Decimal) -> Result<Real, String> {
+    let denominator = 10i128.checked_pow(value.scale()).ok_or("amount has too many decimal places")?;
+    Real::from_rational_str(&value.mantissa().to_string(), &denominator.to_string())
+        .ok_or_else(|| format!("{} is not a Z3 number", value))
+}
+
+fn extract_rate(data: &[u8]) -> i32 {
+    if data.len() < 4 { return 0; }
+    let mut arr = [0u8; 4];
+    arr.copy_from_slice(&data[0..4]);
+    i32::from_le_bytes(arr)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::finance::{Fee, Payment};
+    use serde_json::json;
+
+    fn decide(endpoint: &str, resolved: &[&str], stage: SovereigntyStage) -> SovereigntyDecision {
+        let resolved: Vec<IpAddr> = resolved.iter().map(|ip| ip.parse().unwrap()).collect();
+        AetherGuard::with_policy(SovereigntyPolicy::default()).decide_sovereignty(endpoint, 2, &resolved, stage)
+    }
+
+    #[test]
+    fn sovereign_names_are_checked_on_whole_labels() {
+        assert!(decide("https://bank.my/api", &[], SovereigntyStage::Persist).allowed);
+        assert!(!decide("https://bank.my.evil.com/api", &[], SovereigntyStage::Persist).allowed);
+        assert!(!decide("https://notmy/api", &[], SovereigntyStage::Persist).allowed);
+        assert!(decide("http://127.0.0.1:8080/", &[], SovereigntyStage::Persist).allowed);
+        assert!(!decide("http://8.8.8.8/", &[], SovereigntyStage::Persist).allowed);
+    }
+
+    #[test]
+    fn allowed_domains_must_resolve_into_allowed_ranges() {
+        let foreign = decide("https://bank.my/api", &["127.0.0.1", "203.0.113.7"], SovereigntyStage::Connect);
+        assert!(!foreign.allowed);
+        assert!(foreign.reason.contains("203.0.113.7"));
+        assert!(!decide("https://bank.my/next", &["203.0.113.7"], SovereigntyStage::Redirect).allowed);
+        assert!(decide("https://bank.my/api", &["127.0.0.1"], SovereigntyStage::Connect).allowed);
+    }
+
+    #[test]
+    fn name_only_domains_are_an_explicit_exception() {
+        let policy = SovereigntyPolicy { name_only_suffixes: vec!["my".to_string()], ..Default::default() };
+        let decision = AetherGuard::with_policy(policy)
+            .decide_sovereignty("https://bank.my/api", 2, &["203.0.113.7".parse().unwrap()], SovereigntyStage::Connect);
+        assert!(decision.allowed);
+    }
+
+    fn verify_law(law: &dyn Law, op_code: u16, blob: &[u8]) -> Result<(), String> {
+        let atom = crate::LogicAtom { op_code, inputs: vec![], storage_ref: String::new(), context_id: "global".to_string() };
+        let (guard, vault) = (AetherGuard::new(), crate::AetherVault::temporary());
+        law.verify(&LawContext { atom: &atom, blob, inputs: &[], guard: &guard, vault: &vault })
+    }
+
+    #[test]
+    fn bare_law_names_resolve_to_the_newest_version() {
+        let registry = LawRegistry::default();
+        assert_eq!(registry.resolve("no_riba").unwrap().version(), 2);
+        assert_eq!(registry.resolve("no_riba@1").unwrap().version(), 1);
+        assert_eq!(registry.resolve("zakat").unwrap().name(), "zakat");
+        assert!(registry.resolve("no_riba@3").is_err());
+    }
+
+    #[test]
+    fn no_riba_v1_rejects_json_contracts() {
+        assert!(verify_law(&NoRiba, 100, br#"{"principal": "1000"}"#).unwrap_err().contains("no_riba@2"));
+        assert_eq!(verify_law(&NoRiba, 100, &0i32.to_le_bytes()), Ok(()));
+        assert!(verify_law(&NoRiba, 100, &5i32.to_le_bytes()).is_err());
+    }
+
+    #[test]
+    fn zakat_law_checks_rate_and_nisab() {
+        assert_eq!(verify_law(&ZakatRules, 101, br#"{"balance": "5000", "nisab": {"fixed": "4000"}}"#), Ok(()));
+        assert!(verify_law(&ZakatRules, 101, br#"{"balance": "5000", "rate": "0.03", "nisab": {"fixed": "4000"}}"#).is_err());
+        assert!(verify_law(&ZakatRules, 101, br#"{"balance": "5000", "nisab": {"fixed": "0"}}"#).is_err());
+        assert!(verify_law(&ZakatRules, 101, br#"{"nisab": {"metal": "gold", "price": "-1"}}"#).is_err());
+    }
+
+    fn filter(name: &str, field: &str, op: CompareOp, val: serde_json::Value) -> (String, Predicate) {
+        (name.to_string(), Predicate::Compare { field: field.to_string(), op, val })
+    }
+
+    #[test]
+    fn contradictory_filter_names_its_cause() {
+        let chain = [
+            filter("cheap", "price", CompareOp::Lt, json!(100)),
+            filter("new", "built", CompareOp::Gt, json!(2020)),
+            filter("pricey", "price", CompareOp::Gt, json!(500)),
+        ];
+        let diagnostic = AetherGuard::new().check_filter_chain(&chain).unwrap();
+        assert_eq!(diagnostic.kind, DiagnosticKind::Contradiction);
+        assert!(diagnostic.nodes.contains(&"cheap".to_string()));
+        assert_eq!(diagnostic.nodes.last().map(String::as_str), Some("pricey"));
+    }
+
+    #[test]
+    fn redundant_filter_is_reported() {
+        let chain = [
+            filter("lrt", "type", CompareOp::Eq, json!("LRT")),
+            filter("rail", "type", CompareOp::In, json!(["LRT", "MRT"])),
+        ];
+        let diagnostic = AetherGuard::new().check_filter_chain(&chain).unwrap();
+        assert_eq!(diagnostic.kind, DiagnosticKind::Redundant);
+        assert_eq!(diagnostic.nodes, vec!["lrt".to_string(), "rail".to_string()]);
+    }
+
+    #[test]
+    fn independent_filters_pass() {
+        let chain = [
+            filter("cheap", "price", CompareOp::Lt, json!(100)),
+            filter("affordable", "price", CompareOp::Gt, json!(50)),
+        ];
+        assert_eq!(AetherGuard::new().check_filter_chain(&chain), None);
+        assert_eq!(AetherGuard::new().check_filter_chain(&[]), None);
+    }
+
+    fn contract(late_charge_per_day: i64, profit: Option<Profit>) -> FinancialContract {
+        FinancialContract {
+            principal: Decimal::from(1000),
+            schedule: (1..=2).map(|period| Payment {
+                period,
+                amount: Decimal::from(540),
+                late_charge_per_day: Decimal::from(late_charge_per_day),
+            }).collect(),
+            fees: vec![Fee { name: "stamp duty".into(), amount: Decimal::from(10), actual_cost: true }],
+            profit,
+        }
+    }
+
+    #[test]
+    fn disclosed_markup_is_not_riba() {
+        let murabaha = contract(0, Some(Profit::Markup { amount: Decimal::from(80) }));
+        assert_eq!(AetherGuard::new().find_riba(&murabaha), Ok(None));
+    }
+
+    #[test]
+    fn undisclosed_excess_is_riba_even_on_time() {
+        let counterexample = AetherGuard::new().find_riba(&contract(0, None)).unwrap().unwrap();
+        assert!(counterexample.exceeds_limit());
+        assert!(counterexample.schedule.iter().all(|p| p.days_late == 0));
+    }
+
+    #[test]
+    fn late_charges_are_riba_with_the_smallest_delay() {
+        let generous = contract(5, Some(Profit::Markup { amount: Decimal::from(82) }));
+        let counterexample = AetherGuard::new().find_riba(&generous).unwrap().unwrap();
+        assert!(counterexample.exceeds_limit());
+        assert_eq!(counterexample.schedule.iter().map(|p| p.days_late).sum::<u64>(), 1);
     }
 }
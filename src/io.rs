@@ -1,8 +1,410 @@
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use crate::kernel::KernelError;
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct IOContract {
     pub endpoint: String,    // e.g., "http://localhost:8080/shopee/balance"
     pub schema: serde_json::Value, // The JSON Schema the response must follow
     pub sensitivity: u8,     // 0: Public, 1: Private, 2: Sovereign (Local Only)
+    #[serde(default, skip_serializing_if = "ValidationMode::is_reject")]
+    pub validation: ValidationMode, // What to do with a response that breaks `schema`
+    // Request shape. All optional: a bare `endpoint` is a plain GET.
+    #[serde(default, skip_serializing_if = "HttpMethod::is_get")]
+    pub method: HttpMethod,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub query: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub secret_headers: BTreeMap<String, String>, // Header -> name of the secret holding its value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<Value>, // Sent as JSON
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth: Option<IOAuth>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_path: Option<String>, // Dotted path to the data inside the response, e.g. "data.items"
+    // Local sources (`file://`, `sqlite://`, see source.rs)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<crate::source::FileFormat>, // Inferred from the file extension if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sql: Option<String>, // Query for `sqlite://` endpoints
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sql_params: Vec<Value>, // Bound to the query's `?` placeholders
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+}
+
+impl HttpMethod {
+    fn is_get(&self) -> bool {
+        *self == HttpMethod::Get
+    }
+}
+
+impl From<HttpMethod> for reqwest::Method {
+    fn from(method: HttpMethod) -> Self {
+        match method {
+            HttpMethod::Get => reqwest::Method::GET,
+            HttpMethod::Post => reqwest::Method::POST,
+            HttpMethod::Put => reqwest::Method::PUT,
+            HttpMethod::Patch => reqwest::Method::PATCH,
+            HttpMethod::Delete => reqwest::Method::DELETE,
+        }
+    }
+}
+
+/// Credentials are never stored in the contract: each names a secret, read from the
+/// environment (or `.env`) when the request is made. Only `AETHER_SECRET_*` variables are secrets.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum IOAuth {
+    Bearer { token_secret: String },
+    Basic { username: String, password_secret: String },
+}
+
+/// Prefix of the environment variables a contract may name as secrets
+pub const SECRET_PREFIX: &str = "AETHER_SECRET_";
+
+/// Rejects secret names outside `SECRET_PREFIX`, so a contract can't read arbitrary variables
+pub fn check_secret_name(name: &str) -> Result<(), String> {
+    match name.strip_prefix(SECRET_PREFIX) {
+        Some(rest) if !rest.is_empty() => Ok(()),
+        _ => Err(format!("Secret '{}' must be named {}<NAME>", name, SECRET_PREFIX)),
+    }
+}
+
+/// Resolves a secret by name
+pub fn secret(name: &str) -> Result<String, KernelError> {
+    check_secret_name(name).map_err(KernelError::Runtime)?;
+    std::env::var(name).map_err(|_| KernelError::Runtime(format!("Secret '{}' is not set", name)))
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationMode {
+    /// Fail the node with `KernelError::SchemaViolation`
+    #[default]
+    Reject,
+    /// Drop rows that break the schema's `items` and report them as warnings
+    DropInvalid,
+}
+
+impl ValidationMode {
+    fn is_reject(&self) -> bool {
+        *self == ValidationMode::Reject
+    }
+}
+
+/// Most violations listed per rejected response or dropped row
+pub(crate) const MAX_VIOLATIONS: usize = 10;
+
+/// Where a contract's schema is registered while one of its rows is validated
+const CONTRACT_SCHEMA_URI: &str = "urn:aether:io-contract";
+
+/// Binds a run's inputs into a contract blob: `{{x}}` query values of the endpoint are
+/// URL-encoded, SQL placeholders become `?` parameters, header and query map values take the
+/// value's text, and everything else takes the typed value. Nothing is spliced into text.
+pub fn bind_contract(config: Value, params: &serde_json::Map<String, Value>) -> Result<Value, String> {
+    let Value::Object(mut fields) = config else {
+        return crate::product::bind_placeholders(config, params);
+    };
+    let endpoint = match fields.remove("endpoint") {
+        Some(Value::String(endpoint)) => Some(Value::String(crate::product::bind_url(&endpoint, params)?)),
+        other => other,
+    };
+    let mut text_maps = Vec::new();
+    for key in ["query", "headers"] {
+        match fields.remove(key) {
+            Some(Value::Object(map)) => {
+                let map = map.into_iter()
+                    .map(|(k, v)| match v {
+                        Value::String(s) => crate::product::bind_text(&s, params).map(|s| (k, Value::String(s))),
+                        other => Ok((k, other)),
+                    })
+                    .collect::<Result<serde_json::Map<_, _>, String>>()?;
+                text_maps.push((key, Value::Object(map)));
+            },
+            Some(other) => text_maps.push((key, other)),
+            None => {},
+        }
+    }
+    let sql_params = match fields.remove("sql_params") {
+        Some(Value::Array(items)) => items,
+        Some(_) => return Err("sql_params must be a list".to_string()),
+        None => Vec::new(),
+    };
+    let (sql, sql_params) = match fields.remove("sql") {
+        Some(Value::String(sql)) => {
+            let (sql, sql_params) = crate::product::bind_sql(&sql, sql_params, params)?;
+            (Some(Value::String(sql)), sql_params)
+        },
+        other => (other, sql_params.into_iter().map(|v| crate::product::bind_placeholders(v, params)).collect::<Result<_, _>>()?),
+    };
+
+    let mut bound = serde_json::Map::new();
+    for (key, value) in fields {
+        bound.insert(key, crate::product::bind_placeholders(value, params)?);
+    }
+    bound.extend(endpoint.map(|v| ("endpoint".to_string(), v)));
+    bound.extend(sql.map(|v| ("sql".to_string(), v)));
+    if !sql_params.is_empty() {
+        bound.insert("sql_params".to_string(), Value::Array(sql_params));
+    }
+    bound.extend(text_maps.into_iter().map(|(k, v)| (k.to_string(), v)));
+    Ok(Value::Object(bound))
+}
+
+impl IOContract {
+    /// Contract for a plain GET of `endpoint`
+    pub fn get(endpoint: &str, schema: Value, sensitivity: u8) -> Self {
+        Self {
+            endpoint: endpoint.to_string(),
+            schema,
+            sensitivity,
+            validation: ValidationMode::Reject,
+            method: HttpMethod::Get,
+            query: BTreeMap::new(),
+            headers: BTreeMap::new(),
+            secret_headers: BTreeMap::new(),
+            body: None,
+            auth: None,
+            data_path: None,
+            format: None,
+            sql: None,
+            sql_params: Vec::new(),
+        }
+    }
+
+    /// Read-only `sql` against a local SQLite file
+    pub fn sqlite(endpoint: &str, sql: &str, schema: Value, sensitivity: u8) -> Self {
+        Self { sql: Some(sql.to_string()), ..Self::get(endpoint, schema, sensitivity) }
+    }
+
+    /// Names of the secrets the request reads
+    pub fn secret_names(&self) -> impl Iterator<Item = &str> {
+        let auth = match &self.auth {
+            Some(IOAuth::Bearer { token_secret }) => Some(token_secret.as_str()),
+            Some(IOAuth::Basic { password_secret, .. }) => Some(password_secret.as_str()),
+            None => None,
+        };
+        self.secret_headers.values().map(String::as_str).chain(auth)
+    }
+
+    /// `file://` and `sqlite://` sources never leave the machine
+    pub fn is_local(&self) -> bool {
+        crate::source::is_local(&self.endpoint)
+    }
+
+    /// Builds the HTTP request the contract describes against `url` (the endpoint, or a
+    /// redirect target), resolving its secrets
+    pub fn request(&self, client: &reqwest::Client, url: &str) -> Result<reqwest::RequestBuilder, KernelError> {
+        let mut request = client.request(self.method.into(), url);
+        if !self.query.is_empty() {
+            request = request.query(&self.query);
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        for (name, secret_name) in &self.secret_headers {
+            request = request.header(name, secret(secret_name)?);
+        }
+        if let Some(body) = &self.body {
+            request = request.json(body);
+        }
+        request = match &self.auth {
+            Some(IOAuth::Bearer { token_secret }) => request.bearer_auth(secret(token_secret)?),
+            Some(IOAuth::Basic { username, password_secret }) => request.basic_auth(username, Some(secret(password_secret)?)),
+            None => request,
+        };
+        Ok(request)
+    }
+
+    /// Identity of the request this contract makes (after input binding); keys IO recordings.
+    /// Secrets are referenced by name, so their values never affect it.
+    pub fn content_hash(&self) -> String {
+        blake3::hash(&serde_json::to_vec(self).unwrap()).to_hex().to_string()
+    }
+
+    /// Selects the data at `data_path` (the whole response if unset)
+    pub fn extract_data(&self, response: Value) -> Result<Value, KernelError> {
+        match &self.data_path {
+            None => Ok(response),
+            Some(path) => crate::predicate::lookup(&response, path).cloned().ok_or_else(|| {
+                KernelError::Runtime(format!("IO response from '{}' has no data at '{}'", self.endpoint, path))
+            }),
+        }
+    }
+
+    /// Validator for one row of the response: `schema.items`, read in place so its `$ref`s
+    /// (e.g. to `#/definitions/...`) resolve against the whole schema. None without `items`.
+    pub(crate) fn row_validator(&self) -> Result<Option<jsonschema::Validator>, KernelError> {
+        if self.schema.get("items").is_none() {
+            return Ok(None);
+        }
+        jsonschema::options()
+            .with_resource(CONTRACT_SCHEMA_URI, jsonschema::Resource::from_contents(self.schema.clone()))
+            .build(&serde_json::json!({ "$ref": format!("{}#/items", CONTRACT_SCHEMA_URI) }))
+            .map(Some)
+            .map_err(|e| self.invalid_schema(e))
+    }
+
+    fn invalid_schema(&self, error: impl std::fmt::Display) -> KernelError {
+        KernelError::Runtime(format!("Invalid IO schema for '{}': {}", self.endpoint, error))
+    }
+
+    /// Validates a response against `schema`. Returns the (possibly filtered) response
+    /// and a warning per dropped row.
+    pub fn validate_response(&self, response: Value) -> Result<(Value, Vec<String>), KernelError> {
+        let validator = jsonschema::validator_for(&self.schema).map_err(|e| self.invalid_schema(e))?;
+
+        let row_validator = match self.validation {
+            ValidationMode::DropInvalid => self.row_validator()?,
+            ValidationMode::Reject => None,
+        };
+
+        let mut warnings = Vec::new();
+        let response = match (row_validator, response) {
+            (Some(row_validator), Value::Array(rows)) => {
+                let mut kept = Vec::with_capacity(rows.len());
+                for (index, row) in rows.into_iter().enumerate() {
+                    let violations = violations(&row_validator, &row);
+                    if violations.is_empty() {
+                        kept.push(row);
+                    } else {
+                        warnings.push(format!("IO '{}': dropped row {}: {}", self.endpoint, index, violations.join("; ")));
+                    }
+                }
+                Value::Array(kept)
+            },
+            (_, response) => response,
+        };
+
+        let violations = violations(&validator, &response);
+        if !violations.is_empty() {
+            return Err(KernelError::SchemaViolation { endpoint: self.endpoint.clone(), violations });
+        }
+        Ok((response, warnings))
+    }
+}
+
+pub(crate) fn violations(validator: &jsonschema::Validator, instance: &Value) -> Vec<String> {
+    validator.iter_errors(instance)
+        .take(MAX_VIOLATIONS)
+        .map(|e| {
+            let path = e.instance_path().to_string();
+            format!("{}: {}", if path.is_empty() { "/" } else { &path }, e)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn contract(config: Value) -> IOContract {
+        serde_json::from_value(config).unwrap()
+    }
+
+    /// Rows defined under `definitions` and referenced from `items`
+    fn with_definitions(validation: &str) -> IOContract {
+        contract(json!({
+            "endpoint": "https://api.example.com/orders",
+            "sensitivity": 0,
+            "validation": validation,
+            "schema": {
+                "type": "array",
+                "items": {"$ref": "#/definitions/order"},
+                "definitions": {
+                    "order": {"type": "object", "required": ["id"], "properties": {"id": {"type": "integer"}}}
+                }
+            }
+        }))
+    }
+
+    #[test]
+    fn dropped_rows_are_checked_against_referenced_definitions() {
+        let io = with_definitions("drop_invalid");
+        let (kept, warnings) = io.validate_response(json!([{"id": 1}, {"id": "two"}, {}])).unwrap();
+        assert_eq!(kept, json!([{"id": 1}]));
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("IO 'https://api.example.com/orders': dropped row 1:"), "{}", warnings[0]);
+    }
+
+    #[test]
+    fn rejected_responses_name_the_bad_row() {
+        let io = with_definitions("reject");
+        assert!(io.validate_response(json!([{"id": 1}])).is_ok());
+        match io.validate_response(json!([{"id": 1}, {"id": "two"}])) {
+            Err(KernelError::SchemaViolation { violations, .. }) => assert!(violations[0].starts_with("/1/id:"), "{:?}", violations),
+            other => panic!("expected a schema violation, got {:?}", other.map(|(v, _)| v)),
+        }
+    }
+
+    fn authed() -> IOContract {
+        contract(json!({
+            "endpoint": "https://api.example.com/orders",
+            "schema": {},
+            "sensitivity": 0,
+            "headers": {"accept": "application/json"},
+            "secret_headers": {"x-api-key": "AETHER_SECRET_IO_TEST_KEY"},
+            "auth": {"type": "bearer", "token_secret": "AETHER_SECRET_IO_TEST_TOKEN"}
+        }))
+    }
+
+    #[test]
+    fn secrets_must_be_in_the_secret_namespace() {
+        assert_eq!(check_secret_name("AETHER_SECRET_SHOPEE"), Ok(()));
+        assert!(check_secret_name("AETHER_SECRET_").is_err());
+        assert!(check_secret_name("DATABASE_URL").is_err());
+        assert!(matches!(secret("HOME"), Err(KernelError::Runtime(message)) if message.contains("AETHER_SECRET_<NAME>")));
+    }
+
+    #[test]
+    fn contracts_name_their_secrets_but_never_hold_them() {
+        let io = authed();
+        assert_eq!(io.secret_names().collect::<Vec<_>>(), vec!["AETHER_SECRET_IO_TEST_KEY", "AETHER_SECRET_IO_TEST_TOKEN"]);
+        let stored = serde_json::to_string(&io).unwrap();
+        assert!(stored.contains(r#""secret_headers":{"x-api-key":"AETHER_SECRET_IO_TEST_KEY"}"#), "{}", stored);
+        // Unset secrets fail the request instead of sending it without credentials
+        let unset = IOContract { secret_headers: BTreeMap::from([("x-api-key".to_string(), "AETHER_SECRET_IO_TEST_UNSET".to_string())]), auth: None, ..io };
+        match unset.request(&reqwest::Client::new(), &unset.endpoint) {
+            Err(KernelError::Runtime(message)) => assert_eq!(message, "Secret 'AETHER_SECRET_IO_TEST_UNSET' is not set"),
+            other => panic!("expected a missing secret, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn requests_carry_headers_and_resolved_secrets() {
+        // SAFETY: these variables are only read by this test
+        unsafe {
+            std::env::set_var("AETHER_SECRET_IO_TEST_KEY", "k-123");
+            std::env::set_var("AETHER_SECRET_IO_TEST_TOKEN", "t-456");
+        }
+        let io = authed();
+        let request = io.request(&reqwest::Client::new(), &io.endpoint).unwrap().build().unwrap();
+        let header = |name: &str| request.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        assert_eq!(header("accept").as_deref(), Some("application/json"));
+        assert_eq!(header("x-api-key").as_deref(), Some("k-123"));
+        assert_eq!(header("authorization").as_deref(), Some("Bearer t-456"));
+        assert!(request.headers()["authorization"].is_sensitive());
+    }
+
+    #[test]
+    fn row_validators_follow_references() {
+        let validator = with_definitions("reject").row_validator().unwrap().unwrap();
+        assert!(validator.is_valid(&json!({"id": 7})));
+        assert!(!validator.is_valid(&json!({"id": "7"})));
+        assert!(contract(json!({"endpoint": "x", "schema": {"type": "object"}, "sensitivity": 0})).row_validator().unwrap().is_none());
+    }
 }
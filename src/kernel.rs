@@ -1,6 +1,17 @@
 use crate::{AetherVault, VaultError, LogicAtom};
+use std::collections::HashMap;
 use std::convert::TryInto;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use futures::channel::oneshot;
+use futures::future::{BoxFuture, FutureExt, Shared};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
+use crate::guard::SovereigntyStage;
+use crate::runs::unix_micros;
+
+/// Redirect hops followed per IO request
+const MAX_REDIRECTS: usize = 10;
 
 #[derive(Error, Debug)]
 pub enum KernelError {
@@ -10,15 +21,231 @@ pub enum KernelError {
     Runtime(String),
     #[error("Invalid OpCode: {0}")]
     InvalidOpCode(u16),
+    #[error("Execution budget exceeded: graph did not finish within {0}ms")]
+    Timeout(u64),
+    #[error("Execution budget exceeded: IO to '{endpoint}' did not respond within {timeout_ms}ms")]
+    IoTimeout { endpoint: String, timeout_ms: u64 },
+    #[error("Execution budget exceeded: node {hash} produced {rows} rows (limit {limit})")]
+    RowLimit { hash: String, rows: usize, limit: usize },
+    #[error("Execution budget exceeded: output is {bytes} bytes (limit {limit})")]
+    OutputLimit { bytes: usize, limit: usize },
+    #[error("Arithmetic error: {0}")]
+    Arithmetic(String),
+    #[error("Input binding error: {0}")]
+    Binding(String),
+    #[error("IO response from '{endpoint}' violates its contract schema: {}", violations.join("; "))]
+    SchemaViolation { endpoint: String, violations: Vec<String> },
+    #[error("Violation of Sovereignty Law: {reason} ({endpoint})")]
+    Sovereignty { endpoint: String, reason: String },
+    #[error("No recorded response for IO '{endpoint}' (replay mode)")]
+    ReplayMiss { endpoint: String },
+    /// A shared node's failure as its later consumers see it; the first one gets the original (see `Memo`)
+    #[error("{0}")]
+    Upstream(String),
+}
+
+impl KernelError {
+    /// Stable, machine-readable name of the variant (e.g. for API clients)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            KernelError::Vault(_) => "vault",
+            KernelError::Runtime(_) => "runtime",
+            KernelError::InvalidOpCode(_) => "invalid_op_code",
+            KernelError::Timeout(_) => "timeout",
+            KernelError::IoTimeout { .. } => "io_timeout",
+            KernelError::RowLimit { .. } => "row_limit",
+            KernelError::OutputLimit { .. } => "output_limit",
+            KernelError::Arithmetic(_) => "arithmetic",
+            KernelError::Binding(_) => "binding",
+            KernelError::SchemaViolation { .. } => "schema_violation",
+            KernelError::Sovereignty { .. } => "sovereignty",
+            KernelError::ReplayMiss { .. } => "replay_miss",
+            KernelError::Upstream(_) => "upstream",
+        }
+    }
+}
+
+/// A node that failed by itself, as opposed to failing because one of its inputs did.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct NodeError {
+    pub hash: String,
+    pub op_code: Option<u16>, // None if the atom could not be fetched
+    pub kind: String,         // See `KernelError::kind`
+    pub message: String,
+}
+
+/// Resource limits for a single execution.
+/// Set per project via the manifest `budget:` block; missing keys fall back to the defaults.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ExecutionBudget {
+    pub timeout_ms: u64,         // Wall clock for the whole graph
+    pub io_timeout_ms: u64,      // Per IO request
+    pub max_rows_per_node: usize,
+    pub max_output_bytes: usize, // Serialized size of the root output
+}
+
+impl Default for ExecutionBudget {
+    fn default() -> Self {
+        Self {
+            timeout_ms: 30_000,
+            io_timeout_ms: 10_000,
+            max_rows_per_node: 100_000,
+            max_output_bytes: 16 * 1024 * 1024,
+        }
+    }
+}
+
+/// Per-call switches for `execute_with`.
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionOptions {
+    /// Record a `TraceNode` tree alongside the output.
+    pub trace: bool,
+    /// Values for the graph's PARAM (Op 20) atoms, by input name.
+    pub bindings: HashMap<String, serde_json::Value>,
+    /// Whether IO atoms hit the network, record their responses, or replay recordings.
+    pub io_mode: IoMode,
+    /// Let MERGE (Op 3) drop failed branches and merge the healthy ones instead of failing.
+    /// Only MERGE opts in: every other node still fails with its first failed input.
+    /// Every failure is listed in `ExecutionReport.errors` either way.
+    pub partial: bool,
+}
+
+/// How IO (Op 500) atoms get their responses.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum IoMode {
+    #[default]
+    Live,
+    /// Live, and save each response keyed by the contract's hash
+    Record,
+    /// Serve saved responses only; never touches the network
+    Replay,
+}
+
+/// What one node did during a traced execution.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TraceNode {
+    pub hash: String,
+    pub op_code: Option<u16>, // None if the atom could not be fetched
+    pub started_at_us: u64,   // Unix epoch, microseconds
+    pub finished_at_us: u64,
+    pub duration_ns: u128,
+    pub input_rows: Vec<usize>,
+    pub output_rows: Option<usize>,
+    pub cache_hit: bool,
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>, // What the node ran, e.g. a filter's predicate or an IO request
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>, // Non-fatal issues, e.g. rows dropped by IO validation
+    pub children: Vec<TraceNode>,
+}
+
+pub struct ExecutionReport {
+    pub output: Result<serde_json::Value, KernelError>,
+    pub trace: Option<TraceNode>,
+    pub warnings: Vec<String>, // Every node's warnings, in completion order
+    pub errors: Vec<NodeError>, // Every node that failed, whether or not the output survived it
+}
+
+/// State shared by every node of a single `execute_with` call.
+struct ExecutionRun<'a> {
+    options: &'a ExecutionOptions,
+    memo: Memo,
+    warnings: Mutex<Vec<String>>,
+    errors: Mutex<Vec<NodeError>>,
+}
+
+impl ExecutionRun<'_> {
+    /// Notes a node's own failure
+    fn record_error(&self, hash: &str, op_code: Option<u16>, error: &KernelError) {
+        self.errors.lock().unwrap().push(NodeError {
+            hash: hash.to_string(),
+            op_code,
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+        });
+    }
+}
+
+/// A node's settled output; a failure keeps its message (see `KernelError::Upstream`)
+type Settled = Result<serde_json::Value, String>;
+
+/// Outputs of the nodes a run has started. The first consumer of a node runs it; consumers
+/// reaching it meanwhile (diamonds) wait for that result instead of running it again.
+#[derive(Default)]
+pub(crate) struct Memo(Mutex<HashMap<String, Shared<oneshot::Receiver<Settled>>>>);
+
+pub(crate) enum Claim {
+    /// Run the node and `settle` its result
+    Run(oneshot::Sender<Settled>),
+    /// Another consumer runs (or ran) it: `wait` for its result
+    Wait(Shared<oneshot::Receiver<Settled>>),
+}
+
+impl Memo {
+    /// A memo whose nodes are already settled with the given outputs
+    pub(crate) fn seeded(seed: HashMap<String, serde_json::Value>) -> Self {
+        let memo = Memo::default();
+        for (hash, value) in seed {
+            if let Claim::Run(sender) = memo.claim(&hash) {
+                let _ = sender.send(Ok(value));
+            }
+        }
+        memo
+    }
+
+    pub(crate) fn claim(&self, hash: &str) -> Claim {
+        let mut memo = self.0.lock().unwrap();
+        if let Some(pending) = memo.get(hash) {
+            return Claim::Wait(pending.clone());
+        }
+        let (sender, receiver) = oneshot::channel();
+        memo.insert(hash.to_string(), receiver.shared());
+        Claim::Run(sender)
+    }
+
+    pub(crate) fn settle(sender: oneshot::Sender<Settled>, result: &Result<serde_json::Value, KernelError>) {
+        let _ = sender.send(result.as_ref().cloned().map_err(|e| e.to_string()));
+    }
+
+    pub(crate) async fn wait(hash: &str, pending: Shared<oneshot::Receiver<Settled>>) -> Result<serde_json::Value, KernelError> {
+        match pending.await {
+            Ok(settled) => settled.map_err(KernelError::Upstream),
+            Err(_) => Err(KernelError::Runtime(format!("Node {} was abandoned before it finished", hash))),
+        }
+    }
+
+    /// Every node that settled successfully, by hash
+    pub(crate) fn into_outputs(self) -> HashMap<String, serde_json::Value> {
+        self.0.into_inner().unwrap().into_iter()
+            .filter_map(|(hash, pending)| match pending.now_or_never() {
+                Some(Ok(Ok(value))) => Some((hash, value)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+struct NodeOutcome {
+    result: Result<serde_json::Value, KernelError>,
+    trace: Option<TraceNode>,
 }
 
 pub struct AetherKernel {
     pub vault: AetherVault,
+    pub budget: ExecutionBudget,
+    pub guard: crate::AetherGuard, // Re-checks sovereignty when IO connects
 }
 
 impl AetherKernel {
     pub fn new(vault: AetherVault) -> Self {
-        Self { vault }
+        Self::with_budget(vault, ExecutionBudget::default())
+    }
+
+    pub fn with_budget(vault: AetherVault, budget: ExecutionBudget) -> Self {
+        Self { vault, budget, guard: crate::AetherGuard::new() }
     }
 
     fn resolve_data(&self, atom: &LogicAtom) -> Result<Vec<u8>, KernelError> {
@@ -27,19 +254,74 @@ impl AetherKernel {
             .map_err(|e| KernelError::Runtime(format!("Blob Fetch Error: {}", e)))
     }
 
+    /// Loads a JSON config blob with the run's bound inputs substituted for `{{placeholders}}`
+    pub(crate) fn resolve_bound(&self, atom: &LogicAtom, params: &serde_json::Map<String, serde_json::Value>) -> Result<serde_json::Value, KernelError> {
+        let data = self.resolve_data(atom)?;
+        let config = serde_json::from_slice(&data)
+            .map_err(|e| KernelError::Runtime(format!("Op {} Config Error: {}", atom.op_code, e)))?;
+        let bound = match (atom.op_code, config) {
+            (500, config) => crate::io::bind_contract(config, params),
+            // COMPUTE binds `{{x}}` in its expression as variables (see `Expr::bind`)
+            (9, serde_json::Value::Object(mut fields)) => {
+                let expr = fields.remove("expr");
+                crate::product::bind_placeholders(serde_json::Value::Object(fields), params).map(|mut bound| {
+                    if let (serde_json::Value::Object(bound), Some(expr)) = (&mut bound, expr) {
+                        bound.insert("expr".to_string(), expr);
+                    }
+                    bound
+                })
+            },
+            (_, config) => crate::product::bind_placeholders(config, params),
+        };
+        bound.map_err(KernelError::Binding)
+    }
+
+    /// Loads and parses a JSON config blob (e.g. SortConfig for Op 4)
+    pub(crate) fn resolve_config<T: serde::de::DeserializeOwned>(&self, atom: &LogicAtom, params: &serde_json::Map<String, serde_json::Value>) -> Result<T, KernelError> {
+        serde_json::from_value(self.resolve_bound(atom, params)?)
+            .map_err(|e| KernelError::Runtime(format!("Op {} Config Error: {}", atom.op_code, e)))
+    }
+
+    /// Binds a PARAM (Op 20) atom to the run's value for it, validated against its `InputSchema`
+    fn bind_param(&self, atom: &LogicAtom, options: &ExecutionOptions) -> Result<(String, serde_json::Value), KernelError> {
+        let schema: crate::InputSchema = self.resolve_config(atom, &serde_json::Map::new())?;
+        let value = schema.bind(options.bindings.get(&schema.name)).map_err(KernelError::Binding)?;
+        Ok((schema.name, value))
+    }
+
+    /// Splits a node's inputs into bound PARAM (Op 20) values and the data inputs to execute.
+    /// A failed binding is returned alongside so callers can still report on the other inputs.
+    pub(crate) fn partition_inputs<'a>(
+        &self,
+        atom: &'a LogicAtom,
+        options: &ExecutionOptions,
+    ) -> (serde_json::Map<String, serde_json::Value>, Vec<&'a String>, Option<KernelError>) {
+        let mut failure = None;
+        let mut params = serde_json::Map::new();
+        let mut data_inputs = Vec::new();
+        for input in &atom.inputs {
+            match self.vault.fetch(input) {
+                Ok(input_atom) if input_atom.op_code == crate::OP_PARAM => match self.bind_param(&input_atom, options) {
+                    Ok((name, value)) => {
+                        params.insert(name, value);
+                    },
+                    Err(e) => {
+                        failure.get_or_insert(e);
+                    },
+                },
+                _ => data_inputs.push(input),
+            }
+        }
+        (params, data_inputs, failure)
+    }
+
     /// Fetches a node by hash and executed its logic (Legacy Sync)
     pub fn execute(&self, hash: &str) -> Result<i32, KernelError> {
         let atom = self.vault.fetch(hash).map_err(KernelError::Vault)?;
         let data = self.resolve_data(&atom)?;
         
         match atom.op_code {
-            1 => {
-                // ADD
-                if data.len() < 8 { return Err(KernelError::Runtime("Invalid data length for ADD".into())); }
-                let a = i32::from_le_bytes(data[0..4].try_into().unwrap());
-                let b = i32::from_le_bytes(data[4..8].try_into().unwrap());
-                Ok(a + b)
-            },
+            1 => legacy_add(&data), // ADD
             100 => Ok(0),
             _ => Err(KernelError::InvalidOpCode(atom.op_code)),
         }
@@ -54,70 +336,289 @@ impl AetherKernel {
     
     /// Smart Execution: recursive pipeline that returns JSON (Async)
     pub async fn execute_smart(&self, hash: &str) -> Result<serde_json::Value, KernelError> {
-        let atom = self.vault.fetch(hash).map_err(KernelError::Vault)?;
+        self.execute_with(hash, &ExecutionOptions::default()).await.output
+    }
+
+    /// Smart Execution with per-call options (e.g. a per-node trace tree)
+    pub async fn execute_with(&self, hash: &str, options: &ExecutionOptions) -> ExecutionReport {
+        self.execute_seeded(hash, options, HashMap::new()).await.0
+    }
+
+    /// Like `execute_with`, but nodes already in `seed` (hash -> output) are not re-run.
+    /// Also returns every node's output, to seed a later incremental run.
+    pub async fn execute_seeded(
+        &self,
+        hash: &str,
+        options: &ExecutionOptions,
+        seed: HashMap<String, serde_json::Value>,
+    ) -> (ExecutionReport, HashMap<String, serde_json::Value>) {
+        let run = ExecutionRun {
+            options,
+            memo: Memo::seeded(seed),
+            warnings: Mutex::new(Vec::new()),
+            errors: Mutex::new(Vec::new()),
+        };
+        let timeout = Duration::from_millis(self.budget.timeout_ms);
+        let outcome = match tokio::time::timeout(timeout, self.run_node(hash, &run)).await {
+            Ok(outcome) => outcome,
+            Err(_) => NodeOutcome {
+                result: Err(KernelError::Timeout(self.budget.timeout_ms)),
+                trace: None,
+            },
+        };
+
+        let output = outcome.result.and_then(|value| {
+            let bytes = serialized_len(&value);
+            if bytes > self.budget.max_output_bytes {
+                return Err(KernelError::OutputLimit { bytes, limit: self.budget.max_output_bytes });
+            }
+            Ok(value)
+        });
+        // Graph-wide budgets fail no single node: charge them to the root
+        if let Err(e) = &output && run.errors.lock().unwrap().is_empty() {
+            run.record_error(hash, None, e);
+        }
+        let report = ExecutionReport {
+            output,
+            trace: outcome.trace,
+            warnings: run.warnings.into_inner().unwrap(),
+            errors: run.errors.into_inner().unwrap(),
+        };
+        (report, run.memo.into_outputs())
+    }
+
+    fn run_node<'a>(&'a self, hash: &'a str, run: &'a ExecutionRun<'a>) -> BoxFuture<'a, NodeOutcome> {
+        Box::pin(async move {
+            let started_at_us = unix_micros();
+            let clock = Instant::now();
+
+            let sender = match run.memo.claim(hash) {
+                Claim::Run(sender) => sender,
+                Claim::Wait(pending) => {
+                    let result = Memo::wait(hash, pending).await;
+                    let trace = run.options.trace.then(|| TraceNode {
+                        hash: hash.to_string(),
+                        op_code: self.vault.fetch(hash).ok().map(|a| a.op_code),
+                        started_at_us,
+                        finished_at_us: unix_micros(),
+                        duration_ns: clock.elapsed().as_nanos(),
+                        input_rows: vec![],
+                        output_rows: result.as_ref().ok().map(row_count),
+                        cache_hit: true,
+                        error: result.as_ref().err().map(|e| e.to_string()),
+                        detail: None,
+                        warnings: vec![],
+                        children: vec![],
+                    });
+                    return NodeOutcome { result, trace };
+                },
+            };
+            let outcome = self.evaluate_node(hash, run, started_at_us, clock).await;
+            Memo::settle(sender, &outcome.result);
+            outcome
+        })
+    }
+
+    /// Runs a node the run has not seen yet: its inputs, then its op
+    async fn evaluate_node(&self, hash: &str, run: &ExecutionRun<'_>, started_at_us: u64, clock: Instant) -> NodeOutcome {
+        let atom = match self.vault.fetch(hash) {
+            Ok(atom) => atom,
+            Err(e) => {
+                let error = KernelError::Vault(e);
+                run.record_error(hash, None, &error);
+                let trace = run.options.trace.then(|| TraceNode {
+                    hash: hash.to_string(),
+                    op_code: None,
+                    started_at_us,
+                    finished_at_us: unix_micros(),
+                    duration_ns: clock.elapsed().as_nanos(),
+                    input_rows: vec![],
+                    output_rows: None,
+                    cache_hit: false,
+                    error: Some(error.to_string()),
+                    detail: None,
+                    warnings: vec![],
+                    children: vec![],
+                });
+                return NodeOutcome { result: Err(error), trace };
+            }
+        };
+
+        // Parameter atoms (Op 20) are bound from the run's inputs, not executed as data
+        let (params, data_inputs, failure) = self.partition_inputs(&atom, run.options);
 
         // Recursive: Execute dependencies in parallel (Async Resonance)
-        let futures = atom.inputs.iter().map(|h| Box::pin(self.execute_smart(h)));
-        let results = futures::future::join_all(futures).await;
+        let futures = data_inputs.iter().map(|h| self.run_node(h, run));
+        let outcomes = futures::future::join_all(futures).await;
 
+        // In partial mode a MERGE carries on with its healthy branches
+        let skip_failed = run.options.partial && atom.op_code == 3;
+        let mut warnings = Vec::new();
+        let mut input_failure = None;
+        let mut children = Vec::new();
         let mut input_results = Vec::new();
-        for res in results {
-            input_results.push(res?);
+        for (input, outcome) in data_inputs.iter().zip(outcomes) {
+            children.extend(outcome.trace);
+            match outcome.result {
+                Ok(value) => input_results.push(value),
+                Err(e) if skip_failed => warnings.push(format!("MERGE skipped failed input {}: {}", input, e)),
+                Err(e) => {
+                    input_failure.get_or_insert(e);
+                }
+            }
         }
+        let input_rows = input_results.iter().map(row_count).collect();
+
+        let result = match (failure, input_failure) {
+            // Already recorded by the input that failed
+            (None, Some(e)) => Err(e),
+            (failure, _) => {
+                let result = match failure {
+                    Some(e) => Err(e),
+                    None => self.apply_op(hash, &atom, input_results, &params, run.options, &mut warnings).await
+                        .and_then(|value| {
+                            let rows = row_count(&value);
+                            if rows > self.budget.max_rows_per_node {
+                                return Err(KernelError::RowLimit {
+                                    hash: hash.to_string(),
+                                    rows,
+                                    limit: self.budget.max_rows_per_node,
+                                });
+                            }
+                            Ok(value)
+                        }),
+                };
+                if let Err(e) = &result {
+                    run.record_error(hash, Some(atom.op_code), e);
+                }
+                result
+            },
+        };
+        run.warnings.lock().unwrap().extend(warnings.iter().cloned());
+
+        let detail = if run.options.trace { self.detail(&atom, &params) } else { None };
+        let trace = run.options.trace.then(|| TraceNode {
+            hash: hash.to_string(),
+            op_code: Some(atom.op_code),
+            started_at_us,
+            finished_at_us: unix_micros(),
+            duration_ns: clock.elapsed().as_nanos(),
+            input_rows,
+            output_rows: result.as_ref().ok().map(row_count),
+            cache_hit: false,
+            error: result.as_ref().err().map(|e| e.to_string()),
+            detail,
+            warnings,
+            children,
+        });
+        NodeOutcome { result, trace }
+    }
+
+    /// A traced node's detail: a FILTER's predicate or the request an IO contract makes
+    fn detail(&self, atom: &LogicAtom, params: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+        match atom.op_code {
+            2 => {
+                let predicate: crate::predicate::Predicate = serde_json::from_value(self.resolve_bound(atom, params).ok()?).ok()?;
+                Some(format!("where {}", predicate))
+            },
+            500 => {
+                let contract: crate::IOContract = serde_json::from_value(self.resolve_bound(atom, params).ok()?).ok()?;
+                Some(format!("{:?} {}", contract.method, contract.endpoint))
+            },
+            _ => None,
+        }
+    }
 
+    pub(crate) async fn apply_op(
+        &self,
+        hash: &str,
+        atom: &LogicAtom,
+        input_results: Vec<serde_json::Value>,
+        params: &serde_json::Map<String, serde_json::Value>,
+        options: &ExecutionOptions,
+        warnings: &mut Vec<String>,
+    ) -> Result<serde_json::Value, KernelError> {
         match atom.op_code {
             1 => { // ADD (Legacy wrapper)
-                 Ok(serde_json::json!(0)) 
+                 let data = self.resolve_data(atom)?;
+                 Ok(serde_json::json!(legacy_add(&data)?))
             },
             2 => { // FILTER
                 // Input 0: The List
-                // Data: The Filter Logic JSON
-                if let Some(list) = input_results.get(0) {
-                    if let Some(array) = list.as_array() {
-                        let data = self.resolve_data(&atom)?;
-                        let filter_config: serde_json::Value = serde_json::from_slice(&data)
-                            .map_err(|e| KernelError::Runtime(e.to_string()))?;
-                        let field = filter_config["field"].as_str().unwrap_or("");
-                        let op = filter_config["op"].as_str().unwrap_or("");
-                        let val_i = filter_config["val"].as_i64();
-                        let val_s = filter_config["val"].as_str();
-
-                        // Debug print
-                        println!("[Kernel] Filtering {} items with {} {} {}", array.len(), field, op, val_s.unwrap_or("NUM"));
-
-                        let filtered: Vec<_> = array.iter().filter(|item| {
-                            match op {
-                                ">" => item[field].as_i64().unwrap_or(0) > val_i.unwrap_or(0),
-                                "<" => item[field].as_i64().unwrap_or(0) < val_i.unwrap_or(0),
-                                "==" => item[field].as_str().unwrap_or("") == val_s.unwrap_or(""),
-                                "!=" => item[field].as_str().unwrap_or("") != val_s.unwrap_or(""),
-                                "contains" => item[field].as_str().unwrap_or("").contains(val_s.unwrap_or("")),
-                                "not_contains" => !item[field].as_str().unwrap_or("").contains(val_s.unwrap_or("")),
-                                _ => true
-                            }
-                        }).cloned().collect();
-                        
-                        return Ok(serde_json::Value::Array(filtered));
-                    }
+                // Data: The Predicate JSON (see predicate.rs)
+                if let Some(serde_json::Value::Array(array)) = input_results.into_iter().next() {
+                    let predicate: crate::predicate::Predicate = serde_json::from_value(self.resolve_bound(atom, params)?)
+                        .map_err(|e| KernelError::Runtime(format!("Filter Config Error: {}", e)))?;
+                    let matcher = predicate.compile().map_err(KernelError::Runtime)?;
+
+                    let filtered: Vec<_> = array.into_iter().filter(|item| matcher.matches(item)).collect();
+                    return Ok(serde_json::Value::Array(filtered));
                 }
                 Ok(serde_json::json!([]))
             },
             3 => { // MERGE / UNION
-                let mut merged = Vec::new();
+                // Sized up front: an oversized union fails before it is built
+                let rows = input_results.iter().filter_map(serde_json::Value::as_array).map(Vec::len).sum();
+                if rows > self.budget.max_rows_per_node {
+                    return Err(KernelError::RowLimit { hash: hash.to_string(), rows, limit: self.budget.max_rows_per_node });
+                }
+                let mut merged = Vec::with_capacity(rows);
                 for res in input_results {
-                     if let Some(arr) = res.as_array() {
-                         merged.extend(arr.clone());
+                     if let serde_json::Value::Array(arr) = res {
+                         merged.extend(arr);
                      }
                 }
                 Ok(serde_json::Value::Array(merged))
             },
+            4 => { // SORT
+                let config: crate::pipeline::SortConfig = self.resolve_config(atom, params)?;
+                Ok(serde_json::Value::Array(crate::pipeline::sort(first_list(input_results), &config)))
+            },
+            5 => { // LIMIT / OFFSET
+                let config: crate::pipeline::LimitConfig = self.resolve_config(atom, params)?;
+                Ok(serde_json::Value::Array(crate::pipeline::limit(first_list(input_results), &config)))
+            },
+            6 => { // PROJECT / RENAME
+                let config: crate::pipeline::ProjectConfig = self.resolve_config(atom, params)?;
+                Ok(serde_json::Value::Array(crate::pipeline::project(first_list(input_results), &config)))
+            },
+            7 => { // AGGREGATE / GROUP BY
+                let config: crate::pipeline::AggregateConfig = self.resolve_config(atom, params)?;
+                Ok(serde_json::Value::Array(crate::pipeline::aggregate(first_list(input_results), &config)))
+            },
+            8 => { // JOIN
+                // Input 0: Left List, Input 1: Right List
+                let config: crate::pipeline::JoinConfig = self.resolve_config(atom, params)?;
+                let mut lists = input_results.into_iter();
+                let left = into_list(lists.next());
+                let right = into_list(lists.next());
+                let limit = self.budget.max_rows_per_node;
+                let joined = crate::pipeline::join(left, right, &config, limit)
+                    .map_err(|e| KernelError::Runtime(format!("Join: {}", e)))?;
+                if joined.len() > limit {
+                    return Err(KernelError::RowLimit { hash: hash.to_string(), rows: joined.len(), limit });
+                }
+                Ok(serde_json::Value::Array(joined))
+            },
+            9 => { // COMPUTE
+                // Input 0: List (derived column per record) or scalar/record; `$N` reads input N
+                let config: crate::expr::ComputeConfig = self.resolve_config(atom, params)?;
+                crate::expr::compute(&config, input_results, params).map_err(|e| match e {
+                    crate::expr::ExprError::DivisionByZero(_) | crate::expr::ExprError::Overflow(_) => KernelError::Arithmetic(e.to_string()),
+                    crate::expr::ExprError::Unbound(_) => KernelError::Binding(e.to_string()),
+                    _ => KernelError::Runtime(format!("Compute '{}': {}", config.expr, e)),
+                })
+            },
+            20 => { // PARAM (executed directly, e.g. as a root)
+                self.bind_param(atom, options).map(|(_, value)| value)
+            },
             50 => { // REACTIVE_TRIGGER
-                 // This is a UI-Hint OpCode. In the backend, it acts as a pass-through or configuration echo.
-                 // The "root" deployment will include this in the graph, so the UI knows to bind an event.
-                 let data = self.resolve_data(&atom)?;
-                 let config: serde_json::Value = serde_json::from_slice(&data)
-                     .map_err(|e| KernelError::Runtime(e.to_string()))?;
-                 Ok(config)
+                 // Inline (with an input) it passes input 0 through; the ReactiveHub re-runs it when its
+                 // event fires (see reactive.rs). Without inputs it echoes its config as a UI hint.
+                 match input_results.into_iter().next() {
+                     Some(input) => Ok(input),
+                     None => self.resolve_bound(atom, params),
+                 }
             },
             100 => { // FINANCIAL / AUDIT (Identity)
                 if let Some(res) = input_results.get(0) {
@@ -126,27 +627,32 @@ impl AetherKernel {
                     Ok(serde_json::json!({"status": "Audited"}))
                 }
             },
+            101 => { // ZAKAT
+                // Input 0: Asset records (unless the config has a balance); last input: metal price for the nisab
+                let config: crate::zakat::ZakatConfig = self.resolve_config(atom, params)?;
+                crate::zakat::calculate(&config, &input_results)
+                    .map_err(|e| KernelError::Runtime(format!("Zakat: {}", e)))
+            },
             500 => { // IO
-                self.execute_io(hash).await
+                self.fetch_io(atom, params, options.io_mode, warnings).await
             },
             800 => { // GATEWAY / MASKING
                 // Input 0: The Internal Logic Result to be Masked
-                if let Some(internal_result) = input_results.get(0) {
-                     // In a real scenario, this might encrypt fields or filter sensitive keys
-                     // For now, we wrap it in a "Sovereign Envelope"
-                     Ok(serde_json::json!({
-                         "origin": "0xSOVEREIGN_ROOT", 
-                         "payload": internal_result,
-                         "masked_fields": ["private_logic_trace"]
-                     }))
+                // Data: The MaskingPolicy JSON (see masking.rs); an empty blob masks nothing
+                if let Some(internal_result) = input_results.into_iter().next() {
+                     let policy = masking_policy(&self.resolve_data(atom)?)?;
+                     policy.apply(internal_result)
+                         .map_err(|e| KernelError::Runtime(format!("Gateway: {}", e)))
                 } else {
                      Ok(serde_json::json!({"error": "Gateway has no input resonance"}))
                 }
             },
             600 => { // SYNTHESIS_REQUIRED
-                 let data = self.resolve_data(&atom)?;
+                 let data = self.resolve_data(atom)?;
                  let intent = String::from_utf8_lossy(&data).to_string();
-                 
+                 // Atoms stored without the Guard (e.g. injected) join the queue when first run
+                 self.vault.queue_gap(hash, atom)?;
+
                  // Signal to UI: "I need to learn this."
                  // The UI (Architect Mode) should pick this up and trigger the generation flow.
                  Ok(serde_json::json!({
@@ -162,20 +668,364 @@ impl AetherKernel {
 
     pub async fn execute_io(&self, hash: &str) -> Result<serde_json::Value, KernelError> {
         let atom = self.vault.fetch(hash).map_err(KernelError::Vault)?;
-        
+        self.fetch_io(&atom, &serde_json::Map::new(), IoMode::Live, &mut Vec::new()).await
+    }
+
+    /// Runs an IO (Op 500) contract with the run's bound inputs (see `io::bind_contract`)
+    pub(crate) async fn fetch_io(
+        &self,
+        atom: &LogicAtom,
+        params: &serde_json::Map<String, serde_json::Value>,
+        io_mode: IoMode,
+        warnings: &mut Vec<String>,
+    ) -> Result<serde_json::Value, KernelError> {
         if atom.op_code == 500 {
-            let data = self.resolve_data(&atom)?;
-            let contract: crate::IOContract = serde_json::from_slice(&data)
+            let contract: crate::IOContract = serde_json::from_value(self.resolve_bound(atom, params)?)
                 .map_err(|e| KernelError::Runtime(format!("IO Contract Parse Error: {}", e)))?;
-            println!("[Kernel] Fetching IO: {}", contract.endpoint);
-            
-            let response = reqwest::get(&contract.endpoint).await
-                .map_err(|e| KernelError::Runtime(format!("Network Error: {}", e)))?
-                .json::<serde_json::Value>().await
-                .map_err(|e| KernelError::Runtime(format!("JSON Parse Error: {}", e)))?;
-                
+            let response = match io_mode {
+                IoMode::Replay => {
+                    // Served from the recording: no network, no clock, deterministic
+                    let uri = self.vault.recorded_io(&contract.content_hash())?
+                        .ok_or_else(|| KernelError::ReplayMiss { endpoint: contract.endpoint.clone() })?;
+                    let data = crate::read_blob(&uri)
+                        .map_err(|e| KernelError::Runtime(format!("Blob Fetch Error: {}", e)))?;
+                    serde_json::from_slice(&data)
+                        .map_err(|e| KernelError::Runtime(format!("Recorded IO Parse Error: {}", e)))?
+                },
+                IoMode::Live | IoMode::Record => {
+                    let response = if contract.is_local() {
+                        let local = contract.clone();
+                        tokio::task::spawn_blocking(move || crate::source::read_local(&local)).await
+                            .map_err(|e| KernelError::Runtime(format!("Local Source Error: {}", e)))??
+                    } else {
+                        self.fetch_http(&contract).await?
+                    };
+                    if io_mode == IoMode::Record {
+                        let uri = crate::write_blob(&serde_json::to_vec(&response).unwrap())
+                            .map_err(|e| KernelError::Runtime(format!("Blob Write Error: {}", e)))?;
+                        self.vault.record_io(&contract.content_hash(), &uri)?;
+                    }
+                    response
+                },
+            };
+            let response = contract.extract_data(response)?;
+
+            // Enforce the contract: reject, or drop non-conforming rows with a warning each
+            let (response, dropped) = contract.validate_response(response)?;
+            warnings.extend(dropped);
             return Ok(response);
         }
         Err(KernelError::InvalidOpCode(atom.op_code))
     }
+
+    /// Performs the HTTP request a contract describes and returns the raw JSON response
+    async fn fetch_http(&self, contract: &crate::IOContract) -> Result<serde_json::Value, KernelError> {
+        let response = self.send_http(contract, false).await?;
+        response.json::<serde_json::Value>().await
+            .map_err(|e| if e.is_timeout() { io_error(&contract.endpoint, self.budget.io_timeout_ms, e) } else { KernelError::Runtime(format!("JSON Parse Error: {}", e)) })
+    }
+
+    /// Sends the request a contract describes, following redirects, and returns the successful
+    /// response with its body unread. A `streaming` response bounds each read, not the whole body.
+    pub(crate) async fn send_http(&self, contract: &crate::IOContract, streaming: bool) -> Result<reqwest::Response, KernelError> {
+        let network_error = |e: reqwest::Error| io_error(&contract.endpoint, self.budget.io_timeout_ms, e);
+
+        // Redirects are followed by hand so every hop passes the sovereignty check
+        let mut url = contract.endpoint.clone();
+        let mut hop = contract.clone();
+        let mut stage = SovereigntyStage::Connect;
+        let mut redirects = 0;
+        let response = loop {
+            let client = self.sovereign_client(&url, contract.sensitivity, stage, streaming).await?;
+            let response = hop.request(&client, &url)?.send().await
+                .map_err(network_error)?;
+            let status = response.status();
+            if !status.is_redirection() {
+                break response;
+            }
+            redirects += 1;
+            if redirects > MAX_REDIRECTS {
+                return Err(KernelError::Runtime(format!("IO '{}' exceeded {} redirects", contract.endpoint, MAX_REDIRECTS)));
+            }
+            let location = response.headers().get(reqwest::header::LOCATION)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| KernelError::Runtime(format!("IO '{}' sent {} without a Location", url, status)))?;
+            let next = url::Url::parse(&url).and_then(|base| base.join(location))
+                .map_err(|e| KernelError::Runtime(format!("IO '{}' redirected to invalid '{}': {}", url, location, e)))?;
+            if status == reqwest::StatusCode::SEE_OTHER {
+                hop.method = crate::io::HttpMethod::Get;
+                hop.body = None;
+            }
+            // Credentials never follow a redirect to another host
+            if url::Url::parse(&url).ok().and_then(|u| u.host_str().map(str::to_string)).as_deref() != next.host_str() {
+                hop.auth = None;
+                hop.secret_headers.clear();
+            }
+            url = next.to_string();
+            stage = SovereigntyStage::Redirect;
+        };
+        let status = response.status();
+        if !status.is_success() {
+            return Err(KernelError::Runtime(format!("IO '{}' returned HTTP {}", contract.endpoint, status)));
+        }
+        Ok(response)
+    }
+
+    /// HTTP client for one request to `url`. Sovereign hosts are resolved here, checked against
+    /// the Guard's policy and pinned to the checked addresses; the decision is audited.
+    async fn sovereign_client(&self, url: &str, sensitivity: u8, stage: SovereigntyStage, streaming: bool) -> Result<reqwest::Client, KernelError> {
+        let io_timeout = Duration::from_millis(self.budget.io_timeout_ms);
+        let builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        let mut builder = if streaming { builder.read_timeout(io_timeout) } else { builder.timeout(io_timeout) };
+
+        // Names are checked before resolving: a foreign host is rejected without a DNS lookup
+        let by_name = self.guard.decide_sovereignty(url, sensitivity, &[], stage);
+        if !by_name.allowed {
+            self.vault.record_decision(&by_name)?;
+            return Err(KernelError::Sovereignty { endpoint: url.to_string(), reason: by_name.reason });
+        }
+
+        let mut resolved = Vec::new();
+        if sensitivity >= 2
+            && let Ok(parsed) = url::Url::parse(url)
+            && let Some(url::Host::Domain(name)) = parsed.host() {
+            let port = parsed.port_or_known_default().unwrap_or(80);
+            let addrs: Vec<std::net::SocketAddr> = tokio::net::lookup_host((name, port)).await
+                .map_err(|e| KernelError::Runtime(format!("DNS Error for '{}': {}", name, e)))?
+                .collect();
+            if addrs.is_empty() {
+                return Err(KernelError::Runtime(format!("DNS Error for '{}': no addresses", name)));
+            }
+            resolved = addrs.iter().map(|addr| addr.ip()).collect();
+            builder = builder.resolve_to_addrs(name, &addrs);
+        }
+
+        let decision = self.guard.decide_sovereignty(url, sensitivity, &resolved, stage);
+        self.vault.record_decision(&decision)?;
+        if !decision.allowed {
+            return Err(KernelError::Sovereignty { endpoint: url.to_string(), reason: decision.reason });
+        }
+        builder.build().map_err(|e| KernelError::Runtime(format!("HTTP Client Error: {}", e)))
+    }
+}
+
+pub(crate) fn io_error(endpoint: &str, timeout_ms: u64, e: reqwest::Error) -> KernelError {
+    if e.is_timeout() {
+        KernelError::IoTimeout { endpoint: endpoint.to_string(), timeout_ms }
+    } else {
+        KernelError::Runtime(format!("Network Error: {}", e))
+    }
+}
+
+/// Op 1 blob: two little-endian i32 operands
+fn legacy_add(data: &[u8]) -> Result<i32, KernelError> {
+    if data.len() < 8 { return Err(KernelError::Runtime("Invalid data length for ADD".into())); }
+    let a = i32::from_le_bytes(data[0..4].try_into().unwrap());
+    let b = i32::from_le_bytes(data[4..8].try_into().unwrap());
+    a.checked_add(b).ok_or_else(|| KernelError::Arithmetic(format!("Overflow in '{} + {}'", a, b)))
+}
+
+/// Op 800 blob: a masking policy, or nothing for gateways minted before policies existed
+pub(crate) fn masking_policy(data: &[u8]) -> Result<crate::masking::MaskingPolicy, KernelError> {
+    if data.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Default::default());
+    }
+    serde_json::from_slice(data).map_err(|e| KernelError::Runtime(format!("Gateway Config Error: {}", e)))
+}
+
+/// Input 0 as a list; anything else is treated as an empty list
+fn first_list(input_results: Vec<serde_json::Value>) -> Vec<serde_json::Value> {
+    into_list(input_results.into_iter().next())
+}
+
+fn into_list(value: Option<serde_json::Value>) -> Vec<serde_json::Value> {
+    match value {
+        Some(serde_json::Value::Array(items)) => items,
+        _ => Vec::new(),
+    }
+}
+
+pub(crate) fn row_count(value: &serde_json::Value) -> usize {
+    match value {
+        serde_json::Value::Array(items) => items.len(),
+        serde_json::Value::Null => 0,
+        _ => 1,
+    }
+}
+
+/// Byte length of `value` as compact JSON, without allocating the string
+fn serialized_len(value: &serde_json::Value) -> usize {
+    struct Counter(usize);
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut counter = Counter(0);
+    let _ = serde_json::to_writer(&mut counter, value);
+    counter.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    fn atom(vault: &AetherVault, op_code: u16, config: Value, inputs: &[&str]) -> String {
+        let storage_ref = crate::write_blob(config.to_string().as_bytes()).unwrap();
+        vault.persist(&LogicAtom {
+            op_code,
+            inputs: inputs.iter().map(|h| h.to_string()).collect(),
+            storage_ref,
+            context_id: "global".to_string(),
+        }).unwrap()
+    }
+
+    /// A REACTIVE_TRIGGER (Op 50) without inputs echoes its config: a fixed list of rows
+    fn rows(vault: &AetherVault, rows: Value) -> String {
+        atom(vault, 50, rows, &[])
+    }
+
+    fn traced(trace: &TraceNode, hash: &str, cache_hit: bool) -> usize {
+        usize::from(trace.hash == hash && trace.cache_hit == cache_hit)
+            + trace.children.iter().map(|child| traced(child, hash, cache_hit)).sum::<usize>()
+    }
+
+    /// Serves `body` as JSON on a local port and counts the requests it answers
+    async fn serve(body: Value) -> (String, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/rows", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                counter.fetch_add(1, Ordering::SeqCst);
+                let body = body.to_string();
+                tokio::spawn(async move {
+                    let mut request = [0u8; 4096];
+                    let _ = socket.read(&mut request).await;
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body,
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        (endpoint, requests)
+    }
+
+    async fn run(vault: AetherVault, root: &str) -> Value {
+        AetherKernel::new(vault).execute_smart(root).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn sort_orders_numeric_strings_as_numbers() {
+        let vault = AetherVault::temporary();
+        let listings = rows(&vault, json!([{"price": "450000"}, {"price": 90000}, {"name": "no price"}, {"price": "n/a"}]));
+        let sorted = atom(&vault, 4, json!({"keys": [{"field": "price"}]}), &[&listings]);
+        assert_eq!(run(vault, &sorted).await, json!([{"price": 90000}, {"price": "450000"}, {"price": "n/a"}, {"name": "no price"}]));
+    }
+
+    #[tokio::test]
+    async fn sort_then_limit_then_project() {
+        let vault = AetherVault::temporary();
+        let listings = rows(&vault, json!([
+            {"name": "a", "price": 3, "area": 10},
+            {"name": "b", "price": 1, "area": 20},
+            {"name": "c", "price": 2, "area": 30},
+        ]));
+        let sorted = atom(&vault, 4, json!({"keys": [{"field": "price", "descending": true}]}), &[&listings]);
+        let page = atom(&vault, 5, json!({"limit": 1, "offset": 1}), &[&sorted]);
+        let projected = atom(&vault, 6, json!({"fields": [{"field": "name"}, {"field": "price", "as": "cost"}]}), &[&page]);
+        assert_eq!(run(vault, &projected).await, json!([{"name": "c", "cost": 2}]));
+    }
+
+    #[tokio::test]
+    async fn project_can_rename_and_keep_the_rest() {
+        let vault = AetherVault::temporary();
+        let listings = rows(&vault, json!([{"name": "a", "price": 3}]));
+        let renamed = atom(&vault, 6, json!({"fields": [{"field": "price", "as": "cost"}], "keep_others": true}), &[&listings]);
+        let unlimited = atom(&vault, 5, json!({"limit": null, "offset": 0}), &[&renamed]);
+        assert_eq!(run(vault, &unlimited).await, json!([{"name": "a", "cost": 3}]));
+    }
+
+    #[tokio::test]
+    async fn diamond_runs_its_shared_node_once() {
+        let vault = AetherVault::temporary();
+        let (endpoint, requests) = serve(json!([{"price": 1}, {"price": 9}])).await;
+        let source = atom(&vault, 500, json!({"endpoint": endpoint, "schema": {}, "sensitivity": 0}), &[]);
+        let cheap = atom(&vault, 2, json!({"field": "price", "op": "<", "val": 5}), &[&source]);
+        let pricey = atom(&vault, 2, json!({"field": "price", "op": ">", "val": 4}), &[&source]);
+        let root = atom(&vault, 3, json!({}), &[&cheap, &pricey]);
+
+        let kernel = AetherKernel::new(vault);
+        let options = ExecutionOptions { trace: true, ..Default::default() };
+        let report = kernel.execute_with(&root, &options).await;
+        assert_eq!(report.output.unwrap(), json!([{"price": 1}, {"price": 9}]));
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        let trace = report.trace.unwrap();
+        assert_eq!(traced(&trace, &source, false), 1);
+        assert_eq!(traced(&trace, &source, true), 1);
+    }
+
+    #[tokio::test]
+    async fn diamond_shares_its_failure_and_records_it_once() {
+        let vault = AetherVault::temporary();
+        let broken = atom(&vault, 9, json!({"expr": "1 / 0"}), &[]);
+        let left = atom(&vault, 100, json!({}), &[&broken]);
+        let right = atom(&vault, 100, json!({}), &[&broken]);
+        let root = atom(&vault, 3, json!({}), &[&left, &right]);
+
+        let report = AetherKernel::new(vault).execute_with(&root, &ExecutionOptions::default()).await;
+        assert_eq!(report.output.unwrap_err().kind(), "arithmetic");
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].hash, broken);
+    }
+
+    #[tokio::test]
+    async fn merge_fails_before_passing_the_row_limit() {
+        let vault = AetherVault::temporary();
+        let left = rows(&vault, json!([{"k": 1}, {"k": 2}]));
+        let right = rows(&vault, json!([{"k": 3}, {"k": 4}]));
+        let merge = atom(&vault, 3, json!({}), &[&left, &right]);
+
+        let kernel = AetherKernel::with_budget(vault, ExecutionBudget { max_rows_per_node: 3, ..Default::default() });
+        let report = kernel.execute_with(&merge, &ExecutionOptions::default()).await;
+        assert!(matches!(report.output, Err(KernelError::RowLimit { rows: 4, limit: 3, .. })));
+        assert_eq!(report.errors[0].hash, merge);
+    }
+
+    #[tokio::test]
+    async fn join_stops_once_past_the_row_limit() {
+        let vault = AetherVault::temporary();
+        let left = rows(&vault, json!([{"k": 1}, {"k": 1}]));
+        let right = rows(&vault, json!([{"k": 1, "v": 1}, {"k": 1, "v": 2}]));
+        let join = atom(&vault, 8, json!({"on": ["k"]}), &[&left, &right]);
+
+        let kernel = AetherKernel::with_budget(vault, ExecutionBudget { max_rows_per_node: 2, ..Default::default() });
+        let report = kernel.execute_with(&join, &ExecutionOptions::default()).await;
+        assert!(matches!(report.output, Err(KernelError::RowLimit { rows: 3, limit: 2, .. })));
+    }
+
+    #[tokio::test]
+    async fn seeded_nodes_are_not_run_again() {
+        let vault = AetherVault::temporary();
+        let source = rows(&vault, json!([{"price": 1}]));
+        let root = atom(&vault, 3, json!({}), &[&source]);
+        let seed = HashMap::from([(source.clone(), json!([{"price": 2}]))]);
+
+        let kernel = AetherKernel::new(vault);
+        let (report, outputs) = kernel.execute_seeded(&root, &ExecutionOptions::default(), seed).await;
+        assert_eq!(report.output.unwrap(), json!([{"price": 2}]));
+        assert_eq!(outputs[&root], json!([{"price": 2}]));
+    }
 }
@@ -7,6 +7,19 @@ pub mod orchestrator;
 pub mod optimizer;
 pub mod io;
 pub mod product;
+pub mod predicate;
+pub mod pipeline;
+pub mod expr;
+pub mod zakat;
+pub mod finance;
+pub mod source;
+pub mod masking;
+pub mod stream;
+pub mod scheduler;
+pub mod runs;
+pub mod reactive;
+pub mod synthesis;
+pub mod types;
 
 pub use storage::{write_blob, read_blob};
 pub use kernel::AetherKernel;
@@ -19,6 +32,7 @@ pub use optimizer::AetherOptimizer;
 pub use io::IOContract;
 
 pub const OP_PERMISSION: u16 = 10;
+pub const OP_PARAM: u16 = 20;
 pub const OP_GATEWAY: u16 = 800;
 
 use sled::Db;
@@ -48,6 +62,32 @@ pub struct ProjectAtom {
     pub org_hash: String,
     pub status: ProjectStatus,
     pub created_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub budget: Option<kernel::ExecutionBudget>, // The manifest's `budget:`, for runs that don't rebuild it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub laws: Option<Vec<String>>, // The manifest's `laws:`, for graphs rebuilt without it (see synthesis.rs)
+}
+
+/// One execution of a project's root, kept as a numbered output version
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub project: String,
+    pub version: u64, // 1, 2, ... per project, assigned by `record_run`
+    pub root_hash: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule_id: Option<String>,
+    #[serde(default)]
+    pub bindings: std::collections::BTreeMap<String, serde_json::Value>, // Values bound to the root's PARAM atoms
+    pub started_at_us: u64,
+    pub finished_at_us: u64,
+    #[serde(default)]
+    pub output_hash: Option<String>, // BLAKE3 of the output; equal hashes mean equal outputs
+    pub output_ref: Option<String>,  // Blob holding the output, when the run succeeded
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<kernel::TraceNode>,
 }
 
 #[derive(Error, Debug)]
@@ -60,6 +100,8 @@ pub enum VaultError {
     IdentityNotFound,
     #[error("Validation failed: {0}")]
     Validation(String),
+    #[error("Violation of law '{law}' v{version} by Op {op_code}: {reason}")]
+    LawViolation { law: String, version: u32, op_code: u16, reason: String },
 }
 
 /// The fundamental unit of the Aether-Grid
@@ -87,6 +129,12 @@ impl AetherVault {
         Ok(Self { db })
     }
 
+    /// A vault that is deleted when dropped
+    #[cfg(test)]
+    pub(crate) fn temporary() -> Self {
+        Self { db: sled::Config::new().temporary(true).open().unwrap() }
+    }
+
     /// Persists a LogicAtom and returns its unique BLAKE3 hash
     pub fn persist(&self, atom: &LogicAtom) -> Result<String, VaultError> {
         let serialized = serde_json::to_vec(atom).unwrap();
@@ -140,18 +188,43 @@ impl AetherVault {
         let blob = storage::read_blob(&atom.storage_ref)
             .map_err(|e| VaultError::Validation(format!("Blob Load Error: {}", e)))?;
 
-        // If it's a financial op, verify 0% Riba
-        if atom.op_code == 100 {
-             if !guard.verify_interest_free(extract_rate(&blob)) {
-                 return Err(VaultError::Validation("Violation of Genesis Law: Riba Detected".to_string()));
-             }
+        // If it's a filter, the predicate must parse and compile (regexes, list operands)
+        if atom.op_code == 2 {
+            let predicate: predicate::Predicate = serde_json::from_slice(&blob)
+                .map_err(|e| VaultError::Validation(format!("Invalid Filter predicate: {}", e)))?;
+            predicate.compile().map_err(VaultError::Validation)?;
         }
-        
-        // If it's an IO op, verify sovereignty
+
+        // If it's a compute node, the expression must parse
+        if atom.op_code == 9 {
+            let config: expr::ComputeConfig = serde_json::from_slice(&blob)
+                .map_err(|e| VaultError::Validation(format!("Invalid Compute config: {}", e)))?;
+            expr::Expr::parse(&config.expr)
+                .map_err(|e| VaultError::Validation(format!("Invalid Compute expression '{}': {}", config.expr, e)))?;
+        }
+
+        // If it's a gateway, its masking policy must be usable
+        if atom.op_code == OP_GATEWAY {
+            kernel::masking_policy(&blob)
+                .map_err(|e| VaultError::Validation(e.to_string()))?
+                .validate()
+                .map_err(VaultError::Validation)?;
+        }
+
+        // If it's a parameter, the blob must be its input schema
+        if atom.op_code == OP_PARAM {
+            serde_json::from_slice::<InputSchema>(&blob)
+                .map_err(|e| VaultError::Validation(format!("Invalid Param schema: {}", e)))?;
+        }
+
+        // If it's an IO op, the contract and its schema must be usable
         if atom.op_code == 500 {
             if let Ok(contract) = serde_json::from_slice::<crate::IOContract>(&blob) {
-                 if !guard.verify_sovereignty(&contract.endpoint, contract.sensitivity) {
-                     return Err(VaultError::Validation("Violation of Sovereignty Law: Sovereign data must stay in .my or localhost".to_string()));
+                 if let Err(e) = jsonschema::validator_for(&contract.schema) {
+                     return Err(VaultError::Validation(format!("Invalid IO schema: {}", e)));
+                 }
+                 for name in contract.secret_names() {
+                     io::check_secret_name(name).map_err(VaultError::Validation)?;
                  }
             } else {
                  return Err(VaultError::Validation("Invalid IO Contract data".to_string()));
@@ -177,12 +250,25 @@ impl AetherVault {
         // Guard: Static Analysis
         guard.verify_compatibility(atom, &input_atoms)
             .map_err(|e: anyhow::Error| VaultError::Validation(e.to_string()))?;
-            
-        // Restore IO Sovereignty Check
-        if atom.op_code == 500 {
-             // We need to parse storage to get endpoint. But storage is ref.
-             // For now, skip deep inspection here to avoid overhead, relying on Kernel runtime check.
-             // Or verify logic graph compatibility is enough for now.
+
+        // If it's a zakat node, its inputs must hold the assets and the metal price it reads
+        // (the rate and nisab rules are the `zakat` law's)
+        if atom.op_code == 101 {
+            let config: zakat::ZakatConfig = serde_json::from_slice(&blob)
+                .map_err(|e| VaultError::Validation(format!("Invalid Zakat config: {}", e)))?;
+            let data_inputs = input_atoms.iter().filter(|a| a.op_code != OP_PARAM).count();
+            config.check_inputs(data_inputs).map_err(VaultError::Validation)?;
+        }
+
+        // Laws: exactly those the guard enforces (a manifest's `laws:`), e.g. no_riba, data_sovereignty
+        let ctx = guard::LawContext { atom, blob: &blob, inputs: &input_atoms, guard, vault: self };
+        for law in guard.laws().iter().filter(|law| law.applies_to(atom.op_code)) {
+            law.verify(&ctx).map_err(|reason| VaultError::LawViolation {
+                law: law.name().to_string(),
+                version: law.version(),
+                op_code: atom.op_code,
+                reason,
+            })?;
         }
 
         // 3. Hash & Store
@@ -190,6 +276,11 @@ impl AetherVault {
         let hash = blake3::hash(&data).to_string();
 
         self.db.insert(hash.as_bytes(), data)?;
+
+        // Logic the Loom could not weave waits in the synthesis queue
+        if atom.op_code == synthesis::OP_SYNTHESIS {
+            self.queue_gap(&hash, atom)?;
+        }
         Ok(hash)
     }
 
@@ -217,6 +308,200 @@ impl AetherVault {
         Ok(project.name.clone())
     }
 
+    /// Appends a sovereignty decision to the audit trail (`AUDIT:` keys sort by time)
+    pub fn record_decision(&self, decision: &guard::SovereigntyDecision) -> Result<(), VaultError> {
+        let key = format!("AUDIT:{:020}:{:020}", decision.at_us, self.db.generate_id()?);
+        self.db.insert(key.as_bytes(), serde_json::to_vec(decision).unwrap())?;
+        Ok(())
+    }
+
+    /// Links an IO contract hash to the blob holding its recorded response
+    pub fn record_io(&self, contract_hash: &str, blob_uri: &str) -> Result<(), VaultError> {
+        self.db.insert(format!("IOREC:{}", contract_hash).as_bytes(), blob_uri.as_bytes())?;
+        Ok(())
+    }
+
+    /// Blob URI of the recorded response for an IO contract hash, if any
+    pub fn recorded_io(&self, contract_hash: &str) -> Result<Option<String>, VaultError> {
+        let uri = self.db.get(format!("IOREC:{}", contract_hash).as_bytes())?;
+        Ok(uri.map(|bytes| String::from_utf8_lossy(&bytes).to_string()))
+    }
+
+    /// Stores a run as the project's next output version and returns that version
+    pub fn record_run(&self, record: &mut RunRecord) -> Result<u64, VaultError> {
+        let counter = self.db.update_and_fetch(format!("RUNSEQ:{}", record.project).as_bytes(), |old| {
+            let last = old.and_then(|bytes| bytes.try_into().ok()).map(u64::from_be_bytes).unwrap_or(0);
+            Some((last + 1).to_be_bytes().to_vec())
+        })?;
+        record.version = counter.and_then(|bytes| bytes.as_ref().try_into().ok()).map(u64::from_be_bytes).unwrap_or(1);
+        let key = format!("RUN:{}:{:020}", record.project, record.version);
+        self.db.insert(key.as_bytes(), serde_json::to_vec(record).unwrap())?;
+        Ok(record.version)
+    }
+
+    pub fn get_run(&self, project: &str, version: u64) -> Result<RunRecord, VaultError> {
+        match self.db.get(format!("RUN:{}:{:020}", project, version).as_bytes())? {
+            Some(data) => serde_json::from_slice(&data).map_err(|e| VaultError::Validation(e.to_string())),
+            None => Err(VaultError::NotFound),
+        }
+    }
+
+    /// A project's runs, newest version first
+    pub fn list_runs(&self, project: &str, limit: usize) -> Result<Vec<RunRecord>, VaultError> {
+        let mut runs = Vec::new();
+        for item in self.db.scan_prefix(format!("RUN:{}:", project).as_bytes()).rev() {
+            let (_, value) = item?;
+            match serde_json::from_slice::<RunRecord>(&value) {
+                Ok(run) if run.project == project => runs.push(run),
+                _ => {},
+            }
+            if runs.len() >= limit {
+                break;
+            }
+        }
+        Ok(runs)
+    }
+
+    // --- Schedules (`SCHED:{id}`) ---
+    pub fn persist_schedule(&self, schedule: &scheduler::Schedule) -> Result<(), VaultError> {
+        self.db.insert(format!("SCHED:{}", schedule.id).as_bytes(), serde_json::to_vec(schedule).unwrap())?;
+        Ok(())
+    }
+
+    pub fn get_schedule(&self, id: &str) -> Result<scheduler::Schedule, VaultError> {
+        match self.db.get(format!("SCHED:{}", id).as_bytes())? {
+            Some(data) => serde_json::from_slice(&data).map_err(|e| VaultError::Validation(e.to_string())),
+            None => Err(VaultError::NotFound),
+        }
+    }
+
+    pub fn list_schedules(&self) -> Result<Vec<scheduler::Schedule>, VaultError> {
+        let mut schedules = Vec::new();
+        for item in self.db.scan_prefix("SCHED:") {
+            let (_, value) = item?;
+            if let Ok(schedule) = serde_json::from_slice(&value) {
+                schedules.push(schedule);
+            }
+        }
+        Ok(schedules)
+    }
+
+    /// Returns false if there was no such schedule
+    pub fn delete_schedule(&self, id: &str) -> Result<bool, VaultError> {
+        Ok(self.db.remove(format!("SCHED:{}", id).as_bytes())?.is_some())
+    }
+
+    /// Moves a schedule's clock forward without touching its other (possibly just edited) fields.
+    /// A schedule deleted meanwhile stays deleted.
+    pub fn advance_schedule(&self, id: &str, last_run_us: u64, next_run_us: u64) -> Result<(), VaultError> {
+        self.patch_schedule(id, |schedule| {
+            schedule.last_run_us = Some(last_run_us);
+            schedule.next_run_us = next_run_us;
+        })
+    }
+
+    /// Sets (or clears) why a schedule's last tick stored no run, the same way as `advance_schedule`
+    pub fn note_schedule_error(&self, id: &str, last_error: Option<String>) -> Result<(), VaultError> {
+        self.patch_schedule(id, |schedule| schedule.last_error = last_error.clone())
+    }
+
+    fn patch_schedule(&self, id: &str, patch: impl Fn(&mut scheduler::Schedule)) -> Result<(), VaultError> {
+        self.db.update_and_fetch(format!("SCHED:{}", id).as_bytes(), |old| {
+            let old = old?;
+            let Ok(mut schedule) = serde_json::from_slice::<scheduler::Schedule>(old) else {
+                return Some(old.to_vec());
+            };
+            patch(&mut schedule);
+            Some(serde_json::to_vec(&schedule).unwrap())
+        })?;
+        Ok(())
+    }
+
+    // --- Synthesis Gaps (`GAP:{hash}`) ---
+    /// Queues a SYNTHESIS_REQUIRED atom; a gap already queued (or resolved) is left as it is
+    pub fn queue_gap(&self, hash: &str, atom: &LogicAtom) -> Result<(), VaultError> {
+        let gap = synthesis::Gap::from_atom(hash, atom);
+        // A gap queued before keeps its original record: the swap only fills an empty key
+        let _ = self.db.compare_and_swap(format!("GAP:{}", hash).as_bytes(), None as Option<&[u8]>, Some(serde_json::to_vec(&gap).unwrap()))?;
+        Ok(())
+    }
+
+    pub fn persist_gap(&self, gap: &synthesis::Gap) -> Result<(), VaultError> {
+        self.db.insert(format!("GAP:{}", gap.hash).as_bytes(), serde_json::to_vec(gap).unwrap())?;
+        Ok(())
+    }
+
+    /// Stores a resolved gap and moves each project it relinked to its new root, all or nothing.
+    /// A project whose root changed since it was relinked, or a gap resolved meanwhile, aborts.
+    pub fn commit_resolution(&self, gap: &synthesis::Gap) -> Result<(), VaultError> {
+        use sled::transaction::{ConflictableTransactionError, TransactionError};
+        let abort = |reason: String| ConflictableTransactionError::Abort(reason);
+        let committed = self.db.transaction(|tx| {
+            let gap_key = format!("GAP:{}", gap.hash);
+            if let Some(stored) = tx.get(gap_key.as_bytes())?
+                && let Ok(stored) = serde_json::from_slice::<synthesis::Gap>(&stored)
+                && let Some(by) = stored.resolved_by {
+                return Err(abort(format!("Gap '{}' was resolved meanwhile by {}", gap.hash, by)));
+            }
+            for relink in &gap.relinked {
+                let Some(name) = &relink.project else {
+                    continue;
+                };
+                let key = format!("PROJ:{}", name);
+                let mut project: ProjectAtom = match tx.get(key.as_bytes())? {
+                    Some(data) => serde_json::from_slice(&data).map_err(|e| abort(e.to_string()))?,
+                    None => return Err(abort(format!("Project '{}' no longer exists", name))),
+                };
+                if project.root_hash != relink.old_root {
+                    return Err(abort(format!("Project '{}' moved to {} while the gap was resolved", name, project.root_hash)));
+                }
+                project.root_hash = relink.new_root.clone();
+                tx.insert(key.as_bytes(), serde_json::to_vec(&project).unwrap())?;
+            }
+            tx.insert(gap_key.as_bytes(), serde_json::to_vec(gap).unwrap())?;
+            Ok(())
+        });
+        committed.map_err(|e| match e {
+            TransactionError::Abort(reason) => VaultError::Validation(reason),
+            TransactionError::Storage(e) => VaultError::Storage(e),
+        })
+    }
+
+    pub fn get_gap(&self, hash: &str) -> Result<synthesis::Gap, VaultError> {
+        match self.db.get(format!("GAP:{}", hash).as_bytes())? {
+            Some(data) => serde_json::from_slice(&data).map_err(|e| VaultError::Validation(e.to_string())),
+            None => Err(VaultError::NotFound),
+        }
+    }
+
+    pub fn list_gaps(&self) -> Result<Vec<synthesis::Gap>, VaultError> {
+        let mut gaps = Vec::new();
+        for item in self.db.scan_prefix("GAP:") {
+            let (_, value) = item?;
+            if let Ok(gap) = serde_json::from_slice(&value) {
+                gaps.push(gap);
+            }
+        }
+        Ok(gaps)
+    }
+
+    /// A fresh, unique id from the store (monotonic)
+    pub fn generate_id(&self) -> Result<u64, VaultError> {
+        Ok(self.db.generate_id()?)
+    }
+
+    /// Most recent audit entries first
+    pub fn list_audit(&self, limit: usize) -> Result<Vec<guard::SovereigntyDecision>, VaultError> {
+        let mut decisions = Vec::new();
+        for item in self.db.scan_prefix("AUDIT:").rev().take(limit) {
+            let (_, value) = item?;
+            if let Ok(decision) = serde_json::from_slice(&value) {
+                decisions.push(decision);
+            }
+        }
+        Ok(decisions)
+    }
+
     pub fn list_projects(&self) -> Result<Vec<ProjectAtom>, VaultError> {
         let mut projects = Vec::new();
         let prefix = "PROJ:";
@@ -366,10 +651,3 @@ impl AetherVault {
         dot
     }
 }
-
-fn extract_rate(data: &[u8]) -> i32 {
-    if data.len() < 4 { return 0; }
-    let mut arr = [0u8; 4];
-    arr.copy_from_slice(&data[0..4]);
-    i32::from_le_bytes(arr)
-}
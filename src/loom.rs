@@ -1,5 +1,6 @@
 use anyhow::{Result, Ok};
 use crate::{LogicAtom, write_blob};
+use crate::predicate::{CompareOp, Predicate};
 
 // Placeholder for Candle-based LLM state
 pub struct AetherLoom {
@@ -22,6 +23,18 @@ impl AetherLoom {
         self.weave_with_context(intent, "global")
     }
     
+    /// Stores a JSON config blob and wraps it in an atom of the given opcode
+    fn config_atom<T: serde::Serialize>(&self, op_code: u16, config: &T, context: &str) -> Result<LogicAtom> {
+        let blob = serde_json::to_vec(config)?;
+        let ref_uri = write_blob(&blob)?;
+        Ok(LogicAtom {
+            op_code,
+            inputs: vec![],
+            storage_ref: ref_uri,
+            context_id: context.to_string(),
+        })
+    }
+
     pub fn weave_with_context(&self, intent: &str, context: &str) -> Result<LogicAtom> {
         println!("[Loom] Processing Intent: '{}' in context '{}'", intent, context);
 
@@ -35,11 +48,9 @@ impl AetherLoom {
              if let Some(url_idx) = parts.iter().position(|&x| x == "from") {
                  if url_idx + 1 < parts.len() {
                      let url = parts[url_idx+1];
-                     let contract = crate::IOContract {
-                         endpoint: url.to_string(),
-                         schema: serde_json::json!({"type": "array"}),
-                         sensitivity: if url.contains("localhost") || url.contains("127.0.0.1") { 2 } else { 0 },
-                     };
+                     let local = url.contains("localhost") || url.contains("127.0.0.1") || crate::source::is_local(url);
+                     let sensitivity = if local { 2 } else { 0 };
+                     let contract = crate::IOContract::get(url, serde_json::json!({"type": "array"}), sensitivity);
                      
                      let blob = serde_json::to_vec(&contract)?;
                      let ref_uri = write_blob(&blob)?;
@@ -54,28 +65,13 @@ impl AetherLoom {
              }
         }
 
-        // 2. Generic Filter: "Filter where <field> <op> <value>"
+        // 2. Generic Filter: "Filter where <field> <op> <value> [and|or ...]"
         // Example: "Filter where built > 2020"
-        if parts[0] == "Filter" && parts.get(1) == Some(&"where") && parts.len() >= 5 {
-             let field = parts[2];
-             let op = parts[3];
-             // Support multi-word values (e.g. "Bukit Bintang")
-             let val_str = parts[4..].join(" ");
-             
-             // Try parsing val as number, else string
-             let val_json = if let std::result::Result::Ok(num) = val_str.parse::<i64>() {
-                 serde_json::to_value(num)?
-             } else {
-                 serde_json::to_value(&val_str)?
-             };
+        // Example: "Filter where price <= 500000 and station_type in LRT, MRT"
+        if parts[0] == "Filter" && parts.get(1) == Some(&"where") && parts.len() >= 4 {
+             let predicate = parse_predicate(&parts[2..])?;
 
-             let config = serde_json::json!({
-                "field": field,
-                "op": op,
-                "val": val_json
-             });
-
-             let blob = serde_json::to_vec(&config)?;
+             let blob = serde_json::to_vec(&predicate)?;
              let ref_uri = write_blob(&blob)?;
 
              return Ok(LogicAtom {
@@ -86,10 +82,14 @@ impl AetherLoom {
             });
         }
         
-        // 3. Generic Financial: "Verify ..." (Placeholder)
+        // 3. Generic Financial: "Verify financing of 1000 repaid in 12 payments of 90 with 80 profit"
+        // Terms the Guard checks for riba (see finance.rs); without them, an identity with an empty blob
         if parts[0] == "Verify" {
-             // Just identity, empty blob
-             let ref_uri = write_blob(&[])?;
+             let blob = match crate::finance::FinancialContract::parse_intent(intent) {
+                 Some(contract) => serde_json::to_vec(&contract)?,
+                 None => Vec::new(),
+             };
+             let ref_uri = write_blob(&blob)?;
              return Ok(LogicAtom {
                  op_code: 100, 
                  inputs: vec![],
@@ -125,11 +125,7 @@ impl AetherLoom {
         if parts[0] == "Web" && parts.contains(&"scrape") {
              // Hardcode the mock scraper URL for this demo intent
              let url = "http://127.0.0.1:8080/kl/properties";
-             let contract = crate::IOContract {
-                 endpoint: url.to_string(),
-                 schema: serde_json::json!({"type": "array"}),
-                 sensitivity: 2,
-             };
+             let contract = crate::IOContract::get(url, serde_json::json!({"type": "array"}), 2);
              
              let blob = serde_json::to_vec(&contract)?;
              let ref_uri = write_blob(&blob)?;
@@ -145,11 +141,10 @@ impl AetherLoom {
         // 7. Trigger/Event: "Trigger data refresh when <event>"
         // Example: "Trigger data refresh when the dropdown value changes"
         if parts[0] == "Trigger" {
-             // We can parse the event details here or just store the raw intent for the UI to interpret
-             // For now, let's treat it as a Reactive Binding (OpCode 50)
-             let blob = serde_json::to_vec(&serde_json::json!({
-                 "event": intent
-             }))?;
+             // Input-change and interval events are parsed for the ReactiveHub; anything else
+             // (e.g. a click) stays raw for the UI to interpret
+             let on = crate::reactive::parse_event(intent);
+             let blob = serde_json::to_vec(&crate::reactive::TriggerConfig { event: intent.to_string(), on })?;
              let ref_uri = write_blob(&blob)?;
              
              return Ok(LogicAtom {
@@ -161,6 +156,92 @@ impl AetherLoom {
         }
 
 
+        // 8. Sort: "Sort by <field> [asc|desc] [numeric|text], ..."
+        // Example: "Sort by price ascending", "Sort by price desc, name"
+        if parts[0] == "Sort" && parts.get(1) == Some(&"by") && parts.len() >= 3 {
+             let config = parse_sort(&parts[2..].join(" "))?;
+             return self.config_atom(4, &config, context); // SORT
+        }
+
+        // 9. Limit/Offset: "Take top 10", "Limit to 10", "Skip 20 and take 10"
+        if matches!(parts[0], "Take" | "Limit" | "Skip")
+            && let Some(config) = parse_limit(&parts) {
+             return self.config_atom(5, &config, context); // LIMIT
+        }
+
+        // 10. Projection: "Keep only name, price as cost" / Rename: "Rename price to cost"
+        if (parts[0] == "Keep" || parts[0] == "Select") && parts.len() >= 2 {
+             let skip = if parts.get(1) == Some(&"only") { 2 } else { 1 };
+             let fields = parse_field_list(&parts[skip..].join(" "), "as")?;
+             let config = crate::pipeline::ProjectConfig { fields, keep_others: false };
+             return self.config_atom(6, &config, context); // PROJECT
+        }
+        if parts[0] == "Rename" && parts.len() >= 4 {
+             let fields = parse_field_list(&parts[1..].join(" "), "to")?;
+             let config = crate::pipeline::ProjectConfig { fields, keep_others: true };
+             return self.config_atom(6, &config, context); // PROJECT
+        }
+
+        // 11. Aggregation: "Group by <fields> and <agg> [<field>] [and ...]"
+        // Example: "Group by station_type and average price", "Summarize count and max price"
+        if (parts[0] == "Group" && parts.get(1) == Some(&"by") && parts.len() >= 3) || parts[0] == "Summarize" {
+             let config = if parts[0] == "Group" {
+                 let mut segments = split_on_word(&parts[2..], "and");
+                 let group_by: Vec<String> = segments.remove(0).join(" ")
+                     .split(',').map(|f| f.trim().to_string()).filter(|f| !f.is_empty()).collect();
+                 parse_aggregates(group_by, &segments)?
+             } else {
+                 parse_aggregates(vec![], &split_on_word(&parts[1..], "and"))?
+             };
+             return self.config_atom(7, &config, context); // AGGREGATE
+        }
+
+        // 12. Join: "[Left|Anti] Join on <key>[, <left>=<right>] [with prefix <p>]"
+        // Example: "Join on station_name", "Left join on station = name"
+        if let Some(join_at) = parts.iter().take(2).position(|w| w.eq_ignore_ascii_case("join"))
+            && parts.get(join_at + 1) == Some(&"on")
+            && (join_at == 0 || matches!(parts[0], "Left" | "Anti" | "Inner")) {
+             let config = parse_join(&parts[0].to_ascii_lowercase(), &parts[join_at + 2..])?;
+             return self.config_atom(8, &config, context); // JOIN
+        }
+
+        // 13. Compute: "Compute <name> as <expression>" or "Compute <expression>"
+        // Example: "Compute price_per_sqft as price / area", "Compute $0 * 1.1"
+        if parts[0] == "Compute" && parts.len() >= 2 {
+             let config = match parts.iter().position(|w| *w == "as") {
+                 Some(2) if parts.len() > 3 => crate::expr::ComputeConfig {
+                     expr: parts[3..].join(" "),
+                     alias: Some(parts[1].to_string()),
+                 },
+                 _ => crate::expr::ComputeConfig { expr: parts[1..].join(" "), alias: None },
+             };
+             crate::expr::Expr::parse(&config.expr)
+                 .map_err(|e| anyhow::anyhow!("Invalid expression '{}': {}", config.expr, e))?;
+             return self.config_atom(9, &config, context); // COMPUTE
+        }
+
+        // 14. Zakat: "Calculate Zakat [for <balance>] with nisab <amount> | with gold|silver nisab [at <price>] [at rate <r>]"
+        // Example: "Calculate Zakat for 5000 with nisab 2000", "Calculate Zakat with gold nisab"
+        // Without a nisab there is nothing to weigh the wealth against: it is left to synthesis
+        if parts[0] == "Calculate" && parts.get(1).is_some_and(|w| w.eq_ignore_ascii_case("zakat"))
+            && let Some(config) = parse_zakat(&parts[2..])? {
+             return self.config_atom(101, &config, context); // ZAKAT
+        }
+
+        // 15. Local SQL: "Query <sqlite://path> for <SQL>"
+        // Example: "Query sqlite://data/listings.db for SELECT name, price FROM listings"
+        if parts[0] == "Query" && parts.len() >= 4 && parts[1].starts_with(crate::source::SQLITE_SCHEME) && parts[2] == "for" {
+             let contract = crate::IOContract::sqlite(parts[1], &parts[3..].join(" "), serde_json::json!({"type": "array"}), 2);
+             return self.config_atom(500, &contract, context); // IO
+        }
+
+        // 16. Gateway: "Gateway mask <field> [as drop|redact|hash|truncate <n>|generalize <bucket>], ... [with salt <secret>]"
+        // Example: "Gateway mask ic_number as hash, salary as generalize 1000, notes as drop"
+        if parts[0] == "Gateway" && (parts.len() == 1 || parts[1] == "mask") {
+             let policy = parse_masking(parts.get(2..).unwrap_or_default())?;
+             return self.config_atom(crate::OP_GATEWAY, &policy, context); // GATEWAY
+        }
+
         // Fallback: Legacy "Add X and Y"
         if parts[0] == "Add" && parts.len() >= 4 {
              let a: i32 = parts[1].parse().unwrap_or(0);
@@ -193,3 +274,343 @@ impl AetherLoom {
         })
     }
 }
+
+fn parse_sort(spec: &str) -> Result<crate::pipeline::SortConfig> {
+    use crate::pipeline::{SortKey, SortMode};
+
+    let mut keys = Vec::new();
+    for key in spec.split(',').flat_map(|k| k.split(" then ")) {
+        let words: Vec<&str> = key.split_whitespace().collect();
+        let Some((field, modifiers)) = words.split_first() else { continue };
+        let mut sort_key = SortKey { field: field.to_string(), descending: false, mode: SortMode::Auto };
+        for modifier in modifiers {
+            match modifier.to_ascii_lowercase().as_str() {
+                "asc" | "ascending" => sort_key.descending = false,
+                "desc" | "descending" => sort_key.descending = true,
+                "numeric" | "numerically" => sort_key.mode = SortMode::Numeric,
+                "text" | "string" | "alphabetically" => sort_key.mode = SortMode::String,
+                other => return Err(anyhow::anyhow!("Unknown sort modifier '{}' for '{}'", other, field)),
+            }
+        }
+        keys.push(sort_key);
+    }
+    if keys.is_empty() {
+        return Err(anyhow::anyhow!("Sort needs at least one field"));
+    }
+    Ok(crate::pipeline::SortConfig { keys })
+}
+
+/// First bare number is the limit; a number after skip/offset/after is the offset
+fn parse_limit(parts: &[&str]) -> Option<crate::pipeline::LimitConfig> {
+    let mut limit = None;
+    let mut offset = None;
+    let mut words = parts.iter().peekable();
+    while let Some(word) = words.next() {
+        let lower = word.to_ascii_lowercase();
+        if matches!(lower.as_str(), "skip" | "offset" | "after") {
+            if let Some(n) = words.peek().and_then(|w| w.parse::<usize>().ok()) {
+                offset = Some(n);
+                words.next();
+            }
+        } else if let std::result::Result::Ok(n) = word.parse::<usize>() {
+            limit = limit.or(Some(n));
+        }
+    }
+    if limit.is_none() && offset.is_none() {
+        return None;
+    }
+    Some(crate::pipeline::LimitConfig { limit, offset: offset.unwrap_or(0) })
+}
+
+/// "for 5000 with silver nisab at rate 2.5%" or "... with gold nisab at 350" (a per-gram price);
+/// without a balance, assets come from input 0. None if the intent states no nisab.
+fn parse_zakat(words: &[&str]) -> Result<Option<crate::zakat::ZakatConfig>> {
+    let decimal = |raw: &str| -> Result<rust_decimal::Decimal> {
+        raw.trim_end_matches('%').replace(',', "").parse::<rust_decimal::Decimal>()
+            .map_err(|_| anyhow::anyhow!("Invalid Zakat amount '{}'", raw))
+    };
+    let mut balance = None;
+    let mut rate = None;
+    let mut nisab = None;
+    let mut i = 0;
+    while i < words.len() {
+        match (words[i], words.get(i + 1), words.get(i + 2)) {
+            ("for" | "on", Some(amount), _) => {
+                balance = Some(decimal(amount)?);
+                i += 2;
+            },
+            ("with", Some(&"nisab"), Some(amount)) => {
+                nisab = Some(crate::zakat::Nisab::Fixed { fixed: decimal(amount)? });
+                i += 3;
+            },
+            ("with", Some(metal @ (&"gold" | &"silver")), Some(&"nisab")) => {
+                let metal = if *metal == "gold" { crate::zakat::Metal::Gold } else { crate::zakat::Metal::Silver };
+                // "at <price>" declares the per-gram price; otherwise it is read from an input
+                let price = match (words.get(i + 3), words.get(i + 4)) {
+                    (Some(&"at"), Some(raw)) if *raw != "rate" => Some(decimal(raw)?),
+                    _ => None,
+                };
+                nisab = Some(crate::zakat::Nisab::Metal { metal, grams: None, price, price_input: None, price_field: "price".to_string() });
+                i += if price.is_some() { 5 } else { 3 };
+            },
+            ("at", Some(&"rate"), Some(raw)) => {
+                let value = decimal(raw)?;
+                rate = Some(if raw.ends_with('%') { value / rust_decimal::Decimal::ONE_HUNDRED } else { value });
+                i += 3;
+            },
+            (other, _, _) => return Err(anyhow::anyhow!("Unexpected '{}' in Zakat intent", other)),
+        }
+    }
+    Ok(nisab.map(|nisab| crate::zakat::ZakatConfig {
+        balance,
+        amount_field: "amount".to_string(),
+        class_field: "class".to_string(),
+        rate: rate.unwrap_or(rust_decimal::Decimal::new(25, 3)),
+        nisab,
+    }))
+}
+
+/// "name, price as cost" (or "price to cost" for renames)
+fn parse_field_list(spec: &str, alias_word: &str) -> Result<Vec<crate::pipeline::ProjectField>> {
+    let mut fields = Vec::new();
+    for item in spec.split(',') {
+        let words: Vec<&str> = item.split_whitespace().collect();
+        match words.as_slice() {
+            [] => continue,
+            [field] => fields.push(crate::pipeline::ProjectField { field: field.to_string(), alias: None }),
+            [field, word, alias] if *word == alias_word => fields.push(crate::pipeline::ProjectField {
+                field: field.to_string(),
+                alias: Some(alias.to_string()),
+            }),
+            _ => return Err(anyhow::anyhow!("Cannot read field '{}' (expected '<field>' or '<field> {} <name>')", item.trim(), alias_word)),
+        }
+    }
+    if fields.is_empty() {
+        return Err(anyhow::anyhow!("No fields listed"));
+    }
+    Ok(fields)
+}
+
+/// "ic as hash, name as truncate 3, salary as generalize 1000 with salt AETHER_SECRET_MASK_SALT"; a bare field is redacted
+fn parse_masking(words: &[&str]) -> Result<crate::masking::MaskingPolicy> {
+    use crate::masking::{FieldRule, MaskAction};
+    let mut words = words.to_vec();
+    let mut salt_secret = None;
+    if let Some(at) = words.windows(2).position(|w| w == ["with", "salt"]) {
+        salt_secret = words.get(at + 2).map(|s| s.to_string());
+        words.truncate(at);
+    }
+    let mut fields = Vec::new();
+    for item in words.join(" ").split(',') {
+        let item: Vec<&str> = item.split_whitespace().collect();
+        let number = |raw: Option<&&str>| raw.and_then(|r| r.parse::<f64>().ok())
+            .ok_or_else(|| anyhow::anyhow!("Mask '{}' needs a number", item.join(" ")));
+        let action = match item.as_slice() {
+            [] => continue,
+            [_] | [_, "as", "redact"] => MaskAction::Redact { with: "***".to_string() },
+            [_, "as", "drop"] => MaskAction::Drop,
+            [_, "as", "hash"] => MaskAction::Hash,
+            [_, "as", "truncate", ..] => MaskAction::Truncate { keep: number(item.get(3))? as usize },
+            [_, "as", "generalize", ..] => MaskAction::Generalize { bucket: number(item.get(3))? },
+            _ => return Err(anyhow::anyhow!("Unknown mask '{}'", item.join(" "))),
+        };
+        fields.push(FieldRule { field: item[0].to_string(), action });
+    }
+    Ok(crate::masking::MaskingPolicy { fields, salt_secret })
+}
+
+fn parse_join(kind: &str, words: &[&str]) -> Result<crate::pipeline::JoinConfig> {
+    use crate::pipeline::{JoinConfig, JoinKey, JoinKind};
+
+    let kind = match kind {
+        "left" => JoinKind::Left,
+        "anti" => JoinKind::Anti,
+        _ => JoinKind::Inner,
+    };
+    let (keys, prefix) = match words.iter().position(|w| *w == "with") {
+        Some(at) if words.get(at + 1) == Some(&"prefix") && at + 2 < words.len() => (&words[..at], Some(words[at + 2].to_string())),
+        _ => (words, None),
+    };
+    let on: Vec<JoinKey> = keys.join(" ").split(',')
+        .map(str::trim)
+        .filter(|k| !k.is_empty())
+        .map(|k| match k.split_once('=') {
+            Some((left, right)) => JoinKey::Pair { left: left.trim().to_string(), right: right.trim().to_string() },
+            None => JoinKey::Same(k.to_string()),
+        })
+        .collect();
+    if on.is_empty() {
+        return Err(anyhow::anyhow!("Join needs at least one key field"));
+    }
+    Ok(JoinConfig { kind, on, prefix: prefix.unwrap_or_else(|| "right_".to_string()) })
+}
+
+fn split_on_word<'a>(words: &[&'a str], separator: &str) -> Vec<Vec<&'a str>> {
+    words.split(|w| w.eq_ignore_ascii_case(separator)).map(|group| group.to_vec()).collect()
+}
+
+/// Each segment is "<func> [of] [<field>] [as <name>]", e.g. "average price", "count distinct station"
+fn parse_aggregates(group_by: Vec<String>, segments: &[Vec<&str>]) -> Result<crate::pipeline::AggregateConfig> {
+    use crate::pipeline::{Aggregate, AggregateFn};
+
+    let mut aggregates = Vec::new();
+    let phrases: Vec<String> = segments.iter()
+        .flat_map(|segment| segment.join(" ").split(',').map(|p| p.trim().to_string()).collect::<Vec<_>>())
+        .filter(|p| !p.is_empty())
+        .collect();
+    for phrase in &phrases {
+        let words: Vec<&str> = phrase.split_whitespace().collect();
+        let lower = phrase.to_ascii_lowercase();
+        let (func, used) = if lower.starts_with("count distinct") || lower.starts_with("distinct count") {
+            (AggregateFn::DistinctCount, 2)
+        } else {
+            let func = match lower.split_whitespace().next().unwrap_or("") {
+                "count" => AggregateFn::Count,
+                "sum" | "total" => AggregateFn::Sum,
+                "average" | "avg" | "mean" => AggregateFn::Avg,
+                "min" | "minimum" | "lowest" => AggregateFn::Min,
+                "max" | "maximum" | "highest" => AggregateFn::Max,
+                _ => return Err(anyhow::anyhow!("Unknown aggregate '{}'", phrase)),
+            };
+            (func, 1)
+        };
+        let mut rest = &words[used..];
+        if rest.first() == Some(&"of") {
+            rest = &rest[1..];
+        }
+        let (field, alias) = match rest {
+            [] => (None, None),
+            [field] => (Some(field.to_string()), None),
+            [field, "as", alias] => (Some(field.to_string()), Some(alias.to_string())),
+            ["as", alias] => (None, Some(alias.to_string())),
+            _ => return Err(anyhow::anyhow!("Cannot read aggregate '{}'", phrase)),
+        };
+        if field.is_none() && func != AggregateFn::Count {
+            return Err(anyhow::anyhow!("Aggregate '{}' needs a field", phrase));
+        }
+        aggregates.push(Aggregate { func, field, alias });
+    }
+    if aggregates.is_empty() {
+        aggregates.push(Aggregate { func: AggregateFn::Count, field: None, alias: None });
+    }
+    Ok(crate::pipeline::AggregateConfig { group_by, aggregates })
+}
+
+/// Parses the clause list of a "Filter where ..." intent. `and` binds tighter than `or`.
+fn parse_predicate(words: &[&str]) -> Result<Predicate> {
+    let mut alternatives = words.split(|w| w.eq_ignore_ascii_case("or"))
+        .map(|group| {
+            let mut clauses = group.split(|w| w.eq_ignore_ascii_case("and"))
+                .map(parse_clause)
+                .collect::<Result<Vec<_>>>()?;
+            Ok(if clauses.len() == 1 { clauses.remove(0) } else { Predicate::And { and: clauses } })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(if alternatives.len() == 1 { alternatives.remove(0) } else { Predicate::Or { or: alternatives } })
+}
+
+/// "<field> <op> <value>", optionally prefixed with "not"
+fn parse_clause(words: &[&str]) -> Result<Predicate> {
+    if let Some((first, rest)) = words.split_first()
+        && first.eq_ignore_ascii_case("not") {
+        return Ok(Predicate::Not { not: Box::new(parse_clause(rest)?) });
+    }
+    if words.len() < 2 {
+        return Err(anyhow::anyhow!("Incomplete filter clause: '{}'", words.join(" ")));
+    }
+    let field = words[0].to_string();
+
+    // Longest operator first: "is not null" before "is null", "not in" before "in"
+    for width in (1..=3).rev() {
+        if words.len() < 1 + width {
+            continue;
+        }
+        let token = words[1..1 + width].join(" ");
+        if let Some(op) = CompareOp::parse(&token) {
+            // Support multi-word values (e.g. "Bukit Bintang")
+            let rest = words[1 + width..].join(" ");
+            let val = if op.is_unary() {
+                serde_json::Value::Null
+            } else if rest.is_empty() {
+                return Err(anyhow::anyhow!("Filter clause '{}' is missing a value", words.join(" ")));
+            } else if matches!(op, CompareOp::In | CompareOp::NotIn) {
+                serde_json::Value::Array(rest.split(',').map(|v| parse_scalar(v.trim())).collect())
+            } else {
+                parse_scalar(&rest)
+            };
+            return Ok(Predicate::Compare { field, op, val });
+        }
+    }
+    Err(anyhow::anyhow!("Unknown filter operator in '{}'", words.join(" ")))
+}
+
+/// Try parsing as integer, float or boolean, else keep the (unquoted) string
+fn parse_scalar(raw: &str) -> serde_json::Value {
+    if let std::result::Result::Ok(num) = raw.parse::<i64>() {
+        return serde_json::json!(num);
+    }
+    if let std::result::Result::Ok(num) = raw.parse::<f64>()
+        && num.is_finite() {
+        return serde_json::json!(num);
+    }
+    match raw {
+        "true" => serde_json::json!(true),
+        "false" => serde_json::json!(false),
+        _ => serde_json::json!(raw.trim_matches(|c| c == '"' || c == '\'')),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn predicate(text: &str) -> Predicate {
+        parse_predicate(&text.split_whitespace().collect::<Vec<_>>()).unwrap()
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let parsed = predicate("a > 1 and b < 2 or c == x");
+        assert_eq!(parsed.to_string(), r#"((a > 1 and b < 2) or c == "x")"#);
+    }
+
+    #[test]
+    fn parses_lists_multi_word_values_and_unary_operators() {
+        assert_eq!(predicate("station_type in LRT, MRT"), Predicate::Compare {
+            field: "station_type".into(), op: CompareOp::In, val: json!(["LRT", "MRT"]),
+        });
+        assert_eq!(predicate("area == Bukit Bintang"), Predicate::Compare {
+            field: "area".into(), op: CompareOp::Eq, val: json!("Bukit Bintang"),
+        });
+        assert_eq!(predicate("not photo is not null"), Predicate::Not { not: Box::new(Predicate::Compare {
+            field: "photo".into(), op: CompareOp::IsNotNull, val: serde_json::Value::Null,
+        }) });
+    }
+
+    #[test]
+    fn rejects_incomplete_clauses() {
+        assert!(parse_predicate(&["price", "<="]).is_err());
+        assert!(parse_predicate(&["price", "about", "5"]).is_err());
+    }
+
+    #[test]
+    fn zakat_intents_state_their_nisab() {
+        assert_eq!(parse_zakat(&["for", "5000"]).unwrap(), None);
+        let config = parse_zakat(&["for", "5000", "with", "nisab", "4,000"]).unwrap().unwrap();
+        assert_eq!(config.balance, Some(rust_decimal::Decimal::from(5000)));
+        assert_eq!(config.nisab, crate::zakat::Nisab::Fixed { fixed: rust_decimal::Decimal::from(4000) });
+        let config = parse_zakat(&["with", "gold", "nisab"]).unwrap().unwrap();
+        assert!(config.nisab.needs_input());
+        let config = parse_zakat(&["with", "silver", "nisab", "at", "3", "at", "rate", "2.5%"]).unwrap().unwrap();
+        assert!(matches!(config.nisab, crate::zakat::Nisab::Metal { price: Some(p), .. } if p == rust_decimal::Decimal::from(3)));
+        assert_eq!(config.rate, rust_decimal::Decimal::new(25, 3));
+    }
+
+    #[test]
+    fn zakat_without_a_nisab_is_left_to_synthesis() {
+        let loom = AetherLoom::new().unwrap();
+        assert_eq!(loom.weave("Calculate Zakat for 5000").unwrap().op_code, 600);
+        assert_eq!(loom.weave("Calculate Zakat for 5000 with nisab 4000").unwrap().op_code, 101);
+    }
+}
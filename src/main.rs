@@ -1,16 +1,30 @@
-use aether_store::{AetherVault, AetherKernel, AetherOrchestrator, ProductTemplate, InputSchema, ProjectAtom, ProjectStatus};
+use aether_store::{AetherVault, AetherKernel, AetherOrchestrator, ProductTemplate, InputSchema, ProjectAtom, ProjectStatus, RunRecord};
+use aether_store::kernel::{ExecutionBudget, ExecutionOptions, IoMode, NodeError, TraceNode};
+use aether_store::guard::{Diagnostic, SovereigntyDecision};
+use aether_store::scheduler::{Scheduler, SchedulePatch};
+use aether_store::runs;
+use aether_store::reactive::ReactiveHub;
+use aether_store::synthesis::{self, GapStatus};
 use std::fs;
 use std::sync::Arc;
 use std::env;
-use axum::{Router, routing::{get, post}, Json, extract::{State, Query}, http::Method};
+use axum::{Router, routing::{get, post, put}, Json, Extension, extract::{State, Query, Path}, http::Method};
 use tower_http::{services::ServeDir, cors::{CorsLayer, Any}};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use futures::StreamExt;
 
 #[derive(Deserialize)]
 struct OrchestrationRequest {
     manifest: String,
-    // inputs: HashMap<String, Value> // Future extension
+    #[serde(default)]
+    trace: bool,
+    #[serde(default)]
+    io_mode: IoMode, // "live", "record" or "replay"
+    #[serde(default)]
+    partial: bool, // MERGE nodes only: drop failed branches and merge the healthy ones (failures listed in `errors`)
+    #[serde(default)]
+    inputs: HashMap<String, serde_json::Value>, // Bound to the manifest's {{placeholders}} at execution
 }
 
 #[derive(Serialize)]
@@ -19,12 +33,26 @@ struct OrchestrationResult {
     ui_hint: Option<String>,
     output: serde_json::Value,
     logs: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    trace: Option<TraceNode>, // Per-node timings, row counts and errors (opt-in)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    run_version: Option<u64>, // Stored run (see /api/projects/{name}/runs)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    errors: Vec<NodeError>, // Nodes that failed, with hash, opcode and error kind
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    diagnostics: Vec<Diagnostic>, // Build-time findings, e.g. filters that can never match
 }
 
 #[derive(Deserialize)]
 struct RunTemplateRequest {
     product_id: String,
-    inputs: HashMap<String, String>,
+    inputs: HashMap<String, serde_json::Value>,
+    #[serde(default)]
+    trace: bool,
+    #[serde(default)]
+    io_mode: IoMode,
+    #[serde(default)]
+    partial: bool,
 }
 
 #[derive(Deserialize)]
@@ -57,6 +85,8 @@ struct InputPatch {
     label: String,
     input_type: String, // text, select, number
     options: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    default: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize)]
@@ -92,8 +122,8 @@ async fn handle_orchestration(
     // 1. Build the App from the manifest
     let orchestrator = AetherOrchestrator::new((*vault).clone()).unwrap(); 
     
-    match orchestrator.build_app(&payload.manifest) {
-        Ok((root_hash, ui_hint)) => {
+    match orchestrator.build(&payload.manifest, &[]) {
+        Ok(build) => {
             
             // 2. Verify Resonance (Sovereign Gate)
             // In a real implementation, we check if the user has permission to EXECUTE this root hash.
@@ -101,31 +131,82 @@ async fn handle_orchestration(
             // if !vault.verify_resonance(user_hash, &root_hash) { ... }
 
             // 3. Execute
-            let kernel = AetherKernel::new((*vault).clone());
-            match kernel.execute_smart(&root_hash).await {
-                Ok(result) => Json(OrchestrationResult {
-                    root_hash,
-                    ui_hint,
-                    output: result,
-                    logs: vec!["Execution Successful".to_string()]
-                }),
-                Err(e) => Json(OrchestrationResult {
-                    root_hash,
-                    ui_hint: None,
-                    output: serde_json::json!({"error": e.to_string()}),
-                    logs: vec![format!("Execution Error: {}", e)]
-                })
-            }
+            let budget = manifest_budget(&orchestrator, &payload.manifest);
+            let options = ExecutionOptions { trace: payload.trace, bindings: payload.inputs, io_mode: payload.io_mode, partial: payload.partial };
+            let app_name = orchestrator.load_manifest(&payload.manifest).ok().map(|m| m.app_name);
+            let mut result = execute_root(&vault, app_name.as_deref(), build.root_hash, build.ui_hint, budget, &options, "Execution Successful".to_string()).await;
+            result.diagnostics = build.diagnostics;
+            Json(result)
         },
         Err(e) => Json(OrchestrationResult {
             root_hash: String::new(),
             ui_hint: None,
             output: serde_json::json!({"error": e.to_string()}),
-            logs: vec![format!("Build Error: {}", e)]
+            logs: vec![format!("Build Error: {}", e)],
+            trace: None,
+            run_version: None,
+            errors: Vec::new(),
+            diagnostics: Vec::new(),
         })
     }
 }
 
+/// Execution limits declared by the manifest (`budget:`), or the kernel defaults
+fn manifest_budget(orchestrator: &AetherOrchestrator, manifest_raw: &str) -> ExecutionBudget {
+    orchestrator.load_manifest(manifest_raw)
+        .ok()
+        .and_then(|m| m.budget)
+        .unwrap_or_default()
+}
+
+/// Executes a built root hash and packages the output (and optional trace) for the UI.
+/// Runs of a known project are stored as its next output version.
+async fn execute_root(
+    vault: &AetherVault,
+    project: Option<&str>,
+    root_hash: String,
+    ui_hint: Option<String>,
+    budget: ExecutionBudget,
+    options: &ExecutionOptions,
+    success_log: String,
+) -> OrchestrationResult {
+    let kernel = AetherKernel::with_budget(vault.clone(), budget);
+    let started_at_us = runs::unix_micros();
+    let report = kernel.execute_with(&root_hash, options).await;
+    let mut logs = Vec::new();
+    let run_version = project.and_then(|name| {
+        match runs::record_execution(vault, name, &root_hash, options, &report, started_at_us, None) {
+            Ok(record) => Some(record.version),
+            Err(e) => {
+                logs.push(format!("Run Not Stored: {}", e));
+                None
+            },
+        }
+    });
+    match report.output {
+        Ok(result) => OrchestrationResult {
+            root_hash,
+            ui_hint,
+            output: result,
+            logs: std::iter::once(success_log).chain(report.warnings).chain(logs).collect(),
+            trace: report.trace,
+            run_version,
+            errors: report.errors.clone(),
+            diagnostics: Vec::new(),
+        },
+        Err(e) => OrchestrationResult {
+            root_hash,
+            ui_hint: None,
+            output: serde_json::json!({"error": e.to_string()}),
+            logs: std::iter::once(format!("Execution Error: {}", e)).chain(report.warnings).chain(logs).collect(),
+            trace: report.trace,
+            run_version,
+            errors: report.errors.clone(),
+            diagnostics: Vec::new(),
+        }
+    }
+}
+
 async fn handle_run_template(
     State(vault): State<Arc<AetherVault>>,
     Json(payload): Json<RunTemplateRequest>,
@@ -135,38 +216,26 @@ async fn handle_run_template(
     let catalog: HashMap<String, ProductTemplate> = serde_json::from_str(&content).unwrap_or_default();
 
     if let Some(product) = catalog.get(&payload.product_id) {
-        // Hydrate Template
-        let mut manifest = product.manifest_template.clone();
-        for (key, val) in payload.inputs {
-            let placeholder = format!("{{{{{}}}}}", key); // {{key}}
-            manifest = manifest.replace(&placeholder, &val);
-        }
-
-        // Build & Run
+        // Build once (inputs become PARAM atoms), then bind this request's values at execution
+        let manifest = &product.manifest_template;
         let orchestrator = AetherOrchestrator::new((*vault).clone()).unwrap();
-         match orchestrator.build_app(&manifest) {
-            Ok((root_hash, ui_hint)) => {
-                let kernel = AetherKernel::new((*vault).clone());
-                match kernel.execute_smart(&root_hash).await {
-                    Ok(result) => Json(OrchestrationResult {
-                        root_hash,
-                        ui_hint,
-                        output: result,
-                        logs: vec!["Template Executed".to_string()]
-                    }),
-                    Err(e) => Json(OrchestrationResult {
-                        root_hash,
-                        ui_hint: None,
-                        output: serde_json::json!({"error": e.to_string()}),
-                        logs: vec![format!("Execution Error: {}", e)]
-                    })
-                }
+         match orchestrator.build(manifest, &product.inputs) {
+            Ok(build) => {
+                let budget = manifest_budget(&orchestrator, manifest);
+                let options = ExecutionOptions { trace: payload.trace, bindings: payload.inputs, io_mode: payload.io_mode, partial: payload.partial };
+                let mut result = execute_root(&vault, Some(&payload.product_id), build.root_hash, build.ui_hint, budget, &options, "Template Executed".to_string()).await;
+                result.diagnostics = build.diagnostics;
+                Json(result)
             },
             Err(e) => Json(OrchestrationResult {
                 root_hash: String::new(),
                 ui_hint: None,
                 output: serde_json::json!({"error": e.to_string()}),
-                logs: vec![format!("Build Error: {}", e)]
+                logs: vec![format!("Build Error: {}", e)],
+                trace: None,
+                run_version: None,
+                errors: Vec::new(),
+                diagnostics: Vec::new(),
             })
         }
     } else {
@@ -174,7 +243,11 @@ async fn handle_run_template(
             root_hash: String::new(),
             ui_hint: None,
             output: serde_json::json!({"error": "Product ID not found"}),
-            logs: vec!["Catalog Error".to_string()]
+            logs: vec!["Catalog Error".to_string()],
+            trace: None,
+            run_version: None,
+            errors: Vec::new(),
+            diagnostics: Vec::new(),
         })
     }
 }
@@ -286,12 +359,14 @@ nodes:
                     label: "Station Type (LRT, MRT, KTM)".to_string(),
                     input_type: "select".to_string(),
                     options: Some(vec!["LRT".to_string(), "MRT".to_string(), "KTM".to_string(), "Monorail".to_string()]),
+                    default: None,
                 },
                 InputSchema {
                     name: "station_name".to_string(),
                     label: "Preferred Station Name".to_string(),
                     input_type: "text".to_string(),
                     options: None,
+                    default: None,
                 }
             ],
         };
@@ -321,6 +396,8 @@ nodes:
                                      org_hash: "global".to_string(),
                                      status: aether_store::ProjectStatus::Active,
                                      created_at: 0,
+                                     budget: None,
+                                     laws: None,
                                  };
                                  let _ = vault.persist_project(&atom);
                              }
@@ -345,6 +422,9 @@ nodes:
                                 println!("[Repair] Built '{}' -> Root Hash: {}", proj.name, hash);
                                 proj.root_hash = hash;
                                 proj.status = aether_store::ProjectStatus::Active;
+                                let manifest = orchestrator.load_manifest(&content).ok();
+                                proj.budget = manifest.as_ref().and_then(|m| m.budget.clone());
+                                proj.laws = manifest.and_then(|m| m.laws);
                                 let _ = vault.persist_project(&proj);
                             },
                             Err(e) => println!("[Repair] Failed to build '{}': {}", proj.name, e),
@@ -357,12 +437,24 @@ nodes:
         }
     }
 
+    // --- Scheduled Executions ---
+    Scheduler::new((*vault).clone()).spawn();
+
+    // --- Reactive Triggers (Op 50) of Active projects ---
+    let hub = ReactiveHub::new((*vault).clone());
+    for proj in vault.list_projects().unwrap_or_default() {
+        if proj.status == ProjectStatus::Active && vault.fetch(&proj.root_hash).is_ok()
+            && let Err(e) = hub.register(&proj.name, &proj.root_hash, proj.budget.clone().unwrap_or_default()) {
+            println!("[Reactive] Failed to register '{}': {}", proj.name, e);
+        }
+    }
+
     // --- Start Web Server ---
     let user_vault = Arc::clone(&vault);
     
     let cors = CorsLayer::new()
         .allow_origin(Any)
-        .allow_methods([Method::GET, Method::POST])
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
         .allow_headers(Any);
 
     let app = Router::new()
@@ -382,12 +474,25 @@ nodes:
         .route("/api/deploy", post(handle_deploy))
         .route("/api/project_schema", post(handle_get_project_schema))
         .route("/api/execute", post(handle_execution_by_hash))
+        .route("/api/execute/stream", post(handle_execution_stream))
         .route("/api/projects", get(handle_list_projects))
+        .route("/api/audit", get(handle_audit))
+        .route("/api/laws", get(handle_list_laws))
+        .route("/api/schedules", get(handle_list_schedules).post(handle_create_schedule))
+        .route("/api/schedules/{id}", put(handle_update_schedule).delete(handle_delete_schedule))
+        .route("/api/projects/{name}/runs", get(handle_list_runs))
+        .route("/api/projects/{name}/runs/diff", get(handle_diff_runs))
+        .route("/api/projects/{name}/runs/{version}", get(handle_get_run))
+        .route("/api/projects/{name}/inputs", post(handle_set_inputs))
+        .route("/api/projects/{name}/events", get(handle_project_events))
+        .route("/api/synthesis", get(handle_list_gaps))
+        .route("/api/synthesis/{hash}/resolve", post(handle_resolve_gap))
         .route("/api/chat", post(handle_chat))
         .route("/api/project/weave", post(handle_weave))
         .route("/api/warehouse/inventory", get(handle_warehouse_inventory))
         .route("/api/warehouse/inject", post(handle_warehouse_inject))
         .with_state(Arc::clone(&vault))
+        .layer(Extension(hub))
         .layer(cors)
         .fallback_service(ServeDir::new("../universal_shell"));
 
@@ -401,16 +506,33 @@ nodes:
 #[derive(Deserialize)]
 struct ExecuteRequest {
     hash: String,
+    #[serde(default)]
+    project: Option<String>, // Store the run under this project; `hash` must be its deployed root
+    #[serde(default)]
+    trace: bool,
+    #[serde(default)]
+    io_mode: IoMode,
+    #[serde(default)]
+    partial: bool,
+    #[serde(default)]
+    inputs: HashMap<String, serde_json::Value>,
 }
 
 #[derive(Deserialize)]
 struct ProjectRequest {
     name: String,
-    inputs: Option<HashMap<String, String>>,
+    inputs: Option<HashMap<String, serde_json::Value>>,
+    #[serde(default)]
+    trace: bool,
+    #[serde(default)]
+    io_mode: IoMode,
+    #[serde(default)]
+    partial: bool,
 }
 
 async fn handle_orchestrate_project(
     State(vault): State<Arc<AetherVault>>,
+    Extension(hub): Extension<Arc<ReactiveHub>>,
     Json(payload): Json<ProjectRequest>,
 ) -> Json<OrchestrationResult> {
     // 1. Initial Status: Building
@@ -420,60 +542,57 @@ async fn handle_orchestrate_project(
         org_hash: "legacy_org".to_string(),
         status: ProjectStatus::Building,
         created_at: 0,
+        budget: None,
+        laws: None,
     };
     let _ = vault.persist_project(&project_atom); // Persist Initial State
 
     // 2. Load Manifest (Currently FS, future Sled)
     let path = format!("../../products/{}/manifest.yaml", payload.name); 
     match fs::read_to_string(&path) {
-        Ok(mut content) => {
-             // Templating: Replace {{key}} with value
-             if let Some(inputs) = payload.inputs {
-                 for (k, v) in inputs {
-                     content = content.replace(&format!("{{{{{}}}}}", k), &v);
-                 }
-             }
-
+        Ok(content) => {
              let orchestrator = AetherOrchestrator::new((*vault).clone()).unwrap(); 
              // Build
-             match orchestrator.build_app(&content) {
-                Ok((root_hash, ui_hint)) => {
+             match orchestrator.build(&content, &[]) {
+                Ok(build) => {
+                     let root_hash = build.root_hash;
                      // 3. Update Status: Active
                      let _ = vault.update_project_status(&payload.name, ProjectStatus::Active);
                      // Update Root Hash in separate atomic op or refetch-modify-save (Simplified here)
                      // Ideally persist_project should be upsert. 
                      // For now, re-save with Hash
+                     let manifest = orchestrator.load_manifest(&content).ok();
                      let final_atom = ProjectAtom {
                         name: payload.name.clone(),
                         root_hash: root_hash.clone(),
                         org_hash: "legacy_org".to_string(),
                         status: ProjectStatus::Active,
                         created_at: 0,
+                        budget: manifest.as_ref().and_then(|m| m.budget.clone()),
+                        laws: manifest.and_then(|m| m.laws),
                      };
                      let _ = vault.persist_project(&final_atom);
+                     if let Err(e) = hub.register(&payload.name, &root_hash, final_atom.budget.clone().unwrap_or_default()) {
+                         println!("[Reactive] Failed to register '{}': {}", payload.name, e);
+                     }
 
                      // Exec
-                    let kernel = AetherKernel::new((*vault).clone());
-                    match kernel.execute_smart(&root_hash).await {
-                        Ok(result) => Json(OrchestrationResult {
-                            root_hash,
-                            ui_hint,
-                            output: result,
-                            logs: vec![format!("Project '{}' Build & Exec Successful", payload.name)]
-                        }),
-                        Err(e) => Json(OrchestrationResult {
-                            root_hash,
-                            ui_hint: None,
-                            output: serde_json::json!({"error": e.to_string()}),
-                            logs: vec![format!("Execution Error: {}", e)]
-                        })
-                    }
+                    let budget = manifest_budget(&orchestrator, &content);
+                    let success_log = format!("Project '{}' Build & Exec Successful", payload.name);
+                    let options = ExecutionOptions { trace: payload.trace, bindings: payload.inputs.unwrap_or_default(), io_mode: payload.io_mode, partial: payload.partial };
+                    let mut result = execute_root(&vault, Some(&payload.name), root_hash, build.ui_hint, budget, &options, success_log).await;
+                    result.diagnostics = build.diagnostics;
+                    Json(result)
                 },
                 Err(e) => Json(OrchestrationResult {
                      root_hash: String::new(),
                      ui_hint: None,
                      output: serde_json::json!({"error": e.to_string()}),
-                     logs: vec![format!("Build Error: {}", e)]
+                     logs: vec![format!("Build Error: {}", e)],
+                     trace: None,
+                     run_version: None,
+                     errors: Vec::new(),
+                     diagnostics: Vec::new(),
                 })
              }
         },
@@ -481,7 +600,11 @@ async fn handle_orchestrate_project(
              root_hash: String::new(),
              ui_hint: None,
              output: serde_json::json!({"error": e.to_string()}),
-             logs: vec![format!("Manifest Read Error: {}", e)]
+             logs: vec![format!("Manifest Read Error: {}", e)],
+             trace: None,
+             run_version: None,
+             errors: Vec::new(),
+             diagnostics: Vec::new(),
         })
     }
 }
@@ -494,19 +617,19 @@ struct DeployResult {
 
 async fn handle_deploy(
     State(vault): State<Arc<AetherVault>>,
+    Extension(hub): Extension<Arc<ReactiveHub>>,
     Json(payload): Json<ProjectRequest>,
 ) -> Json<DeployResult> {
     // 1. Build & Orchestrate to freeze logic
     let path = format!("../../products/{}/manifest.yaml", payload.name); 
-    if let Ok(mut content) = fs::read_to_string(&path) {
-         if let Some(inputs) = payload.inputs {
-             for (k, v) in inputs {
-                 content = content.replace(&format!("{{{{{}}}}}", k), &v);
-             }
-         }
-         
+    // Inputs are not baked in: the deployed root hash is executed with per-run bindings
+    if let Ok(content) = fs::read_to_string(&path) {
          let orchestrator = AetherOrchestrator::new((*vault).clone()).unwrap();
          if let Ok((root_hash, _)) = orchestrator.build_app(&content) {
+             // Deployed triggers fire from here on (see /api/projects/{name}/events)
+             if let Err(e) = hub.register(&payload.name, &root_hash, manifest_budget(&orchestrator, &content)) {
+                 println!("[Reactive] Failed to register '{}': {}", payload.name, e);
+             }
              return Json(DeployResult {
                  app_url: format!("http://localhost:3000/?app={}", root_hash),
                  root_hash,
@@ -519,6 +642,246 @@ async fn handle_deploy(
     })
 }
 
+/// Every registered law; manifests pick theirs with `laws:` ("no_riba" or pinned "no_riba@1")
+async fn handle_list_laws() -> Json<serde_json::Value> {
+    let guard = aether_store::AetherGuard::new();
+    let laws: Vec<serde_json::Value> = guard.registry.all().iter().map(|law| serde_json::json!({
+        "name": law.name(),
+        "version": law.version(),
+        "description": law.description(),
+    })).collect();
+    Json(serde_json::json!(laws))
+}
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    limit: Option<usize>,
+}
+
+async fn handle_audit(
+    State(vault): State<Arc<AetherVault>>,
+    Query(query): Query<AuditQuery>,
+) -> Json<Vec<SovereigntyDecision>> {
+    // Newest first; sovereignty decisions from builds (persist) and runs (connect / redirect)
+    Json(vault.list_audit(query.limit.unwrap_or(100)).unwrap_or_default())
+}
+
+#[derive(Deserialize)]
+struct ScheduleQuery {
+    project: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScheduleRequest {
+    project: String,
+    spec: String, // Cron ("0 6 * * *", UTC) or "@every 15m"
+    #[serde(default)]
+    inputs: HashMap<String, serde_json::Value>,
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+async fn handle_list_schedules(
+    State(vault): State<Arc<AetherVault>>,
+    Query(query): Query<ScheduleQuery>,
+) -> Json<serde_json::Value> {
+    let schedules = vault.list_schedules().unwrap_or_default().into_iter()
+        .filter(|s| query.project.as_ref().is_none_or(|p| &s.project == p))
+        .collect::<Vec<_>>();
+    Json(serde_json::json!(schedules))
+}
+
+async fn handle_create_schedule(
+    State(vault): State<Arc<AetherVault>>,
+    Json(payload): Json<ScheduleRequest>,
+) -> Json<serde_json::Value> {
+    match aether_store::scheduler::create_schedule(&vault, &payload.project, &payload.spec, payload.inputs, payload.enabled) {
+        Ok(schedule) => Json(serde_json::json!(schedule)),
+        Err(e) => Json(serde_json::json!({"error": e.to_string()})),
+    }
+}
+
+async fn handle_update_schedule(
+    State(vault): State<Arc<AetherVault>>,
+    Path(id): Path<String>,
+    Json(patch): Json<SchedulePatch>,
+) -> Json<serde_json::Value> {
+    match aether_store::scheduler::update_schedule(&vault, &id, patch) {
+        Ok(schedule) => Json(serde_json::json!(schedule)),
+        Err(e) => Json(serde_json::json!({"error": e.to_string()})),
+    }
+}
+
+async fn handle_delete_schedule(
+    State(vault): State<Arc<AetherVault>>,
+    Path(id): Path<String>,
+) -> Json<serde_json::Value> {
+    match vault.delete_schedule(&id) {
+        Ok(true) => Json(serde_json::json!({"deleted": id})),
+        Ok(false) => Json(serde_json::json!({"error": format!("Unknown schedule '{}'", id)})),
+        Err(e) => Json(serde_json::json!({"error": e.to_string()})),
+    }
+}
+
+#[derive(Deserialize)]
+struct RunsQuery {
+    limit: Option<usize>,
+}
+
+async fn handle_list_runs(
+    State(vault): State<Arc<AetherVault>>,
+    Path(name): Path<String>,
+    Query(query): Query<RunsQuery>,
+) -> Json<Vec<RunRecord>> {
+    // Newest version first
+    Json(vault.list_runs(&name, query.limit.unwrap_or(50)).unwrap_or_default())
+}
+
+async fn handle_get_run(
+    State(vault): State<Arc<AetherVault>>,
+    Path((name, version)): Path<(String, u64)>,
+) -> Json<serde_json::Value> {
+    // The stored record plus its output, without re-running anything
+    match vault.get_run(&name, version) {
+        Ok(record) => {
+            let output = runs::load_output(&record).unwrap_or_else(|e| serde_json::json!({"error": e}));
+            Json(serde_json::json!({"run": record, "output": output}))
+        },
+        Err(_) => Json(serde_json::json!({"error": format!("No run {} v{}", name, version)})),
+    }
+}
+
+#[derive(Deserialize)]
+struct DiffQuery {
+    from: u64,
+    to: u64,
+    key: Option<String>, // Match rows on this field instead of by position
+}
+
+async fn handle_diff_runs(
+    State(vault): State<Arc<AetherVault>>,
+    Path(name): Path<String>,
+    Query(query): Query<DiffQuery>,
+) -> Json<serde_json::Value> {
+    let load = |version: u64| -> Result<(RunRecord, serde_json::Value), String> {
+        let record = vault.get_run(&name, version).map_err(|_| format!("No run {} v{}", name, version))?;
+        let output = runs::load_output(&record)?;
+        Ok((record, output))
+    };
+    match (load(query.from), load(query.to)) {
+        (Ok((from, before)), Ok((to, after))) => {
+            let mut diff = runs::diff_outputs(&before, &after, query.key.as_deref());
+            diff["from"] = serde_json::json!(from.version);
+            diff["to"] = serde_json::json!(to.version);
+            Json(diff)
+        },
+        (Err(e), _) | (_, Err(e)) => Json(serde_json::json!({"error": e})),
+    }
+}
+
+async fn handle_set_inputs(
+    Extension(hub): Extension<Arc<ReactiveHub>>,
+    Path(name): Path<String>,
+    Json(inputs): Json<HashMap<String, serde_json::Value>>,
+) -> Json<serde_json::Value> {
+    // Results of the triggers this fires are pushed to /api/projects/{name}/events
+    match hub.set_inputs(&name, inputs).await {
+        Ok(fired) => Json(serde_json::json!({"fired": fired})),
+        Err(e) => Json(serde_json::json!({"error": e})),
+    }
+}
+
+/// Server-Sent Events: one `update` event per trigger firing of the project
+async fn handle_project_events(
+    Extension(hub): Extension<Arc<ReactiveHub>>,
+    Path(name): Path<String>,
+) -> impl axum::response::IntoResponse {
+    use tokio::sync::broadcast::error::RecvError;
+    let updates = futures::stream::unfold(hub.subscribe(), move |mut updates| {
+        let name = name.clone();
+        async move {
+            loop {
+                match updates.recv().await {
+                    Ok(update) if update.project == name => {
+                        let event = axum::response::sse::Event::default().event("update").json_data(&update).unwrap();
+                        return Some((Ok::<_, std::convert::Infallible>(event), updates));
+                    },
+                    // Other projects' updates, or ones this slow client missed
+                    Ok(_) | Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    axum::response::Sse::new(updates).keep_alive(axum::response::sse::KeepAlive::default())
+}
+
+#[derive(Deserialize)]
+struct GapQuery {
+    #[serde(default)]
+    status: Option<String>, // "pending" (default), "resolved" or "all"
+}
+
+async fn handle_list_gaps(
+    State(vault): State<Arc<AetherVault>>,
+    Query(query): Query<GapQuery>,
+) -> Json<serde_json::Value> {
+    let status = match query.status.as_deref() {
+        None | Some("pending") => Some(GapStatus::Pending),
+        Some("resolved") => Some(GapStatus::Resolved),
+        Some("all") => None,
+        Some(other) => return Json(serde_json::json!({"error": format!("Unknown gap status '{}'", other)})),
+    };
+    match synthesis::list_gaps(&vault, status) {
+        Ok(gaps) => Json(serde_json::json!(gaps)),
+        Err(e) => Json(serde_json::json!({"error": e.to_string()})),
+    }
+}
+
+#[derive(Deserialize)]
+struct ResolveGapRequest {
+    atom: Option<aether_store::LogicAtom>, // The replacement logic...
+    intent: Option<String>,                // ...or an intent for the Loom to weave it from
+    #[serde(default)]
+    roots: Vec<String>, // Other graphs to relink besides project roots (e.g. deployed hashes)
+}
+
+async fn handle_resolve_gap(
+    State(vault): State<Arc<AetherVault>>,
+    Extension(hub): Extension<Arc<ReactiveHub>>,
+    Path(hash): Path<String>,
+    Json(payload): Json<ResolveGapRequest>,
+) -> Json<serde_json::Value> {
+    let replacement = match (payload.atom, payload.intent) {
+        (Some(atom), _) => atom,
+        (None, Some(intent)) => {
+            let context = vault.get_gap(&hash).map(|gap| gap.context).unwrap_or_else(|_| "global".to_string());
+            match aether_store::AetherLoom::new().and_then(|loom| loom.weave_with_context(&intent, &context)) {
+                Ok(atom) => atom,
+                Err(e) => return Json(serde_json::json!({"error": format!("Weave Error: {}", e)})),
+            }
+        },
+        (None, None) => return Json(serde_json::json!({"error": "Provide a replacement 'atom' or 'intent'"})),
+    };
+    match synthesis::resolve_gap(&vault, &aether_store::AetherGuard::new(), &hash, replacement, &payload.roots) {
+        Ok(gap) => {
+            // Relinked projects keep their triggers on the new root
+            for relink in &gap.relinked {
+                if let Some(project) = &relink.project
+                    && let Err(e) = hub.register(project, &relink.new_root, vault.get_project(project).ok().and_then(|p| p.budget).unwrap_or_default()) {
+                    println!("[Reactive] Failed to register '{}': {}", project, e);
+                }
+            }
+            Json(serde_json::json!(gap))
+        },
+        Err(e) => Json(serde_json::json!({"error": e.to_string()})),
+    }
+}
+
 async fn handle_list_projects(
     State(vault): State<Arc<AetherVault>>,
 ) -> Json<Vec<ProjectAtom>> {
@@ -555,24 +918,71 @@ async fn handle_execution_by_hash(
     State(vault): State<Arc<AetherVault>>,
     Json(payload): Json<ExecuteRequest>,
 ) -> Json<OrchestrationResult> {
-    let kernel = AetherKernel::new((*vault).clone());
-    match kernel.execute_smart(&payload.hash).await {
-         Ok(result) => Json(OrchestrationResult {
-            root_hash: payload.hash,
-            ui_hint: None, // Logic Execution doesn't re-parse manifest, so hint is lost unless stored in Atom?
-            // For now, raw execution has no hint.
-            output: result,
-            logs: vec!["Executed from Registry".to_string()]
-        }),
-        Err(e) => Json(OrchestrationResult {
+    // Logic Execution doesn't re-parse manifest, so hint is lost unless stored in Atom?
+    // For now, raw execution has no hint.
+    // A named project runs its deployed root, within the budget its manifest declared
+    let budget = match request_budget(&vault, &payload) {
+        Ok(budget) => budget,
+        Err(e) => return Json(OrchestrationResult {
             root_hash: payload.hash,
             ui_hint: None,
             output: serde_json::json!({"error": e.to_string()}),
-            logs: vec![format!("Execution Error: {}", e)]
-        })
+            logs: vec![format!("Execution Refused: {}", e)],
+            trace: None,
+            run_version: None,
+            errors: Vec::new(),
+            diagnostics: Vec::new(),
+        }),
+    };
+    let options = ExecutionOptions { trace: payload.trace, bindings: payload.inputs, io_mode: payload.io_mode, partial: payload.partial };
+    Json(execute_root(&vault, payload.project.as_deref(), payload.hash, None, budget, &options, "Executed from Registry".to_string()).await)
+}
+
+/// Budget for executing a request's hash: its project's, when it names the project whose deployed
+/// root the hash is (anything else is refused), or the defaults
+fn request_budget(vault: &AetherVault, payload: &ExecuteRequest) -> Result<ExecutionBudget, aether_store::VaultError> {
+    match &payload.project {
+        Some(name) => Ok(runs::deployed_project(vault, name, &payload.hash)?.budget.unwrap_or_default()),
+        None => Ok(ExecutionBudget::default()),
     }
 }
 
+/// Executes a hash and streams the root's rows back as NDJSON while they are produced
+async fn handle_execution_stream(
+    State(vault): State<Arc<AetherVault>>,
+    Json(payload): Json<ExecuteRequest>,
+) -> axum::response::Response {
+    if payload.trace || payload.partial {
+        let error = serde_json::json!({"error": "trace and partial are not supported when streaming; use /api/execute"});
+        return axum::response::Response::builder()
+            .status(axum::http::StatusCode::BAD_REQUEST)
+            .header(axum::http::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(axum::body::Body::from(format!("{}\n", error)))
+            .unwrap();
+    }
+    let budget = match request_budget(&vault, &payload) {
+        Ok(budget) => budget,
+        Err(e) => {
+            let error = serde_json::json!({"error": e.to_string()});
+            return axum::response::Response::builder()
+                .status(axum::http::StatusCode::FORBIDDEN)
+                .header(axum::http::header::CONTENT_TYPE, "application/x-ndjson")
+                .body(axum::body::Body::from(format!("{}\n", error)))
+                .unwrap();
+        },
+    };
+    let kernel = AetherKernel::with_budget((*vault).clone(), budget);
+    let options = ExecutionOptions { bindings: payload.inputs, io_mode: payload.io_mode, ..Default::default() };
+    let lines = match kernel.execute_stream(&payload.hash, &options).await {
+        Ok(execution) => aether_store::stream::to_ndjson(execution),
+        Err(e) => futures::stream::once(async move { format!("{}\n", serde_json::json!({"error": e.to_string()})) }).boxed(),
+    };
+    axum::response::Response::builder()
+        .header(axum::http::header::CONTENT_TYPE, "application/x-ndjson")
+        .body(axum::body::Body::from_stream(lines.map(Ok::<_, std::convert::Infallible>)))
+        .unwrap()
+}
+
 async fn handle_chat(
     Json(payload): Json<ChatRequest>,
 ) -> Json<serde_json::Value> {
@@ -910,6 +1320,10 @@ async fn handle_weave(
                  let opt_vec: Vec<serde_yaml::Value> = opts.iter().map(|o| serde_yaml::Value::String(o.clone())).collect();
                  new_input.insert(serde_yaml::Value::String("options".into()), serde_yaml::Value::Sequence(opt_vec));
             }
+            if let Some(default) = &patch.default
+                && let Ok(value) = serde_yaml::to_value(default) {
+                 new_input.insert(serde_yaml::Value::String("default".into()), value);
+            }
             inputs.push(serde_yaml::Value::Mapping(new_input));
             changes.push(format!("Added Input: {}", patch.name));
         }
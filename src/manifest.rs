@@ -14,6 +14,10 @@ pub struct ManifestNode {
     pub ui_hint: Option<String>, // New Field: "Dashboard", "Card", "Table", etc.
     #[serde(default)]
     pub dependencies: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>, // JSON Schema of an IO node's data; replaces the contract's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contract: Option<crate::finance::FinancialContract>, // Terms of a financial (Op 100) node
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -25,4 +29,8 @@ pub struct AetherManifest {
     #[serde(default)]
     pub imports: Vec<ManifestImport>,
     pub nodes: Vec<ManifestNode>,
+    #[serde(default)]
+    pub budget: Option<crate::kernel::ExecutionBudget>, // Per-project execution limits
+    #[serde(default)]
+    pub laws: Option<Vec<String>>, // Laws the Guard enforces ("no_riba" = newest, "no_riba@1" = pinned, "zakat"); None = all
 }
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Salt used by `hash` rules when the policy doesn't name its own secret
+pub const DEFAULT_SALT_SECRET: &str = "AETHER_SECRET_MASK_SALT";
+
+/// GATEWAY (Op 800) config blob: what to do with each sensitive field before data leaves the grid.
+/// Rules match a field name at any depth of the payload.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct MaskingPolicy {
+    #[serde(default)]
+    pub fields: Vec<FieldRule>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub salt_secret: Option<String>, // Name of the secret salting `hash` rules (`AETHER_SECRET_*`)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldRule {
+    pub field: String,
+    #[serde(flatten)]
+    pub action: MaskAction,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MaskAction {
+    Drop,
+    Redact {
+        #[serde(default = "default_redaction")]
+        with: String,
+    },
+    /// Keyed BLAKE3 of the value: stable for joins, not reversible without the salt
+    Hash,
+    /// Keep the first `keep` characters
+    Truncate { keep: usize },
+    /// Numbers into `bucket`-wide ranges, e.g. 4250 with bucket 1000 -> "4000-5000"
+    Generalize { bucket: f64 },
+}
+
+fn default_redaction() -> String {
+    "***".to_string()
+}
+
+impl MaskAction {
+    fn name(&self) -> &'static str {
+        match self {
+            MaskAction::Drop => "drop",
+            MaskAction::Redact { .. } => "redact",
+            MaskAction::Hash => "hash",
+            MaskAction::Truncate { .. } => "truncate",
+            MaskAction::Generalize { .. } => "generalize",
+        }
+    }
+}
+
+impl MaskingPolicy {
+    /// Rejects rules that can't mask anything and salts outside the secret namespace
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(name) = &self.salt_secret {
+            crate::io::check_secret_name(name)?;
+        }
+        for rule in &self.fields {
+            match rule.action {
+                MaskAction::Truncate { keep: 0 } => {
+                    return Err(format!("Mask '{}': truncate must keep at least 1 character (use redact)", rule.field));
+                },
+                MaskAction::Generalize { bucket } if !(bucket.is_finite() && bucket > 0.0) => {
+                    return Err(format!("Mask '{}': generalize bucket must be positive, got {}", rule.field, bucket));
+                },
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Masks `payload` and wraps it in the Sovereign Envelope, reporting each rule that fired
+    pub fn apply(&self, payload: Value) -> Result<Value, String> {
+        let key = if self.fields.iter().any(|rule| rule.action == MaskAction::Hash) {
+            let secret_name = self.salt_secret.as_deref().unwrap_or(DEFAULT_SALT_SECRET);
+            let salt = crate::io::secret(secret_name).map_err(|e| format!("Hash masking needs a salt: {}", e))?;
+            Some(blake3::derive_key("aether_store gateway mask v1", salt.as_bytes()))
+        } else {
+            None
+        };
+
+        let mut counts = vec![0usize; self.fields.len()];
+        let payload = self.mask(payload, key.as_ref(), &mut counts);
+        let masked_fields: Vec<Value> = self.fields.iter().zip(counts)
+            .filter(|(_, count)| *count > 0)
+            .map(|(rule, count)| json!({"field": rule.field, "action": rule.action.name(), "count": count}))
+            .collect();
+
+        Ok(json!({
+            "origin": "0xSOVEREIGN_ROOT",
+            "payload": payload,
+            "masked_fields": masked_fields,
+        }))
+    }
+
+    fn mask(&self, value: Value, key: Option<&[u8; 32]>, counts: &mut [usize]) -> Value {
+        match value {
+            Value::Array(items) => Value::Array(items.into_iter().map(|item| self.mask(item, key, counts)).collect()),
+            Value::Object(fields) => {
+                let mut out = serde_json::Map::new();
+                for (name, field_value) in fields {
+                    match self.fields.iter().position(|rule| rule.field == name) {
+                        Some(i) => {
+                            counts[i] += 1;
+                            if let Some(masked) = mask_value(&self.fields[i].action, field_value, key) {
+                                out.insert(name, masked);
+                            }
+                        },
+                        None => {
+                            out.insert(name, self.mask(field_value, key, counts));
+                        },
+                    }
+                }
+                Value::Object(out)
+            },
+            other => other,
+        }
+    }
+}
+
+/// The masked replacement for one value; `None` drops the field
+fn mask_value(action: &MaskAction, value: Value, key: Option<&[u8; 32]>) -> Option<Value> {
+    let text = || match &value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    Some(match action {
+        MaskAction::Drop => return None,
+        MaskAction::Redact { with } => Value::String(with.clone()),
+        MaskAction::Hash => {
+            let key = key.expect("hash key is derived whenever a hash rule exists");
+            let digest = blake3::keyed_hash(key, text().as_bytes()).to_hex();
+            Value::String(digest[..32].to_string())
+        },
+        MaskAction::Truncate { keep } => Value::String(text().chars().take(*keep).collect()),
+        MaskAction::Generalize { bucket } => match crate::predicate::as_number(&value) {
+            Some(n) => {
+                let low = (n / bucket).floor() * bucket;
+                Value::String(format!("{}-{}", low, low + bucket))
+            },
+            None if value.is_null() => Value::Null,
+            None => Value::String(default_redaction()),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(rules: Value) -> MaskingPolicy {
+        serde_json::from_value(json!({"fields": rules})).unwrap()
+    }
+
+    #[test]
+    fn each_action_masks_its_field_at_any_depth() {
+        let policy = policy(json!([
+            {"field": "ic", "action": "drop"},
+            {"field": "name", "action": "redact"},
+            {"field": "phone", "action": "truncate", "keep": 3},
+            {"field": "salary", "action": "generalize", "bucket": 1000.0},
+        ]));
+        let envelope = policy.apply(json!({
+            "staff": [
+                {"ic": "900101-14-5555", "name": "Aminah", "phone": "0123456789", "salary": 4250},
+                {"ic": "880202-10-1234", "name": "Farid", "salary": "not disclosed"},
+            ],
+            "owner": {"name": "Siti"},
+        })).unwrap();
+        assert_eq!(envelope["payload"], json!({
+            "staff": [
+                {"name": "***", "phone": "012", "salary": "4000-5000"},
+                {"name": "***", "salary": "***"},
+            ],
+            "owner": {"name": "***"},
+        }));
+        assert_eq!(envelope["masked_fields"], json!([
+            {"field": "ic", "action": "drop", "count": 2},
+            {"field": "name", "action": "redact", "count": 3},
+            {"field": "phone", "action": "truncate", "count": 1},
+            {"field": "salary", "action": "generalize", "count": 2},
+        ]));
+    }
+
+    #[test]
+    fn hashes_are_keyed_and_stable() {
+        let key = blake3::derive_key("aether_store gateway mask v1", b"salt");
+        let other = blake3::derive_key("aether_store gateway mask v1", b"pepper");
+        let hash = |key: &[u8; 32], value: Value| mask_value(&MaskAction::Hash, value, Some(key)).unwrap();
+        assert_eq!(hash(&key, json!("900101")), hash(&key, json!("900101")));
+        assert_eq!(hash(&key, json!(900101)), hash(&key, json!("900101")));
+        assert_ne!(hash(&key, json!("900101")), hash(&other, json!("900101")));
+        assert_eq!(hash(&key, json!("900101")).as_str().unwrap().len(), 32);
+    }
+
+    #[test]
+    fn hashing_needs_a_salt_secret() {
+        let mut policy = policy(json!([{"field": "ic", "action": "hash"}]));
+        policy.salt_secret = Some("AETHER_SECRET_MASK_SALT_NEVER_SET".to_string());
+        let err = policy.apply(json!({"ic": "900101"})).unwrap_err();
+        assert!(err.contains("AETHER_SECRET_MASK_SALT_NEVER_SET"), "{}", err);
+    }
+
+    #[test]
+    fn policies_are_validated() {
+        assert!(policy(json!([{"field": "name", "action": "truncate", "keep": 0}])).validate().is_err());
+        assert!(policy(json!([{"field": "salary", "action": "generalize", "bucket": 0.0}])).validate().is_err());
+        let mut salted = policy(json!([{"field": "ic", "action": "hash"}]));
+        salted.salt_secret = Some("HOME".to_string());
+        assert_eq!(salted.validate(), Err("Secret 'HOME' must be named AETHER_SECRET_<NAME>".to_string()));
+        salted.salt_secret = Some("AETHER_SECRET_MASK_SALT".to_string());
+        assert_eq!(salted.validate(), Ok(()));
+    }
+}
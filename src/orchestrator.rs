@@ -1,7 +1,16 @@
-use crate::{AetherLoom, AetherVault, AetherGuard, AetherManifest};
+use crate::{AetherLoom, AetherVault, AetherGuard, AetherManifest, LogicAtom};
+use crate::guard::Diagnostic;
 use std::collections::HashMap;
 use anyhow::{Result, Context};
 
+/// A built app: its root, the root node's UI hint, and what the Guard noticed on the way
+#[derive(Debug, Clone)]
+pub struct BuildReport {
+    pub root_hash: String,
+    pub ui_hint: Option<String>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
 pub struct AetherOrchestrator {
     loom: AetherLoom,
     vault: AetherVault,
@@ -17,7 +26,8 @@ impl AetherOrchestrator {
         })
     }
 
-    pub fn build_app(&self, manifest_raw: &str) -> Result<(String, Option<String>)> {
+    /// Parses a manifest and resolves its `extends` chain (parent first, child wins)
+    pub fn load_manifest(&self, manifest_raw: &str) -> Result<AetherManifest> {
         let manifest: AetherManifest = serde_yaml::from_str(manifest_raw)
             .context("Failed to parse manifest YAML")?;
         
@@ -48,10 +58,44 @@ impl AetherOrchestrator {
             let mut merged_nodes = parent.nodes;
             merged_nodes.extend(final_manifest.nodes);
             final_manifest.nodes = merged_nodes;
+
+            // 3. Budget: Child overrides Parent as a whole
+            if final_manifest.budget.is_none() {
+                final_manifest.budget = parent.budget;
+            }
+
+            // 4. Laws: Child overrides Parent as a whole
+            if final_manifest.laws.is_none() {
+                final_manifest.laws = parent.laws;
+            }
         }
 
+        Ok(final_manifest)
+    }
+
+    pub fn build_app(&self, manifest_raw: &str) -> Result<(String, Option<String>)> {
+        self.build_app_with_inputs(manifest_raw, &[])
+    }
+
+    /// Builds a manifest whose `{{placeholders}}` are bound at execution time.
+    /// Each placeholder becomes a PARAM (Op 20) atom typed by the manifest's `inputs`,
+    /// then `extra_inputs` (e.g. a catalog product's form), else free text.
+    pub fn build_app_with_inputs(&self, manifest_raw: &str, extra_inputs: &[crate::InputSchema]) -> Result<(String, Option<String>)> {
+        self.build(manifest_raw, extra_inputs).map(|report| (report.root_hash, report.ui_hint))
+    }
+
+    /// `build_app_with_inputs`, plus build-time diagnostics (e.g. contradictory filter chains)
+    pub fn build(&self, manifest_raw: &str, extra_inputs: &[crate::InputSchema]) -> Result<BuildReport> {
+        let final_manifest = self.load_manifest(manifest_raw)?;
+
         println!("[Orchestrator] Building App: {}", final_manifest.app_name);
-        // Laws are applied via Registry imports now
+        // Every atom of the app is held to the manifest's laws
+        let guard = match &final_manifest.laws {
+            Some(laws) => self.guard.with_laws(laws)?,
+            None => self.guard.clone(),
+        };
+        let law_names: Vec<String> = guard.laws().iter().map(|law| format!("{}@{}", law.name(), law.version())).collect();
+        println!("[Orchestrator] Laws: [{}]", law_names.join(", "));
 
         // 0. Resolve Imports
         let mut import_map: HashMap<String, String> = HashMap::new();
@@ -59,7 +103,10 @@ impl AetherOrchestrator {
             import_map.insert(import_item.name, import_item.hash);
         }
 
+        let mut param_map: HashMap<String, String> = HashMap::new();
         let mut node_map: HashMap<String, String> = HashMap::new();
+        let mut built: HashMap<String, (String, LogicAtom)> = HashMap::new(); // Hash -> (node name, atom)
+        let mut order: Vec<String> = Vec::new(); // Node hashes in manifest order
         let mut root_hint: Option<String> = None;
 
         for node in final_manifest.nodes {
@@ -104,6 +151,25 @@ impl AetherOrchestrator {
                 return Err(anyhow::anyhow!("Node '{}' must have either 'intent' or 'use_ref'", node.name));
             };
 
+            // 1.4 Declared data shape: an IO node's contract is checked against it at run time
+            // and seeds the record types the graph is checked against below
+            if let Some(schema) = &node.schema {
+                if atom.op_code != 500 {
+                    return Err(anyhow::anyhow!("Node '{}' declares a schema but is not an IO (Op 500) node", node.name));
+                }
+                let mut contract: crate::IOContract = serde_json::from_slice(&crate::read_blob(&atom.storage_ref)?)?;
+                contract.schema = schema.clone();
+                atom.storage_ref = crate::write_blob(&serde_json::to_vec(&contract)?)?;
+            }
+
+            // A financial node's terms, held to the no_riba law when persisted
+            if let Some(contract) = &node.contract {
+                if atom.op_code != 100 {
+                    return Err(anyhow::anyhow!("Node '{}' declares a contract but is not a financial (Op 100) node", node.name));
+                }
+                atom.storage_ref = crate::write_blob(&serde_json::to_vec(contract)?)?;
+            }
+
             // 1.5 Link Dependencies
             for dep_name in &node.dependencies {
                 if let Some(dep_hash) = node_map.get(dep_name) {
@@ -113,23 +179,94 @@ impl AetherOrchestrator {
                 }
             }
 
+            // 1.6 Link Parameters: one PARAM atom per placeholder, appended after the data inputs
+            for name in crate::product::placeholders(node.intent.as_deref().unwrap_or_default()) {
+                let param_hash = match param_map.get(&name) {
+                    Some(hash) => hash.clone(),
+                    None => {
+                        let schema = final_manifest.inputs.iter().chain(extra_inputs)
+                            .find(|input| input.name == name)
+                            .cloned()
+                            .unwrap_or_else(|| crate::InputSchema::text(&name));
+                        let param = crate::LogicAtom {
+                            op_code: crate::OP_PARAM,
+                            inputs: vec![],
+                            storage_ref: crate::write_blob(&serde_json::to_vec(&schema)?)?,
+                            context_id: final_manifest.app_name.clone(),
+                        };
+                        let hash = self.vault.persist_verified(&param, &guard)
+                            .map_err(|e| anyhow::anyhow!("Guard rejected input '{}': {}", name, e))?;
+                        param_map.insert(name.clone(), hash.clone());
+                        hash
+                    }
+                };
+                atom.inputs.push(param_hash);
+            }
+
             // 2. Guard: Verify
-            let hash = self.vault.persist_verified(&atom, &self.guard)
-                .with_context(|| format!("Guard rejected node '{}'", node.name))?;
+            let hash = self.vault.persist_verified(&atom, &guard)
+                .map_err(|e| anyhow::anyhow!("Guard rejected node '{}': {}", node.name, e))?;
             
             println!("[Orchestrator] Node '{}' Persisted. Hash: {}", node.name, hash);
             node_map.insert(node.name.clone(), hash.clone());
+            order.push(hash.clone());
+            built.insert(hash, (node.name.clone(), atom));
+        }
+
+        // 3. Types: every node against the record shapes flowing into it
+        let names: HashMap<String, String> = built.iter().map(|(hash, (name, _))| (hash.clone(), name.clone())).collect();
+        crate::types::check_graph(&self.vault, &order, &names)
+            .map_err(|e| anyhow::anyhow!("Type error: {}", e))?;
+
+        // 4. Guard: Filter chains that can never match or filter nothing
+        let mut diagnostics = Vec::new();
+        let mut filters: Vec<&(String, LogicAtom)> = built.values().filter(|(_, atom)| atom.op_code == 2).collect();
+        filters.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, atom) in filters {
+            let chain = self.filter_chain(name, atom, &built);
+            if let Some(diagnostic) = guard.check_filter_chain(&chain) {
+                println!("[Orchestrator] Diagnostic ({:?}): {}", diagnostic.kind, diagnostic.message);
+                diagnostics.push(diagnostic);
+            }
         }
 
         // Return the Root Hash of the Application
-        match node_map.get("root") {
-            Some(h) => Ok((h.clone(), root_hint)),
+        let root_hash = match node_map.get("root") {
+            Some(h) => h.clone(),
             None => {
                 // Return the last one if 'root' is not defined, explicitly for demo purposes
                 // or just an empty string if nothing processed.
-                 let last = node_map.values().last().cloned().unwrap_or_default();
-                 Ok((last, root_hint))
+                 node_map.values().last().cloned().unwrap_or_default()
+            }
+        };
+        Ok(BuildReport { root_hash, ui_hint: root_hint, diagnostics })
+    }
+
+    /// The predicates of a FILTER and the filters upstream of it, upstream first. The walk follows
+    /// single data inputs through FILTER, SORT and LIMIT, which keep records as they are.
+    fn filter_chain(&self, name: &str, atom: &LogicAtom, built: &HashMap<String, (String, LogicAtom)>) -> Vec<(String, crate::predicate::Predicate)> {
+        let predicate = |atom: &LogicAtom| {
+            crate::read_blob(&atom.storage_ref).ok()
+                .and_then(|blob| serde_json::from_slice::<crate::predicate::Predicate>(&blob).ok())
+        };
+        let mut chain: Vec<(String, crate::predicate::Predicate)> = predicate(atom).map(|p| (name.to_string(), p)).into_iter().collect();
+        let mut current = atom;
+        loop {
+            let data_inputs: Vec<&(String, LogicAtom)> = current.inputs.iter()
+                .filter_map(|input| built.get(input))
+                .filter(|(_, input)| input.op_code != crate::OP_PARAM)
+                .collect();
+            let [(upstream_name, upstream)] = data_inputs.as_slice() else {
+                break;
+            };
+            match upstream.op_code {
+                2 => chain.extend(predicate(upstream).map(|p| (upstream_name.clone(), p))),
+                4 | 5 => {},
+                _ => break,
             }
+            current = upstream;
         }
+        chain.reverse();
+        chain
     }
 }
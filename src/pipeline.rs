@@ -0,0 +1,524 @@
+use crate::predicate::{as_number, lookup};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+
+// --- SORT (Op 4) ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SortConfig {
+    pub keys: Vec<SortKey>, // Earlier keys take precedence
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SortKey {
+    pub field: String,
+    #[serde(default)]
+    pub descending: bool,
+    #[serde(default)]
+    pub mode: SortMode,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SortMode {
+    #[default]
+    Auto,    // Numbers and numeric strings numerically (as FILTER reads them), everything else as text
+    Numeric,
+    String,
+}
+
+/// Stable multi-key sort. Rows missing a key always go last, whatever the direction.
+pub fn sort(mut rows: Vec<Value>, config: &SortConfig) -> Vec<Value> {
+    rows.sort_by(|a, b| {
+        for key in &config.keys {
+            let ordering = match (sort_value(a, key), sort_value(b, key)) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(x), Some(y)) => {
+                    let ordering = x.cmp(&y);
+                    if key.descending { ordering.reverse() } else { ordering }
+                }
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        Ordering::Equal
+    });
+    rows
+}
+
+enum SortValue {
+    Number(f64),
+    Text(String),
+}
+
+impl SortValue {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SortValue::Number(x), SortValue::Number(y)) => x.total_cmp(y),
+            (SortValue::Text(x), SortValue::Text(y)) => x.cmp(y),
+            // Mixed column: numbers before text
+            (SortValue::Number(_), SortValue::Text(_)) => Ordering::Less,
+            (SortValue::Text(_), SortValue::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+fn sort_value(row: &Value, key: &SortKey) -> Option<SortValue> {
+    let value = lookup(row, &key.field).filter(|v| !v.is_null())?;
+    scalar_sort_value(value, key.mode)
+}
+
+fn scalar_sort_value(value: &Value, mode: SortMode) -> Option<SortValue> {
+    match mode {
+        SortMode::Numeric => as_number(value).map(SortValue::Number),
+        SortMode::String => Some(SortValue::Text(text_of(value))),
+        SortMode::Auto => Some(match as_number(value) {
+            Some(n) => SortValue::Number(n),
+            None => SortValue::Text(text_of(value)),
+        }),
+    }
+}
+
+fn text_of(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+// --- LIMIT (Op 5) ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LimitConfig {
+    pub limit: Option<usize>, // None: everything after the offset
+    #[serde(default)]
+    pub offset: usize,
+}
+
+pub fn limit(rows: Vec<Value>, config: &LimitConfig) -> Vec<Value> {
+    let rest = rows.into_iter().skip(config.offset);
+    match config.limit {
+        Some(n) => rest.take(n).collect(),
+        None => rest.collect(),
+    }
+}
+
+// --- PROJECT (Op 6) ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectConfig {
+    pub fields: Vec<ProjectField>,
+    /// Keep unlisted fields too (pure rename)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub keep_others: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ProjectField {
+    pub field: String,
+    #[serde(rename = "as", default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+impl ProjectField {
+    pub fn output_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.field)
+    }
+}
+
+/// Keeps (and optionally renames) the listed fields of every record. Missing fields become null
+/// so every output row has the same shape.
+pub fn project(rows: Vec<Value>, config: &ProjectConfig) -> Vec<Value> {
+    rows.into_iter().map(|row| project_row(row, config)).collect()
+}
+
+/// PROJECT for a single record (used when rows are streamed)
+pub fn project_row(row: Value, config: &ProjectConfig) -> Value {
+    let mut out = Map::new();
+    for spec in &config.fields {
+        let value = lookup(&row, &spec.field).cloned().unwrap_or(Value::Null);
+        out.insert(spec.output_name().to_string(), value);
+    }
+    match row {
+        Value::Object(fields) if config.keep_others => {
+            for (key, value) in fields {
+                if !config.fields.iter().any(|spec| spec.field == key) && !out.contains_key(&key) {
+                    out.insert(key, value);
+                }
+            }
+        },
+        _ => {}
+    }
+    Value::Object(out)
+}
+
+// --- AGGREGATE / GROUP BY (Op 7) ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AggregateConfig {
+    #[serde(default)]
+    pub group_by: Vec<String>, // Empty: one summary record over all rows
+    pub aggregates: Vec<Aggregate>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Aggregate {
+    pub func: AggregateFn,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>, // Only `count` may omit it (counts rows)
+    #[serde(rename = "as", default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateFn {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    DistinctCount,
+}
+
+impl AggregateFn {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Count => "count",
+            Self::Sum => "sum",
+            Self::Avg => "avg",
+            Self::Min => "min",
+            Self::Max => "max",
+            Self::DistinctCount => "distinct_count",
+        }
+    }
+}
+
+impl Aggregate {
+    /// `as` if given, else e.g. "avg_price" or "count"
+    pub fn output_name(&self) -> String {
+        match (&self.alias, &self.field) {
+            (Some(alias), _) => alias.clone(),
+            (None, Some(field)) => format!("{}_{}", self.func.name(), field),
+            (None, None) => self.func.name().to_string(),
+        }
+    }
+}
+
+/// Groups rows by the `group_by` fields (first-seen order) and computes one record per group
+pub fn aggregate(rows: Vec<Value>, config: &AggregateConfig) -> Vec<Value> {
+    let mut order: Vec<Vec<Value>> = Vec::new();
+    let mut groups: std::collections::HashMap<String, Vec<Value>> = std::collections::HashMap::new();
+
+    for row in rows {
+        let key: Vec<Value> = config.group_by.iter()
+            .map(|field| lookup(&row, field).cloned().unwrap_or(Value::Null))
+            .collect();
+        let canonical = Value::Array(key.clone()).to_string();
+        groups.entry(canonical).or_insert_with(|| {
+            order.push(key);
+            Vec::new()
+        }).push(row);
+    }
+    if order.is_empty() && config.group_by.is_empty() {
+        order.push(Vec::new());
+    }
+
+    order.into_iter().map(|key| {
+        let members = groups.remove(&Value::Array(key.clone()).to_string()).unwrap_or_default();
+        let mut out = Map::new();
+        for (field, value) in config.group_by.iter().zip(key) {
+            out.insert(field.clone(), value);
+        }
+        for agg in &config.aggregates {
+            out.insert(agg.output_name(), compute(agg, &members));
+        }
+        Value::Object(out)
+    }).collect()
+}
+
+fn compute(agg: &Aggregate, rows: &[Value]) -> Value {
+    let values: Vec<&Value> = match &agg.field {
+        Some(field) => rows.iter().filter_map(|row| lookup(row, field)).filter(|v| !v.is_null()).collect(),
+        None => rows.iter().collect(),
+    };
+    match agg.func {
+        AggregateFn::Count => Value::from(values.len()),
+        AggregateFn::DistinctCount => {
+            let distinct: std::collections::HashSet<String> = values.iter().map(|v| v.to_string()).collect();
+            Value::from(distinct.len())
+        },
+        AggregateFn::Sum => sum(&values).unwrap_or(Value::from(0)),
+        AggregateFn::Avg => {
+            let numbers: Vec<f64> = values.iter().filter_map(|v| as_number(v)).collect();
+            if numbers.is_empty() {
+                Value::Null
+            } else {
+                Value::from(numbers.iter().sum::<f64>() / numbers.len() as f64)
+            }
+        },
+        AggregateFn::Min | AggregateFn::Max => {
+            let ranked = values.iter().filter_map(|v| Some((scalar_sort_value(v, SortMode::Auto)?, *v)));
+            let best = if agg.func == AggregateFn::Min {
+                ranked.min_by(|a, b| a.0.cmp(&b.0))
+            } else {
+                ranked.max_by(|a, b| a.0.cmp(&b.0))
+            };
+            best.map(|(_, v)| v.clone()).unwrap_or(Value::Null)
+        },
+    }
+}
+
+/// Integer sum while every value is an integer and nothing overflows, float sum otherwise
+fn sum(values: &[&Value]) -> Option<Value> {
+    let mut int_sum: Option<i64> = Some(0);
+    let mut float_sum = 0.0;
+    let mut seen = false;
+    for value in values {
+        let Some(number) = as_number(value) else { continue };
+        seen = true;
+        float_sum += number;
+        int_sum = match (int_sum, value.as_i64()) {
+            (Some(acc), Some(n)) => acc.checked_add(n),
+            _ => None,
+        };
+    }
+    if !seen {
+        return None;
+    }
+    Some(int_sum.map(Value::from).unwrap_or_else(|| Value::from(float_sum)))
+}
+
+// --- JOIN (Op 8) ---
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JoinConfig {
+    #[serde(default)]
+    pub kind: JoinKind,
+    pub on: Vec<JoinKey>,
+    /// Prepended to right-side fields whose names collide with left-side fields
+    #[serde(default = "default_join_prefix")]
+    pub prefix: String,
+}
+
+fn default_join_prefix() -> String {
+    "right_".to_string()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinKind {
+    #[default]
+    Inner,
+    Left, // Unmatched left rows are kept as-is
+    Anti, // Only left rows without a match
+}
+
+/// `"station"` joins on the same field name; `{left, right}` names each side
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum JoinKey {
+    Same(String),
+    Pair { left: String, right: String },
+}
+
+impl JoinKey {
+    pub fn left(&self) -> &str {
+        match self {
+            JoinKey::Same(field) => field,
+            JoinKey::Pair { left, .. } => left,
+        }
+    }
+
+    pub fn right(&self) -> &str {
+        match self {
+            JoinKey::Same(field) => field,
+            JoinKey::Pair { right, .. } => right,
+        }
+    }
+}
+
+/// Hash join of input 0 (left) against input 1 (right). Rows with a null or missing key never match.
+/// Fails when a prefixed right-side field would overwrite an existing field. Stops once it has
+/// more than `max_rows` rows, so an oversized join is never built in full.
+pub fn join(left: Vec<Value>, right: Vec<Value>, config: &JoinConfig, max_rows: usize) -> Result<Vec<Value>, String> {
+    let mut index: std::collections::HashMap<String, Vec<&Value>> = std::collections::HashMap::new();
+    for row in &right {
+        if let Some(key) = join_key(row, config.on.iter().map(JoinKey::right)) {
+            index.entry(key).or_default().push(row);
+        }
+    }
+
+    let mut out = Vec::new();
+    for row in left {
+        if out.len() > max_rows {
+            break;
+        }
+        let matches = join_key(&row, config.on.iter().map(JoinKey::left))
+            .and_then(|key| index.get(&key));
+        match (config.kind, matches) {
+            (JoinKind::Anti, None) | (JoinKind::Left, None) => out.push(row),
+            (JoinKind::Anti, Some(_)) | (JoinKind::Inner, None) => {},
+            (JoinKind::Inner, Some(partners)) | (JoinKind::Left, Some(partners)) => {
+                for partner in partners {
+                    out.push(merge_rows(&row, partner, config)?);
+                    if out.len() > max_rows {
+                        break;
+                    }
+                }
+            },
+        }
+    }
+    Ok(out)
+}
+
+/// Canonical key text; numbers are normalised so 5, 5.0 and "5" meet
+fn join_key<'a>(row: &Value, fields: impl Iterator<Item = &'a str>) -> Option<String> {
+    let mut parts = Vec::new();
+    for field in fields {
+        let value = lookup(row, field).filter(|v| !v.is_null())?;
+        parts.push(match number_key(value) {
+            Some(n) => format!("n:{}", n),
+            None => format!("v:{}", value),
+        });
+    }
+    Some(parts.join("\u{1f}"))
+}
+
+/// Integers keep their exact value (ids above 2^53 must not collide); whole floats key as integers
+fn number_key(value: &Value) -> Option<String> {
+    let exact = match value {
+        Value::Number(n) if n.is_i64() || n.is_u64() => Some(n.to_string()),
+        Value::String(s) => s.trim().parse::<i128>().ok().map(|n| n.to_string()),
+        _ => None,
+    };
+    exact.or_else(|| {
+        let n = as_number(value)?;
+        // Past 2^53 a float no longer names a single integer
+        Some(match n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+            true => format!("{}", n as i64),
+            false => format!("{}", n),
+        })
+    })
+}
+
+fn merge_rows(left: &Value, right: &Value, config: &JoinConfig) -> Result<Value, String> {
+    let mut out = match left {
+        Value::Object(fields) => fields.clone(),
+        other => {
+            let mut wrapped = Map::new();
+            wrapped.insert("left".to_string(), other.clone());
+            wrapped
+        }
+    };
+    if let Value::Object(fields) = right {
+        for (key, value) in fields {
+            // A same-named join key already carries the matching value
+            if config.on.iter().any(|k| matches!(k, JoinKey::Same(f) if f == key)) {
+                continue;
+            }
+            let name = match out.contains_key(key) {
+                true => {
+                    let prefixed = format!("{}{}", config.prefix, key);
+                    if out.contains_key(&prefixed) {
+                        return Err(format!("right field '{}' and its prefixed name '{}' are both taken; choose another prefix", key, prefixed));
+                    }
+                    prefixed
+                },
+                false => key.clone(),
+            };
+            out.insert(name, value.clone());
+        }
+    }
+    Ok(Value::Object(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config<T: serde::de::DeserializeOwned>(value: Value) -> T {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn sales() -> Vec<Value> {
+        vec![
+            json!({"region": "north", "amount": 10, "rep": "a"}),
+            json!({"region": "south", "amount": 2.5, "rep": "b"}),
+            json!({"region": "north", "amount": "5", "rep": "a"}),
+            json!({"region": "north", "amount": null, "rep": "c"}),
+        ]
+    }
+
+    #[test]
+    fn aggregates_group_in_first_seen_order() {
+        let config: AggregateConfig = config(json!({
+            "group_by": ["region"],
+            "aggregates": [
+                {"func": "count"},
+                {"func": "sum", "field": "amount"},
+                {"func": "avg", "field": "amount", "as": "mean"},
+                {"func": "max", "field": "amount"},
+                {"func": "distinct_count", "field": "rep"},
+            ]
+        }));
+        assert_eq!(aggregate(sales(), &config), vec![
+            json!({"region": "north", "count": 3, "sum_amount": 15.0, "mean": 7.5, "max_amount": 10, "distinct_count_rep": 2}),
+            json!({"region": "south", "count": 1, "sum_amount": 2.5, "mean": 2.5, "max_amount": 2.5, "distinct_count_rep": 1}),
+        ]);
+    }
+
+    #[test]
+    fn aggregates_without_groups_summarise_everything() {
+        let config: AggregateConfig = config(json!({"aggregates": [{"func": "sum", "field": "n"}, {"func": "min", "field": "n"}]}));
+        assert_eq!(aggregate(vec![json!({"n": 3}), json!({"n": 4})], &config), vec![json!({"sum_n": 7, "min_n": 3})]);
+        // Even over no rows
+        assert_eq!(aggregate(vec![], &config), vec![json!({"sum_n": 0, "min_n": null})]);
+    }
+
+    fn stations() -> Vec<Value> {
+        vec![json!({"station": 1, "name": "KL Sentral"}), json!({"station": "2", "name": "Pasar Seni"})]
+    }
+
+    fn readings() -> Vec<Value> {
+        vec![json!({"station": 1.0, "pm25": 12}), json!({"station": 1, "pm25": 15}), json!({"station": null, "pm25": 99})]
+    }
+
+    #[test]
+    fn joins_match_keys_across_number_forms() {
+        let inner: JoinConfig = config(json!({"on": ["station"]}));
+        assert_eq!(join(stations(), readings(), &inner, 100).unwrap(), vec![
+            json!({"station": 1, "name": "KL Sentral", "pm25": 12}),
+            json!({"station": 1, "name": "KL Sentral", "pm25": 15}),
+        ]);
+        let left: JoinConfig = config(json!({"kind": "left", "on": ["station"]}));
+        assert_eq!(join(stations(), readings(), &left, 100).unwrap().len(), 3);
+        let anti: JoinConfig = config(json!({"kind": "anti", "on": ["station"]}));
+        assert_eq!(join(stations(), readings(), &anti, 100).unwrap(), vec![stations()[1].clone()]);
+    }
+
+    #[test]
+    fn joins_prefix_colliding_fields() {
+        let config: JoinConfig = config(json!({"on": [{"left": "id", "right": "owner"}]}));
+        let left = vec![json!({"id": 7, "name": "Aminah"})];
+        let right = vec![json!({"owner": 7, "name": "Proton Saga"})];
+        assert_eq!(join(left.clone(), right.clone(), &config, 100).unwrap(), vec![
+            json!({"id": 7, "name": "Aminah", "owner": 7, "right_name": "Proton Saga"}),
+        ]);
+        let taken = vec![json!({"id": 7, "name": "Aminah", "right_name": "x"})];
+        assert!(join(taken, right, &config, 100).is_err());
+    }
+
+    #[test]
+    fn large_integer_keys_do_not_collide() {
+        let config: JoinConfig = config(json!({"on": ["id"]}));
+        let left = vec![json!({"id": 9_007_199_254_740_993_i64})];
+        let right = vec![json!({"id": 9_007_199_254_740_992_i64, "hit": true})];
+        assert!(join(left, right, &config, 100).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,297 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+/// FILTER (Op 2) config blob.
+/// Leaves keep the original `{field, op, val}` shape so existing filter atoms still parse;
+/// `and` / `or` / `not` wrap them into trees.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Predicate {
+    And { and: Vec<Predicate> },
+    Or { or: Vec<Predicate> },
+    Not { not: Box<Predicate> },
+    Compare {
+        field: String, // Dotted paths reach into nested records, e.g. "address.city"
+        op: CompareOp,
+        #[serde(default, skip_serializing_if = "Value::is_null")]
+        val: Value,
+    },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+    #[serde(rename = "in")]
+    In,
+    #[serde(rename = "not_in")]
+    NotIn,
+    #[serde(rename = "contains")]
+    Contains,
+    #[serde(rename = "not_contains")]
+    NotContains,
+    #[serde(rename = "matches")]
+    Matches,
+    #[serde(rename = "is_null")]
+    IsNull,
+    #[serde(rename = "is_not_null")]
+    IsNotNull,
+}
+
+impl CompareOp {
+    /// Parses the operator words the Loom accepts in intents ("<=", "in", "not in", "is null", ...)
+    pub fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            ">" => Self::Gt,
+            "<" => Self::Lt,
+            ">=" => Self::Ge,
+            "<=" => Self::Le,
+            "==" | "=" => Self::Eq,
+            "!=" => Self::Ne,
+            "in" => Self::In,
+            "not_in" | "not in" => Self::NotIn,
+            "contains" => Self::Contains,
+            "not_contains" | "not contains" => Self::NotContains,
+            "matches" | "=~" => Self::Matches,
+            "is_null" | "is null" => Self::IsNull,
+            "is_not_null" | "is not null" => Self::IsNotNull,
+            _ => return None,
+        })
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Gt => ">",
+            Self::Lt => "<",
+            Self::Ge => ">=",
+            Self::Le => "<=",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::In => "in",
+            Self::NotIn => "not in",
+            Self::Contains => "contains",
+            Self::NotContains => "not contains",
+            Self::Matches => "matches",
+            Self::IsNull => "is null",
+            Self::IsNotNull => "is not null",
+        }
+    }
+
+    /// Operators that take no `val`
+    pub fn is_unary(&self) -> bool {
+        matches!(self, Self::IsNull | Self::IsNotNull)
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::And { and } => write_joined(f, and, " and "),
+            Predicate::Or { or } => write_joined(f, or, " or "),
+            Predicate::Not { not } => write!(f, "not ({})", not),
+            Predicate::Compare { field, op, .. } if op.is_unary() => write!(f, "{} {}", field, op.symbol()),
+            Predicate::Compare { field, op, val } => write!(f, "{} {} {}", field, op.symbol(), val),
+        }
+    }
+}
+
+fn write_joined(f: &mut fmt::Formatter<'_>, items: &[Predicate], sep: &str) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, "{}", sep)?;
+        }
+        write!(f, "{}", item)?;
+    }
+    write!(f, ")")
+}
+
+impl Predicate {
+    /// Validates the tree (regexes, list operands) and prepares it for evaluation
+    pub fn compile(&self) -> Result<Matcher, String> {
+        Ok(match self {
+            Predicate::And { and } => Matcher::And(and.iter().map(Predicate::compile).collect::<Result<_, _>>()?),
+            Predicate::Or { or } => Matcher::Or(or.iter().map(Predicate::compile).collect::<Result<_, _>>()?),
+            Predicate::Not { not } => Matcher::Not(Box::new(not.compile()?)),
+            Predicate::Compare { field, op, val } => {
+                let regex = match op {
+                    CompareOp::Matches => {
+                        let pattern = val.as_str()
+                            .ok_or_else(|| format!("'{} matches' expects a string pattern", field))?;
+                        Some(Regex::new(pattern).map_err(|e| format!("Invalid regex for '{}': {}", field, e))?)
+                    },
+                    CompareOp::In | CompareOp::NotIn if !val.is_array() => {
+                        return Err(format!("'{} {}' expects a list value", field, op.symbol()));
+                    },
+                    _ => None,
+                };
+                Matcher::Compare { field: field.clone(), op: *op, val: val.clone(), regex }
+            },
+        })
+    }
+}
+
+/// A compiled `Predicate`, ready to test records
+pub enum Matcher {
+    And(Vec<Matcher>),
+    Or(Vec<Matcher>),
+    Not(Box<Matcher>),
+    Compare { field: String, op: CompareOp, val: Value, regex: Option<Regex> },
+}
+
+impl Matcher {
+    pub fn matches(&self, item: &Value) -> bool {
+        match self {
+            Matcher::And(all) => all.iter().all(|m| m.matches(item)),
+            Matcher::Or(any) => any.iter().any(|m| m.matches(item)),
+            Matcher::Not(inner) => !inner.matches(item),
+            Matcher::Compare { field, op, val, regex } => {
+                let actual = lookup(item, field).filter(|v| !v.is_null());
+                match op {
+                    CompareOp::IsNull => actual.is_none(),
+                    CompareOp::IsNotNull => actual.is_some(),
+                    CompareOp::Eq => actual.is_some_and(|a| loose_eq(a, val)),
+                    CompareOp::Ne => !actual.is_some_and(|a| loose_eq(a, val)),
+                    CompareOp::Gt => compare(actual, val).is_some_and(|o| o.is_gt()),
+                    CompareOp::Lt => compare(actual, val).is_some_and(|o| o.is_lt()),
+                    CompareOp::Ge => compare(actual, val).is_some_and(|o| o.is_ge()),
+                    CompareOp::Le => compare(actual, val).is_some_and(|o| o.is_le()),
+                    CompareOp::In => actual.is_some_and(|a| in_list(a, val)),
+                    CompareOp::NotIn => !actual.is_some_and(|a| in_list(a, val)),
+                    CompareOp::Contains => actual.is_some_and(|a| contains(a, val)),
+                    CompareOp::NotContains => !actual.is_some_and(|a| contains(a, val)),
+                    CompareOp::Matches => match (actual.and_then(Value::as_str), regex) {
+                        (Some(text), Some(re)) => re.is_match(text),
+                        _ => false,
+                    },
+                }
+            },
+        }
+    }
+}
+
+/// Resolves a field on a record. An exact key wins; otherwise the name is walked as a
+/// dotted path (`address.city`, `photos.0.url`).
+pub fn lookup<'a>(item: &'a Value, field: &str) -> Option<&'a Value> {
+    if let Some(value) = item.get(field) {
+        return Some(value);
+    }
+    if !field.contains('.') {
+        return None;
+    }
+    field.split('.').try_fold(item, |current, segment| match current {
+        Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => current.get(segment),
+    })
+}
+
+/// Numeric view of a value; numeric strings ("500000") count as numbers
+pub fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+}
+
+/// Equality that treats `5`, `5.0` and `"5"` as the same number
+pub fn loose_eq(a: &Value, b: &Value) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a, b) {
+        (Value::Number(_), _) | (_, Value::Number(_)) => match (as_number(a), as_number(b)) {
+            (Some(x), Some(y)) => x == y,
+            _ => false,
+        },
+        (Value::String(s), Value::Bool(flag)) | (Value::Bool(flag), Value::String(s)) => s.eq_ignore_ascii_case(&flag.to_string()),
+        _ => false,
+    }
+}
+
+/// Orders numbers numerically and strings lexically (ISO dates sort correctly); mixed kinds don't compare
+fn compare(actual: Option<&Value>, val: &Value) -> Option<std::cmp::Ordering> {
+    let actual = actual?;
+    if let (Some(x), Some(y)) = (as_number(actual), as_number(val)) {
+        return x.partial_cmp(&y);
+    }
+    match (actual, val) {
+        (Value::String(x), Value::String(y)) => Some(x.cmp(y)),
+        _ => None,
+    }
+}
+
+fn in_list(actual: &Value, list: &Value) -> bool {
+    list.as_array().is_some_and(|items| items.iter().any(|item| loose_eq(actual, item)))
+}
+
+fn contains(actual: &Value, needle: &Value) -> bool {
+    match actual {
+        Value::String(text) => match needle {
+            Value::String(n) => text.contains(n.as_str()),
+            other => text.contains(&other.to_string()),
+        },
+        Value::Array(items) => items.iter().any(|item| loose_eq(item, needle)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_operator_words() {
+        assert_eq!(CompareOp::parse("="), Some(CompareOp::Eq));
+        assert_eq!(CompareOp::parse("not in"), Some(CompareOp::NotIn));
+        assert_eq!(CompareOp::parse("is not null"), Some(CompareOp::IsNotNull));
+        assert_eq!(CompareOp::parse("=~"), Some(CompareOp::Matches));
+        assert_eq!(CompareOp::parse("between"), None);
+    }
+
+    #[test]
+    fn legacy_leaf_and_trees_deserialize() {
+        let leaf: Predicate = serde_json::from_value(json!({"field": "built", "op": ">", "val": 2020})).unwrap();
+        assert_eq!(leaf, Predicate::Compare { field: "built".into(), op: CompareOp::Gt, val: json!(2020) });
+        let tree: Predicate = serde_json::from_value(json!({"or": [{"not": {"field": "a", "op": "is_null"}}, leaf.clone()]})).unwrap();
+        assert_eq!(tree.to_string(), "(not (a is null) or built > 2020)");
+    }
+
+    #[test]
+    fn matches_nested_fields_and_loose_numbers() {
+        let matcher = Predicate::And { and: vec![
+            Predicate::Compare { field: "address.city".into(), op: CompareOp::In, val: json!(["KL", "Penang"]) },
+            Predicate::Compare { field: "price".into(), op: CompareOp::Le, val: json!(500000) },
+        ] }.compile().unwrap();
+        assert!(matcher.matches(&json!({"address": {"city": "KL"}, "price": "450000"})));
+        assert!(!matcher.matches(&json!({"address": {"city": "Ipoh"}, "price": 1})));
+        assert!(!matcher.matches(&json!({"price": 1})));
+    }
+
+    #[test]
+    fn missing_fields_fail_comparisons_but_pass_negations() {
+        let ne = Predicate::Compare { field: "x".into(), op: CompareOp::Ne, val: json!(1) }.compile().unwrap();
+        let gt = Predicate::Compare { field: "x".into(), op: CompareOp::Gt, val: json!(1) }.compile().unwrap();
+        assert!(ne.matches(&json!({})));
+        assert!(!gt.matches(&json!({"x": null})));
+    }
+
+    #[test]
+    fn compile_rejects_bad_operands() {
+        assert!(Predicate::Compare { field: "x".into(), op: CompareOp::In, val: json!(1) }.compile().is_err());
+        assert!(Predicate::Compare { field: "x".into(), op: CompareOp::Matches, val: json!("(") }.compile().is_err());
+    }
+}
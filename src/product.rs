@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct InputSchema {
     pub name: String,       // Variable name in template (e.g. "station_type")
     pub label: String,      // Human readable label (e.g. "Station Type")
-    pub input_type: String, // "text", "select", "number"
+    pub input_type: String, // "text", "select", "number", "boolean"
     pub options: Option<Vec<String>>, // For select
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>, // Used when a run doesn't bind the input
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -15,3 +18,260 @@ pub struct ProductTemplate {
     pub manifest_template: String, // YAML with {{variable}} placeholders
     pub inputs: Vec<InputSchema>,
 }
+
+impl InputSchema {
+    /// Schema for a placeholder the manifest doesn't declare: required free text
+    pub fn text(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            label: name.to_string(),
+            input_type: "text".to_string(),
+            options: None,
+            default: None,
+        }
+    }
+
+    /// Validates a bound value (or the default) and converts it to the declared type
+    pub fn bind(&self, raw: Option<&Value>) -> Result<Value, String> {
+        let raw = raw.filter(|v| !v.is_null()).or(self.default.as_ref())
+            .ok_or_else(|| format!("missing value for input '{}'", self.name))?;
+        let value = match self.input_type.as_str() {
+            "number" => {
+                let number = match raw {
+                    Value::Number(n) => Some(n.clone()),
+                    Value::String(s) => s.trim().parse::<i64>().map(Into::into).ok()
+                        .or_else(|| s.trim().parse::<f64>().ok().and_then(serde_json::Number::from_f64)),
+                    _ => None,
+                };
+                Value::Number(number.ok_or_else(|| format!("input '{}' expects a number, got {}", self.name, raw))?)
+            },
+            "boolean" => match raw {
+                Value::Bool(b) => Value::Bool(*b),
+                Value::String(s) if s.eq_ignore_ascii_case("true") => Value::Bool(true),
+                Value::String(s) if s.eq_ignore_ascii_case("false") => Value::Bool(false),
+                _ => return Err(format!("input '{}' expects true or false, got {}", self.name, raw)),
+            },
+            "text" | "select" => match raw {
+                Value::String(s) => Value::String(s.clone()),
+                Value::Number(_) | Value::Bool(_) => Value::String(raw.to_string()),
+                _ => return Err(format!("input '{}' expects text, got {}", self.name, raw)),
+            },
+            other => return Err(format!("input '{}' has unknown input_type '{}'", self.name, other)),
+        };
+        if let Some(options) = &self.options {
+            let text = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+            if !options.contains(&text) {
+                return Err(format!("input '{}' must be one of {:?}, got '{}'", self.name, options, text));
+            }
+        }
+        Ok(value)
+    }
+}
+
+/// Names of the `{{variable}}` placeholders in a template string, in order of appearance
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start + 2..].find("}}") else { break };
+        let name = rest[start + 2..start + 2 + end].trim();
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_') && !names.iter().any(|n| n == name) {
+            names.push(name.to_string());
+        }
+        rest = &rest[start + 2 + end + 2..];
+    }
+    names
+}
+
+/// The placeholder name when `text` is exactly one `{{name}}`
+fn whole_placeholder(text: &str) -> Option<&str> {
+    let name = text.trim().strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    (!name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_')).then_some(name)
+}
+
+fn bound<'a>(name: &str, params: &'a Map<String, Value>) -> Result<&'a Value, String> {
+    params.get(name).ok_or_else(|| format!("input '{}' is not bound", name))
+}
+
+/// A bound value as text (strings unquoted)
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Replaces placeholders in a config: a string that is exactly `{{x}}` takes the typed value.
+/// Bound values are never spliced into text, so a placeholder inside a longer string is an error.
+pub fn bind_placeholders(value: Value, params: &Map<String, Value>) -> Result<Value, String> {
+    Ok(match value {
+        Value::String(s) => match whole_placeholder(&s) {
+            Some(name) => bound(name, params)?.clone(),
+            None => match placeholders(&s).first() {
+                Some(name) => return Err(format!("placeholder '{{{{{}}}}}' must be the whole value, not part of '{}'", name, s)),
+                None => Value::String(s),
+            },
+        },
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| bind_placeholders(v, params)).collect::<Result<_, _>>()?),
+        Value::Object(fields) => Value::Object(fields.into_iter()
+            .map(|(k, v)| bind_placeholders(v, params).map(|v| (k, v)))
+            .collect::<Result<_, _>>()?),
+        other => other,
+    })
+}
+
+/// Binds a string that is exactly `{{x}}` to the value's text (for headers and query values)
+pub fn bind_text(text: &str, params: &Map<String, Value>) -> Result<String, String> {
+    match bind_placeholders(Value::String(text.to_string()), params)? {
+        Value::String(s) => Ok(s),
+        other => Ok(as_text(&other)),
+    }
+}
+
+/// Binds `{{x}}` query values of a URL, URL-encoded. Placeholders anywhere else (scheme, host,
+/// path, keys, inside a longer value) are rejected.
+pub fn bind_url(endpoint: &str, params: &Map<String, Value>) -> Result<String, String> {
+    if placeholders(endpoint).is_empty() {
+        return Ok(endpoint.to_string());
+    }
+    let base = endpoint.split(['?', '#']).next().unwrap_or_default();
+    if let Some(name) = placeholders(base).first() {
+        return Err(format!("placeholder '{{{{{}}}}}' may only bind a query value of '{}'", name, endpoint));
+    }
+    let mut url = url::Url::parse(endpoint).map_err(|e| format!("invalid endpoint '{}': {}", endpoint, e))?;
+    let mut pairs = Vec::new();
+    for (key, value) in url.query_pairs() {
+        if let Some(name) = placeholders(&key).first() {
+            return Err(format!("placeholder '{{{{{}}}}}' may only bind a query value of '{}'", name, endpoint));
+        }
+        pairs.push((key.to_string(), bind_text(&value, params)?));
+    }
+    if url.fragment().is_some_and(|fragment| fragment.contains("{{")) {
+        return Err(format!("placeholders may only bind query values of '{}'", endpoint));
+    }
+    url.query_pairs_mut().clear().extend_pairs(pairs);
+    Ok(url.to_string())
+}
+
+/// Rewrites `{{x}}` placeholders in SQL to `?` parameters, returning the query and its
+/// parameters in order (the bare `?`s keep taking `sql_params`). Placeholders inside quoted
+/// literals, and numbered `?NNN` parameters alongside placeholders, are rejected.
+pub fn bind_sql(sql: &str, sql_params: Vec<Value>, params: &Map<String, Value>) -> Result<(String, Vec<Value>), String> {
+    let sql_params = sql_params.into_iter().map(|v| bind_placeholders(v, params)).collect::<Result<Vec<_>, _>>()?;
+    if placeholders(sql).is_empty() {
+        return Ok((sql.to_string(), sql_params));
+    }
+    let chars: Vec<char> = sql.chars().collect();
+    let mut positional = sql_params.into_iter();
+    let mut text = String::with_capacity(sql.len());
+    let mut values = Vec::new();
+    let mut quote = None;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), '{') if chars.get(i + 1) == Some(&'{') => {
+                return Err(format!("placeholder inside a quoted literal in '{}'; bind it as a whole value", sql));
+            },
+            (Some(_), _) => {},
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '?') if chars.get(i + 1).is_some_and(char::is_ascii_digit) => {
+                return Err(format!("numbered '?' parameters cannot be mixed with placeholders in '{}'", sql));
+            },
+            (None, '?') => values.push(positional.next()
+                .ok_or_else(|| format!("'{}' has more '?' parameters than sql_params", sql))?),
+            (None, '{') if chars.get(i + 1) == Some(&'{') => {
+                let end = (i + 2..chars.len().saturating_sub(1)).find(|j| chars[*j] == '}' && chars[j + 1] == '}')
+                    .ok_or_else(|| format!("unterminated '{{{{' placeholder in '{}'", sql))?;
+                let name: String = chars[i + 2..end].iter().collect();
+                values.push(bound(name.trim(), params)?.clone());
+                text.push('?');
+                i = end + 2;
+                continue;
+            },
+            _ => {},
+        }
+        text.push(c);
+        i += 1;
+    }
+    values.extend(positional);
+    Ok((text, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn params(value: Value) -> Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    fn schema(input_type: &str, options: Option<&[&str]>) -> InputSchema {
+        InputSchema {
+            input_type: input_type.to_string(),
+            options: options.map(|o| o.iter().map(|s| s.to_string()).collect()),
+            ..InputSchema::text("station")
+        }
+    }
+
+    #[test]
+    fn inputs_bind_as_their_declared_type() {
+        assert_eq!(schema("number", None).bind(Some(&json!(" 42 "))), Ok(json!(42)));
+        assert_eq!(schema("number", None).bind(Some(&json!("4.5"))), Ok(json!(4.5)));
+        assert!(schema("number", None).bind(Some(&json!("42; DROP TABLE runs"))).is_err());
+        assert_eq!(schema("boolean", None).bind(Some(&json!("TRUE"))), Ok(json!(true)));
+        assert!(schema("boolean", None).bind(Some(&json!("yes"))).is_err());
+        assert_eq!(schema("text", None).bind(Some(&json!(7))), Ok(json!("7")));
+        assert!(schema("text", None).bind(Some(&json!({"nested": true}))).is_err());
+        assert!(schema("text", None).bind(None).is_err());
+
+        let select = schema("select", Some(&["air", "water"]));
+        assert_eq!(select.bind(Some(&json!("air"))), Ok(json!("air")));
+        assert!(select.bind(Some(&json!("air' OR '1'='1"))).is_err());
+        let defaulted = InputSchema { default: Some(json!("water")), ..select };
+        assert_eq!(defaulted.bind(Some(&Value::Null)), Ok(json!("water")));
+    }
+
+    #[test]
+    fn placeholders_bind_whole_values_only() {
+        let params = params(json!({"limit": 5, "name": "a\"b"}));
+        assert_eq!(bind_placeholders(json!({"limit": "{{ limit }}", "keep": "x"}), &params), Ok(json!({"limit": 5, "keep": "x"})));
+        assert!(bind_placeholders(json!({"expr": "name == {{name}}"}), &params).is_err());
+        assert!(bind_placeholders(json!("{{missing}}"), &params).is_err());
+        assert_eq!(placeholders("{{a}} {{ b }} {{a}} {{not valid}}"), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn urls_bind_only_encoded_query_values() {
+        let params = params(json!({"station": "KL&debug=1 #x", "host": "evil.example"}));
+        assert_eq!(
+            bind_url("https://api.example.com/aq?station={{station}}&unit=ug", &params),
+            Ok("https://api.example.com/aq?station=KL%26debug%3D1+%23x&unit=ug".to_string())
+        );
+        assert!(bind_url("https://{{host}}/aq", &params).is_err());
+        assert!(bind_url("https://api.example.com/{{station}}", &params).is_err());
+        assert!(bind_url("https://api.example.com/aq?{{station}}=1", &params).is_err());
+        assert!(bind_url("https://api.example.com/aq?q=id-{{station}}", &params).is_err());
+        assert_eq!(bind_url("https://api.example.com/aq?a=1", &params), Ok("https://api.example.com/aq?a=1".to_string()));
+    }
+
+    #[test]
+    fn sql_placeholders_become_parameters() {
+        let params = params(json!({"station": "x' OR '1'='1", "since": 2024}));
+        let (sql, values) = bind_sql(
+            "SELECT * FROM readings WHERE station = {{station}} AND year >= {{ since }} AND pm25 > ?",
+            vec![json!(50)],
+            &params,
+        ).unwrap();
+        assert_eq!(sql, "SELECT * FROM readings WHERE station = ? AND year >= ? AND pm25 > ?");
+        assert_eq!(values, vec![json!("x' OR '1'='1"), json!(2024), json!(50)]);
+
+        assert!(bind_sql("SELECT * FROM t WHERE name = '{{station}}'", vec![], &params).is_err());
+        assert!(bind_sql("SELECT * FROM t WHERE a = ?1 AND b = {{since}}", vec![json!(1)], &params).is_err());
+        assert!(bind_sql("SELECT * FROM t WHERE a = ? AND b = {{since}}", vec![], &params).is_err());
+        // Without placeholders, sql_params still bind
+        assert_eq!(bind_sql("SELECT ?", vec![json!("{{since}}")], &params), Ok(("SELECT ?".to_string(), vec![json!(2024)])));
+    }
+}
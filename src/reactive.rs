@@ -0,0 +1,311 @@
+use crate::kernel::{AetherKernel, ExecutionBudget, ExecutionOptions, KernelError};
+use crate::AetherVault;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// Updates kept for slow subscribers before they start missing some
+const UPDATE_BUFFER: usize = 64;
+
+/// REACTIVE_TRIGGER (Op 50) config blob
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TriggerConfig {
+    pub event: String, // The intent as written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on: Option<TriggerEvent>, // None: an event only the UI understands (e.g. a click)
+}
+
+/// What makes a trigger fire
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TriggerEvent {
+    /// A new value for one of the project's inputs (PARAM atoms)
+    InputChanged { input: String },
+    Every { seconds: u64 },
+}
+
+/// Parses "... when input <name> changes", "... when the <name> value changes" or
+/// "... every 5 minutes" / "... every hour"
+pub fn parse_event(text: &str) -> Option<TriggerEvent> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let lower: Vec<String> = words.iter().map(|w| w.to_lowercase()).collect();
+
+    if let Some(at) = lower.iter().position(|w| w == "every") {
+        let (count, unit) = match lower.get(at + 1)?.parse::<u64>() {
+            Ok(n) => (n, lower.get(at + 2)?),
+            Err(_) => (1, lower.get(at + 1)?),
+        };
+        let seconds = match unit.trim_end_matches(['.', ',']).trim_end_matches('s') {
+            "second" | "sec" => 1,
+            "minute" | "min" => 60,
+            "hour" | "hr" => 3600,
+            "day" => 86_400,
+            _ => return None,
+        };
+        return (count > 0).then(|| TriggerEvent::Every { seconds: count * seconds });
+    }
+
+    let when = lower.iter().position(|w| w == "when")?;
+    let end = lower.iter().skip(when + 1)
+        .position(|w| matches!(w.trim_end_matches(['.', ',']), "changes" | "change" | "changed" | "updates" | "updated"))
+        .map(|i| i + when + 1)?;
+    let name: Vec<&str> = words[when + 1..end].iter()
+        .zip(&lower[when + 1..end])
+        .filter(|(_, l)| !matches!(l.as_str(), "the" | "input" | "value" | "field" | "is" | "gets"))
+        .map(|(w, _)| w.trim_start_matches("{{").trim_end_matches("}}"))
+        .collect();
+    (!name.is_empty()).then(|| TriggerEvent::InputChanged { input: name.join("_") })
+}
+
+/// A trigger of a deployed project, and the nodes it refreshes when it fires
+#[derive(Serialize, Debug, Clone)]
+pub struct Registration {
+    pub trigger_hash: String,
+    pub event: TriggerEvent,
+    /// Re-run when the event fires, along with everything downstream of them
+    pub targets: Vec<String>,
+}
+
+/// Pushed to subscribers after a trigger re-executes its project
+#[derive(Serialize, Debug, Clone)]
+pub struct ReactiveUpdate {
+    pub project: String,
+    pub root_hash: String,
+    pub trigger_hash: String,
+    pub event: TriggerEvent,
+    pub recomputed: Vec<String>, // Nodes re-run; every other output came from the previous run
+    pub output: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub at_us: u64,
+}
+
+struct ProjectState {
+    root_hash: String,
+    triggers: Vec<Registration>,
+    consumers: HashMap<String, Vec<String>>, // Reverse edges of the root's graph
+    params: HashMap<String, Vec<String>>,    // Input name -> nodes binding it
+    budget: ExecutionBudget,
+    bindings: HashMap<String, Value>,
+    memo: HashMap<String, Value>, // Last output of every node
+    run_lock: Arc<tokio::sync::Mutex<()>>,
+    timers: Vec<tokio::task::JoinHandle<()>>,
+}
+
+/// Keeps deployed projects' triggers live: interval triggers tick on their own, input
+/// triggers fire from `set_inputs`. Each firing re-runs only the dirty part of the graph.
+pub struct ReactiveHub {
+    vault: AetherVault,
+    projects: Mutex<HashMap<String, ProjectState>>,
+    updates: broadcast::Sender<ReactiveUpdate>,
+}
+
+impl ReactiveHub {
+    pub fn new(vault: AetherVault) -> Arc<Self> {
+        let (updates, _) = broadcast::channel(UPDATE_BUFFER);
+        Arc::new(Self { vault, projects: Mutex::new(HashMap::new()), updates })
+    }
+
+    /// Updates from every project; filter on `ReactiveUpdate.project`
+    pub fn subscribe(&self) -> broadcast::Receiver<ReactiveUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// (Re)registers the triggers in a deployed root's graph, replacing the project's previous ones.
+    /// Firings run within `budget` (the manifest's `budget:`).
+    pub fn register(self: &Arc<Self>, project: &str, root_hash: &str, budget: ExecutionBudget) -> Result<Vec<Registration>, KernelError> {
+        let mut consumers: HashMap<String, Vec<String>> = HashMap::new();
+        let mut params: HashMap<String, Vec<String>> = HashMap::new(); // Input name -> nodes binding it
+        let mut triggers = Vec::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![root_hash.to_string()];
+        while let Some(hash) = pending.pop() {
+            if !visited.insert(hash.clone()) {
+                continue;
+            }
+            let atom = self.vault.fetch(&hash)?;
+            let mut data_inputs = Vec::new();
+            for input in &atom.inputs {
+                consumers.entry(input.clone()).or_default().push(hash.clone());
+                match self.vault.fetch(input) {
+                    Ok(param) if param.op_code == crate::OP_PARAM => {
+                        let schema: crate::InputSchema = serde_json::from_slice(&crate::read_blob(&param.storage_ref).unwrap_or_default())
+                            .map_err(|e| KernelError::Runtime(format!("PARAM Config Error: {}", e)))?;
+                        params.entry(schema.name).or_default().push(hash.clone());
+                    },
+                    _ => data_inputs.push(input.clone()),
+                }
+                pending.push(input.clone());
+            }
+            if atom.op_code == 50 {
+                let config: TriggerConfig = serde_json::from_slice(&crate::read_blob(&atom.storage_ref).unwrap_or_default())
+                    .map_err(|e| KernelError::Runtime(format!("Trigger Config Error: {}", e)))?;
+                if let Some(event) = config.on {
+                    triggers.push((hash.clone(), event, data_inputs));
+                }
+            }
+        }
+
+        let triggers: Vec<Registration> = triggers.into_iter().map(|(trigger_hash, event, data_inputs)| {
+            let targets = match &event {
+                // Whatever binds the input, wherever it is in the graph
+                TriggerEvent::InputChanged { input } => params.get(input).cloned().unwrap_or_default(),
+                // The trigger's own inputs (e.g. the IO it refreshes), or the whole graph
+                TriggerEvent::Every { .. } if data_inputs.is_empty() => visited.iter().cloned().collect(),
+                TriggerEvent::Every { .. } => data_inputs,
+            };
+            Registration { trigger_hash, event, targets }
+        }).collect();
+
+        let timers = triggers.iter().filter_map(|trigger| match trigger.event {
+            TriggerEvent::Every { seconds } => {
+                let hub = Arc::clone(self);
+                let (project, trigger) = (project.to_string(), trigger.clone());
+                Some(tokio::spawn(async move {
+                    let mut ticks = tokio::time::interval(Duration::from_secs(seconds));
+                    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                    ticks.tick().await; // The first tick is immediate
+                    loop {
+                        ticks.tick().await;
+                        hub.fire(&project, &trigger).await;
+                    }
+                }))
+            },
+            TriggerEvent::InputChanged { .. } => None,
+        }).collect();
+
+        let state = ProjectState {
+            root_hash: root_hash.to_string(),
+            triggers: triggers.clone(),
+            consumers,
+            params,
+            budget,
+            bindings: HashMap::new(),
+            memo: HashMap::new(),
+            run_lock: Arc::new(tokio::sync::Mutex::new(())),
+            timers,
+        };
+        if let Some(old) = self.projects.lock().unwrap().insert(project.to_string(), state) {
+            old.timers.iter().for_each(|timer| timer.abort());
+        }
+        Ok(triggers)
+    }
+
+    /// Registered triggers of a project
+    pub fn triggers(&self, project: &str) -> Vec<Registration> {
+        self.projects.lock().unwrap().get(project).map(|state| state.triggers.clone()).unwrap_or_default()
+    }
+
+    /// Sets input values for a project and fires the triggers watching any that changed.
+    /// Returns the fired triggers.
+    pub async fn set_inputs(self: &Arc<Self>, project: &str, inputs: HashMap<String, Value>) -> Result<Vec<Registration>, String> {
+        let fired: Vec<Registration> = {
+            let mut projects = self.projects.lock().unwrap();
+            let state = projects.get_mut(project).ok_or_else(|| format!("Project '{}' has no registered triggers", project))?;
+            let changed: HashSet<String> = inputs.iter()
+                .filter(|(name, value)| state.bindings.get(*name) != Some(value))
+                .map(|(name, _)| name.clone())
+                .collect();
+            state.bindings.extend(inputs);
+            // Whatever binds a changed input is stale, whether or not a trigger watches it
+            let starts = changed.iter().flat_map(|name| state.params.get(name)).flatten().cloned();
+            let stale = downstream(&state.consumers, starts);
+            state.memo.retain(|hash, _| !stale.contains(hash));
+            state.triggers.iter()
+                .filter(|t| matches!(&t.event, TriggerEvent::InputChanged { input } if changed.contains(input)))
+                .cloned()
+                .collect()
+        };
+        for trigger in &fired {
+            self.fire(project, trigger).await;
+        }
+        Ok(fired)
+    }
+
+    /// Re-runs the trigger's targets and everything downstream of them, reusing the last
+    /// outputs of every other node, then pushes the result to subscribers
+    pub async fn fire(&self, project: &str, trigger: &Registration) {
+        let Some(run_lock) = self.projects.lock().unwrap().get(project).map(|state| Arc::clone(&state.run_lock)) else {
+            return;
+        };
+        // One run per project at a time; a queued firing sees the bindings current when it starts
+        let _running = run_lock.lock().await;
+        let (root_hash, bindings, budget, seed, dirty) = {
+            let projects = self.projects.lock().unwrap();
+            let Some(state) = projects.get(project) else {
+                return;
+            };
+            let dirty = downstream(&state.consumers, trigger.targets.iter().chain([&trigger.trigger_hash]).cloned());
+            let seed = state.memo.iter()
+                .filter(|(hash, _)| !dirty.contains(*hash))
+                .map(|(hash, value)| (hash.clone(), value.clone()))
+                .collect();
+            (state.root_hash.clone(), state.bindings.clone(), state.budget.clone(), seed, dirty)
+        };
+
+        let kernel = AetherKernel::with_budget(self.vault.clone(), budget);
+        let options = ExecutionOptions { bindings: bindings.into_iter().collect(), ..Default::default() };
+        let (report, memo) = kernel.execute_seeded(&root_hash, &options, seed).await;
+
+        if let Some(state) = self.projects.lock().unwrap().get_mut(project)
+            && state.root_hash == root_hash {
+            state.memo = memo;
+        }
+        let (output, error) = match report.output {
+            Ok(output) => (output, None),
+            Err(e) => (serde_json::json!({"error": e.to_string()}), Some(e.to_string())),
+        };
+        // No subscribers is fine
+        let _ = self.updates.send(ReactiveUpdate {
+            project: project.to_string(),
+            root_hash,
+            trigger_hash: trigger.trigger_hash.clone(),
+            event: trigger.event.clone(),
+            recomputed: dirty.into_iter().collect(),
+            output,
+            error,
+            at_us: crate::runs::unix_micros(),
+        });
+    }
+}
+
+/// `starts` and every node downstream of them
+fn downstream(consumers: &HashMap<String, Vec<String>>, starts: impl IntoIterator<Item = String>) -> HashSet<String> {
+    let mut reached = HashSet::new();
+    let mut pending: Vec<String> = starts.into_iter().collect();
+    while let Some(hash) = pending.pop() {
+        if reached.insert(hash.clone()) {
+            pending.extend(consumers.get(&hash).into_iter().flatten().cloned());
+        }
+    }
+    reached
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_event("Refresh prices every 5 minutes"), Some(TriggerEvent::Every { seconds: 300 }));
+        assert_eq!(parse_event("Refresh every hour."), Some(TriggerEvent::Every { seconds: 3600 }));
+        assert_eq!(parse_event("Refresh every 2 days"), Some(TriggerEvent::Every { seconds: 172_800 }));
+        assert_eq!(parse_event("Refresh every 0 minutes"), None);
+        assert_eq!(parse_event("Refresh every fortnight"), None);
+    }
+
+    #[test]
+    fn parses_input_changes() {
+        assert_eq!(parse_event("Recompute when input station_type changes"),
+            Some(TriggerEvent::InputChanged { input: "station_type".into() }));
+        assert_eq!(parse_event("Recompute when the {{budget}} value is updated"),
+            Some(TriggerEvent::InputChanged { input: "budget".into() }));
+        assert_eq!(parse_event("Recompute when max price changes"),
+            Some(TriggerEvent::InputChanged { input: "max_price".into() }));
+        assert_eq!(parse_event("On button click"), None);
+        assert_eq!(parse_event("Recompute when the input changes"), None);
+    }
+}
@@ -0,0 +1,234 @@
+use crate::kernel::{ExecutionOptions, ExecutionReport};
+use crate::{AetherVault, ProjectAtom, RunRecord, VaultError};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The project named `name`, if it exists and `root_hash` is its deployed root. A run history is
+/// an audit trail, so nothing else may be recorded under (or spend the budget of) a project.
+pub fn deployed_project(vault: &AetherVault, name: &str, root_hash: &str) -> Result<ProjectAtom, VaultError> {
+    let project = vault.get_project(name).map_err(|e| match e {
+        VaultError::NotFound => VaultError::Validation(format!("Project '{}' does not exist", name)),
+        e => e,
+    })?;
+    if project.root_hash != root_hash {
+        return Err(VaultError::Validation(format!("{} is not the deployed root of project '{}'", root_hash, name)));
+    }
+    Ok(project)
+}
+
+/// Stores an execution as the project's next output version. Outputs live in content-addressed
+/// blobs, so runs with identical results share one blob (and `output_hash`).
+/// Only runs of the project's deployed root are recorded (see `deployed_project`).
+pub fn record_execution(
+    vault: &AetherVault,
+    project: &str,
+    root_hash: &str,
+    options: &ExecutionOptions,
+    report: &ExecutionReport,
+    started_at_us: u64,
+    schedule_id: Option<String>,
+) -> Result<RunRecord, VaultError> {
+    deployed_project(vault, project, root_hash)?;
+    let output = match &report.output {
+        Ok(output) => crate::write_blob(&serde_json::to_vec(output).unwrap())
+            .map_err(|e| format!("Blob Write Error: {}", e)),
+        Err(e) => Err(e.to_string()),
+    };
+    let mut record = RunRecord {
+        project: project.to_string(),
+        version: 0,
+        root_hash: root_hash.to_string(),
+        schedule_id,
+        bindings: options.bindings.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        started_at_us,
+        finished_at_us: unix_micros(),
+        output_hash: output.as_ref().ok().map(|uri| uri.trim_start_matches("local://").to_string()),
+        output_ref: output.as_ref().ok().cloned(),
+        error: output.err(),
+        warnings: report.warnings.clone(),
+        trace: report.trace.clone(),
+    };
+    vault.record_run(&mut record)?;
+    Ok(record)
+}
+
+/// The stored output of a successful run
+pub fn load_output(record: &RunRecord) -> Result<Value, String> {
+    let uri = record.output_ref.as_deref()
+        .ok_or_else(|| format!("{} v{} has no output: {}", record.project, record.version, record.error.as_deref().unwrap_or("not stored")))?;
+    let data = crate::read_blob(uri).map_err(|e| format!("Blob Fetch Error: {}", e))?;
+    serde_json::from_slice(&data).map_err(|e| format!("Stored output is not JSON: {}", e))
+}
+
+/// Row-by-row difference between two outputs. Rows are matched on `key` when given (e.g. an id
+/// column), otherwise by position. A non-list output counts as a single row.
+/// Rows sharing a key value, or missing the key, are matched by position within their group;
+/// such keys are listed in `duplicate_keys` and the keyless rows counted in `rows_without_key`.
+pub fn diff_outputs(before: &Value, after: &Value, key: Option<&str>) -> Value {
+    let rows = |value: &Value| match value {
+        Value::Array(items) => items.clone(),
+        other => vec![other.clone()],
+    };
+    let (before, after) = (rows(before), rows(after));
+    let mut diff = RowDiff::default();
+    let mut extra = Map::new();
+
+    match key {
+        Some(key) => {
+            let key_of = |row: &Value| crate::predicate::lookup(row, key).filter(|v| !v.is_null()).map(Value::to_string);
+            let mut groups: BTreeMap<Option<String>, (Vec<&Value>, Vec<&Value>)> = BTreeMap::new();
+            for row in &before {
+                groups.entry(key_of(row)).or_default().0.push(row);
+            }
+            for row in &after {
+                groups.entry(key_of(row)).or_default().1.push(row);
+            }
+            let mut duplicates = Vec::new();
+            for (k, (old, new)) in &groups {
+                match k {
+                    Some(_) if old.len() > 1 || new.len() > 1 => {
+                        duplicates.extend(old.iter().chain(new).next().and_then(|row| crate::predicate::lookup(row, key)).cloned());
+                    },
+                    None => {
+                        extra.insert("rows_without_key".to_string(), json!({"before": old.len(), "after": new.len()}));
+                    },
+                    _ => {},
+                }
+                for (row, next) in old.iter().zip(new) {
+                    diff.compare(row, next, "key", crate::predicate::lookup(row, key).cloned().unwrap_or(Value::Null));
+                }
+                diff.removed.extend(old.iter().skip(new.len()).map(|row| (*row).clone()));
+                diff.added.extend(new.iter().skip(old.len()).map(|row| (*row).clone()));
+            }
+            if !duplicates.is_empty() {
+                extra.insert("duplicate_keys".to_string(), Value::Array(duplicates));
+            }
+        },
+        None => {
+            for (index, (row, next)) in before.iter().zip(&after).enumerate() {
+                diff.compare(row, next, "index", json!(index));
+            }
+            diff.removed.extend(before.iter().skip(after.len()).cloned());
+            diff.added.extend(after.iter().skip(before.len()).cloned());
+        },
+    }
+
+    let RowDiff { added, removed, changed, unchanged } = diff;
+    let mut out = json!({
+        "identical": added.is_empty() && removed.is_empty() && changed.is_empty(),
+        "summary": {"added": added.len(), "removed": removed.len(), "changed": changed.len(), "unchanged": unchanged},
+        "added": added,
+        "removed": removed,
+        "changed": changed,
+    });
+    if let Value::Object(fields) = &mut out {
+        fields.extend(extra);
+    }
+    out
+}
+
+#[derive(Default)]
+struct RowDiff {
+    added: Vec<Value>,
+    removed: Vec<Value>,
+    changed: Vec<Value>,
+    unchanged: usize,
+}
+
+impl RowDiff {
+    /// Records a matched pair of rows, identified by `at` (e.g. its key or index)
+    fn compare(&mut self, row: &Value, next: &Value, label: &str, at: Value) {
+        if row == next {
+            self.unchanged += 1;
+            return;
+        }
+        self.changed.push(json!({
+            label: at,
+            "fields": changed_fields(row, next),
+            "before": row,
+            "after": next,
+        }));
+    }
+}
+
+/// Top-level fields whose values differ between two records
+fn changed_fields(before: &Value, after: &Value) -> Vec<String> {
+    let empty = Map::new();
+    let (old, new) = match (before, after) {
+        (Value::Object(old), Value::Object(new)) => (old, new),
+        _ => (&empty, &empty),
+    };
+    let names: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    names.into_iter().filter(|name| old.get(*name) != new.get(*name)).cloned().collect()
+}
+
+/// Now, as Unix epoch microseconds
+pub fn unix_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::KernelError;
+
+    #[test]
+    fn runs_are_recorded_only_for_a_project_deployed_root() {
+        let vault = AetherVault::temporary();
+        vault.persist_project(&ProjectAtom {
+            name: "guardian".to_string(),
+            root_hash: "root".to_string(),
+            org_hash: "org".to_string(),
+            status: crate::ProjectStatus::Active,
+            created_at: 0,
+            budget: None,
+            laws: None,
+        }).unwrap();
+        let report = ExecutionReport { output: Err(KernelError::Timeout(1)), trace: None, warnings: vec![], errors: vec![] };
+        let record = |project: &str, root: &str| record_execution(&vault, project, root, &ExecutionOptions::default(), &report, 0, None);
+
+        assert_eq!(record("guardian", "root").unwrap().version, 1);
+        assert!(record("guardian", "forged").is_err());
+        assert!(record("unknown", "root").is_err());
+        assert_eq!(vault.list_runs("guardian", 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn positional_diff() {
+        let diff = diff_outputs(&json!([{"a": 1}, {"a": 2}]), &json!([{"a": 1}, {"a": 3}, {"a": 4}]), None);
+        assert_eq!(diff["summary"], json!({"added": 1, "removed": 0, "changed": 1, "unchanged": 1}));
+        assert_eq!(diff["changed"][0]["index"], json!(1));
+        assert_eq!(diff["changed"][0]["fields"], json!(["a"]));
+    }
+
+    #[test]
+    fn keyed_diff_matches_rows_by_key() {
+        let before = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}]);
+        let after = json!([{"id": 2, "v": "c"}, {"id": 3, "v": "d"}]);
+        let diff = diff_outputs(&before, &after, Some("id"));
+        assert_eq!(diff["summary"], json!({"added": 1, "removed": 1, "changed": 1, "unchanged": 0}));
+        assert_eq!(diff["changed"][0]["key"], json!(2));
+        assert_eq!(diff["removed"], json!([{"id": 1, "v": "a"}]));
+        assert!(diff.get("duplicate_keys").is_none());
+    }
+
+    #[test]
+    fn keyed_diff_keeps_duplicate_and_keyless_rows() {
+        let before = json!([{"id": 1, "v": 1}, {"id": 1, "v": 2}, {"v": 9}]);
+        let after = json!([{"id": 1, "v": 1}, {"id": 1, "v": 5}, {"id": 1, "v": 7}, {"v": 9}, {"v": 10}]);
+        let diff = diff_outputs(&before, &after, Some("id"));
+        assert_eq!(diff["summary"], json!({"added": 2, "removed": 0, "changed": 1, "unchanged": 2}));
+        assert_eq!(diff["duplicate_keys"], json!([1]));
+        assert_eq!(diff["rows_without_key"], json!({"before": 1, "after": 2}));
+    }
+
+    #[test]
+    fn scalars_are_single_rows() {
+        assert_eq!(diff_outputs(&json!(5), &json!(5), None)["identical"], json!(true));
+        assert_eq!(diff_outputs(&json!(5), &json!(6), None)["summary"]["changed"], json!(1));
+    }
+}
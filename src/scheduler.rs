@@ -0,0 +1,393 @@
+use crate::kernel::{AetherKernel, ExecutionOptions, ExecutionReport, KernelError};
+use crate::runs::unix_micros;
+use crate::{AetherVault, RunRecord, VaultError};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+
+/// How often the scheduler looks for due schedules
+pub const TICK: Duration = Duration::from_secs(1);
+
+/// Furthest a cron spec is searched for its next match (specs like `0 0 30 2 *` never match)
+const CRON_HORIZON_DAYS: i64 = 5 * 366;
+
+#[derive(Error, Debug)]
+pub enum SchedulerError {
+    #[error("Vault error: {0}")]
+    Vault(#[from] VaultError),
+    #[error("Invalid schedule '{spec}': {reason}")]
+    Spec { spec: String, reason: String },
+    #[error("Unknown project '{0}'")]
+    UnknownProject(String),
+    #[error("Unknown schedule '{0}'")]
+    NotFound(String),
+}
+
+/// A recurring execution of a project's current root, stored under `SCHED:{id}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub id: String,
+    pub project: String,
+    pub spec: String, // 5-field cron in UTC ("*/15 * * * *"), "@hourly", "@daily", "@weekly", "@monthly" or "@every 15m"
+    #[serde(default)]
+    pub inputs: HashMap<String, Value>, // Bindings for the project's PARAM atoms
+    pub enabled: bool,
+    pub next_run_us: u64, // Unix epoch, microseconds
+    #[serde(default)]
+    pub last_run_us: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>, // Why the last tick stored no run; cleared by the next stored run
+}
+
+/// Changes to a schedule; missing fields are left as they are
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SchedulePatch {
+    pub spec: Option<String>,
+    pub inputs: Option<HashMap<String, Value>>,
+    pub enabled: Option<bool>,
+}
+
+/// When a schedule fires
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cadence {
+    /// A fixed interval
+    Every(Duration),
+    Cron(CronSpec),
+}
+
+/// Parsed 5-field cron: minute, hour, day of month, month, day of week (0 or 7 = Sunday)
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSpec {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl Cadence {
+    pub fn parse(spec: &str) -> Result<Cadence, SchedulerError> {
+        let invalid = |reason: &str| SchedulerError::Spec { spec: spec.to_string(), reason: reason.to_string() };
+        let spec_text = spec.trim();
+        let cron = match spec_text {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            _ => spec_text,
+        };
+        if let Some(interval) = cron.strip_prefix("@every") {
+            let interval = interval.trim();
+            let split = interval.find(|c: char| !c.is_ascii_digit()).unwrap_or(interval.len());
+            let count: u64 = interval[..split].parse().map_err(|_| invalid("@every needs a count, e.g. @every 15m"))?;
+            let unit = match &interval[split..] {
+                "s" => 1,
+                "m" => 60,
+                "h" => 3600,
+                "d" => 86_400,
+                _ => return Err(invalid("@every units are s, m, h or d")),
+            };
+            let every = Duration::from_secs(count.saturating_mul(unit));
+            if every < TICK {
+                return Err(invalid("@every must be at least 1s"));
+            }
+            return Ok(Cadence::Every(every));
+        }
+
+        let fields: Vec<&str> = cron.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid("expected 5 fields: minute hour day-of-month month day-of-week"));
+        }
+        let weekdays = parse_field(fields[4], 0, 7).map_err(|e| invalid(&format!("day of week: {}", e)))?;
+        let cadence = Cadence::Cron(CronSpec {
+            minutes: parse_field(fields[0], 0, 59).map_err(|e| invalid(&format!("minute: {}", e)))?,
+            hours: parse_field(fields[1], 0, 23).map_err(|e| invalid(&format!("hour: {}", e)))?,
+            days: parse_field(fields[2], 1, 31).map_err(|e| invalid(&format!("day of month: {}", e)))?,
+            months: parse_field(fields[3], 1, 12).map_err(|e| invalid(&format!("month: {}", e)))?,
+            // 7 is another name for Sunday
+            weekdays: (weekdays | (weekdays >> 7)) & 0x7f,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        });
+        if cadence.next_after(unix_micros()).is_none() {
+            return Err(invalid("never matches a real date"));
+        }
+        Ok(cadence)
+    }
+
+    /// The first firing strictly after `after_us`, or `None` if there is none in the next few years
+    pub fn next_after(&self, after_us: u64) -> Option<u64> {
+        match self {
+            Cadence::Every(every) => Some(after_us.saturating_add(every.as_micros() as u64)),
+            Cadence::Cron(cron) => cron.next_after(after_us),
+        }
+    }
+}
+
+impl CronSpec {
+    fn next_after(&self, after_us: u64) -> Option<u64> {
+        let bit = |mask: u64, n: i64| mask & (1 << n) != 0;
+        // Cron has minute resolution: start at the next whole minute
+        let mut minute = (after_us / 60_000_000) as i64 + 1;
+        let limit = minute + CRON_HORIZON_DAYS * 1440;
+        while minute < limit {
+            let day = minute.div_euclid(1440);
+            let (_, month, day_of_month) = civil_from_days(day);
+            let weekday = (day + 4).rem_euclid(7); // 1970-01-01 was a Thursday
+            // As in Vixie cron: when both day fields are restricted, either may match
+            let day_matches = match (self.any_day, self.any_weekday) {
+                (true, true) => true,
+                (false, true) => bit(self.days, day_of_month),
+                (true, false) => bit(self.weekdays, weekday),
+                (false, false) => bit(self.days, day_of_month) || bit(self.weekdays, weekday),
+            };
+            if !bit(self.months, month) || !day_matches {
+                minute = (day + 1) * 1440;
+                continue;
+            }
+            let hour = minute.rem_euclid(1440) / 60;
+            if !bit(self.hours, hour) {
+                minute = day * 1440 + (hour + 1) * 60;
+                continue;
+            }
+            if !bit(self.minutes, minute.rem_euclid(60)) {
+                minute += 1;
+                continue;
+            }
+            return Some(minute as u64 * 60_000_000);
+        }
+        None
+    }
+}
+
+/// One cron field as a bitmask: `*`, `*/n`, `a`, `a-b`, `a-b/n`, and comma lists of these
+fn parse_field(field: &str, min: i64, max: i64) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<i64>().map_err(|_| format!("bad step in '{}'", part))?),
+            None => (part, 1),
+        };
+        if step < 1 {
+            return Err(format!("step must be at least 1 in '{}'", part));
+        }
+        let number = |s: &str| s.parse::<i64>().ok().filter(|n| (min..=max).contains(n))
+            .ok_or_else(|| format!("'{}' is not in {}-{}", s, min, max));
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (number(a)?, number(b)?),
+                None if part.contains('/') => (number(range)?, max),
+                None => (number(range)?, number(range)?),
+            },
+        };
+        if start > end {
+            return Err(format!("'{}' runs backwards", range));
+        }
+        for n in (start..=end).step_by(step as usize) {
+            mask |= 1 << n;
+        }
+    }
+    Ok(mask)
+}
+
+/// (year, month 1-12, day 1-31) for days since 1970-01-01 (proleptic Gregorian)
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Validates and stores a new schedule for an existing project
+pub fn create_schedule(
+    vault: &AetherVault,
+    project: &str,
+    spec: &str,
+    inputs: HashMap<String, Value>,
+    enabled: bool,
+) -> Result<Schedule, SchedulerError> {
+    let cadence = Cadence::parse(spec)?;
+    vault.get_project(project).map_err(|_| SchedulerError::UnknownProject(project.to_string()))?;
+    let schedule = Schedule {
+        id: format!("sched-{}", vault.generate_id()?),
+        project: project.to_string(),
+        spec: spec.trim().to_string(),
+        inputs,
+        enabled,
+        next_run_us: cadence.next_after(unix_micros()).unwrap_or(u64::MAX),
+        last_run_us: None,
+        last_error: None,
+    };
+    vault.persist_schedule(&schedule)?;
+    Ok(schedule)
+}
+
+/// Applies a patch; a new spec restarts the schedule's clock from now
+pub fn update_schedule(vault: &AetherVault, id: &str, patch: SchedulePatch) -> Result<Schedule, SchedulerError> {
+    let mut schedule = vault.get_schedule(id).map_err(|_| SchedulerError::NotFound(id.to_string()))?;
+    if let Some(spec) = patch.spec {
+        let cadence = Cadence::parse(&spec)?;
+        schedule.spec = spec.trim().to_string();
+        schedule.next_run_us = cadence.next_after(unix_micros()).unwrap_or(u64::MAX);
+    }
+    if let Some(inputs) = patch.inputs {
+        schedule.inputs = inputs;
+    }
+    if let Some(enabled) = patch.enabled {
+        schedule.enabled = enabled;
+    }
+    vault.persist_schedule(&schedule)?;
+    Ok(schedule)
+}
+
+/// Runs due schedules in the background. A schedule whose previous run is still going
+/// skips its tick instead of starting a second, overlapping run.
+pub struct Scheduler {
+    vault: AetherVault,
+    running: Arc<Mutex<HashSet<String>>>,
+}
+
+impl Scheduler {
+    pub fn new(vault: AetherVault) -> Self {
+        Self { vault, running: Arc::new(Mutex::new(HashSet::new())) }
+    }
+
+    /// Starts the tick loop on the current Tokio runtime
+    pub fn spawn(self) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(TICK);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+            loop {
+                ticks.tick().await;
+                // A store that can't list schedules can't record why either; the next tick retries
+                let _ = self.tick();
+            }
+        })
+    }
+
+    fn tick(&self) -> Result<(), SchedulerError> {
+        let now = unix_micros();
+        for schedule in self.vault.list_schedules()? {
+            if !schedule.enabled || schedule.next_run_us > now {
+                continue;
+            }
+            let next = match Cadence::parse(&schedule.spec) {
+                Ok(cadence) => cadence.next_after(now).unwrap_or(u64::MAX),
+                Err(e) => {
+                    update_schedule(&self.vault, &schedule.id, SchedulePatch { enabled: Some(false), ..Default::default() })?;
+                    self.vault.note_schedule_error(&schedule.id, Some(format!("Disabled: {}", e)))?;
+                    continue;
+                },
+            };
+            self.vault.advance_schedule(&schedule.id, now, next)?;
+
+            if !self.running.lock().unwrap().insert(schedule.id.clone()) {
+                self.vault.note_schedule_error(&schedule.id, Some("Skipped: the previous run was still going".to_string()))?;
+                continue;
+            }
+            let vault = self.vault.clone();
+            let running = Arc::clone(&self.running);
+            tokio::spawn(async move {
+                let id = schedule.id.clone();
+                // The stored run carries its own output and errors
+                let last_error = run_schedule(&vault, schedule).await.err().map(|e| format!("Run not stored: {}", e));
+                let _ = vault.note_schedule_error(&id, last_error);
+                running.lock().unwrap().remove(&id);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Executes the project's current root with the schedule's bindings and stores the result
+/// as the project's next output version
+pub async fn run_schedule(vault: &AetherVault, schedule: Schedule) -> Result<RunRecord, SchedulerError> {
+    let project = vault.get_project(&schedule.project)
+        .map_err(|_| SchedulerError::UnknownProject(schedule.project.clone()))?;
+    let started_at_us = unix_micros();
+    let options = ExecutionOptions { bindings: schedule.inputs, ..Default::default() };
+    let report = if project.root_hash.is_empty() {
+        ExecutionReport {
+            output: Err(KernelError::Runtime(format!("Project '{}' has no deployed root", project.name))),
+            trace: None,
+            warnings: Vec::new(),
+            errors: Vec::new(),
+        }
+    } else {
+        let budget = project.budget.clone().unwrap_or_default();
+        AetherKernel::with_budget(vault.clone(), budget).execute_with(&project.root_hash, &options).await
+    };
+    Ok(crate::runs::record_execution(vault, &project.name, &project.root_hash, &options, &report, started_at_us, Some(schedule.id))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JAN_1_2024_US: u64 = 1_704_067_200_000_000; // Monday, 00:00 UTC
+
+    fn next(spec: &str) -> Option<u64> {
+        Cadence::parse(spec).unwrap().next_after(JAN_1_2024_US).map(|us| us / 1_000_000)
+    }
+
+    #[test]
+    fn cron_fields_and_aliases() {
+        assert_eq!(next("30 9 * * 1-5"), Some(1_704_101_400)); // Jan 1, 09:30
+        assert_eq!(next("@monthly"), Some(1_706_745_600));     // Feb 1, 00:00
+        assert_eq!(next("*/15 * * * *"), Some(1_704_068_100)); // 00:15
+        assert_eq!(next("0 0 * * 7"), Some(1_704_585_600));    // Sunday Jan 7: 7 is Sunday too
+    }
+
+    #[test]
+    fn restricted_day_fields_match_either() {
+        // The 13th or any Friday: Friday Jan 5 comes first
+        assert_eq!(next("0 0 13 * 5"), Some(1_704_412_800));
+    }
+
+    #[test]
+    fn intervals() {
+        assert_eq!(Cadence::parse("@every 15m").unwrap(), Cadence::Every(Duration::from_secs(900)));
+        assert!(Cadence::parse("@every 0s").is_err());
+        assert!(Cadence::parse("@every 5w").is_err());
+    }
+
+    #[test]
+    fn rejects_bad_specs() {
+        assert!(Cadence::parse("* * * *").is_err());
+        assert!(Cadence::parse("60 * * * *").is_err());
+        assert!(Cadence::parse("5-1 * * * *").is_err());
+        assert!(Cadence::parse("*/0 * * * *").is_err());
+        assert!(Cadence::parse("0 0 30 2 *").is_err()); // February 30th never comes
+    }
+
+    #[test]
+    fn invalid_specs_are_disabled_with_their_reason() {
+        let vault = AetherVault::temporary();
+        let schedule = Schedule {
+            id: "sched-1".to_string(),
+            project: "shop".to_string(),
+            spec: "61 * * * *".to_string(),
+            inputs: HashMap::new(),
+            enabled: true,
+            next_run_us: 0,
+            last_run_us: None,
+            last_error: None,
+        };
+        vault.persist_schedule(&schedule).unwrap();
+        Scheduler::new(vault.clone()).tick().unwrap();
+        let stored = vault.get_schedule("sched-1").unwrap();
+        assert!(!stored.enabled);
+        assert!(stored.last_error.as_deref().is_some_and(|e| e.starts_with("Disabled: Invalid schedule '61 * * * *'")), "{:?}", stored.last_error);
+    }
+}
@@ -0,0 +1,259 @@
+use crate::kernel::KernelError;
+use crate::IOContract;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Local data sources for IO (Op 500). These never leave the machine, so the Guard
+/// treats them as sovereign-safe.
+pub const FILE_SCHEME: &str = "file://";
+pub const SQLITE_SCHEME: &str = "sqlite://";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+/// True for `file://` and `sqlite://` endpoints
+pub fn is_local(endpoint: &str) -> bool {
+    endpoint.starts_with(FILE_SCHEME) || endpoint.starts_with(SQLITE_SCHEME)
+}
+
+/// Path part of a local endpoint: `file:///srv/a.csv` -> `/srv/a.csv`, `file://data/a.csv` -> `data/a.csv`
+fn local_path<'a>(endpoint: &'a str, scheme: &str) -> &'a str {
+    let path = &endpoint[scheme.len()..];
+    path.strip_prefix("localhost/").map(|_| &path["localhost".len()..]).unwrap_or(path)
+}
+
+/// Reads a local source described by the contract (blocking; run it off the async executor)
+pub fn read_local(contract: &IOContract) -> Result<Value, KernelError> {
+    if streams_rows(contract) {
+        let mut rows = Vec::new();
+        read_local_each(contract, &mut |row| {
+            rows.push(row);
+            true
+        })?;
+        return Ok(Value::Array(rows));
+    }
+    let path = local_path(&contract.endpoint, FILE_SCHEME);
+    let format = file_format(contract)?;
+    let source_error = |e: &dyn std::fmt::Display| KernelError::Runtime(format!("Local Source Error ({}): {}", path, e));
+    let text = std::fs::read_to_string(path).map_err(|e| source_error(&e))?;
+
+    match format {
+        FileFormat::Json => serde_json::from_str(&text).map_err(|e| source_error(&e)),
+        FileFormat::Ndjson => unreachable!("NDJSON files are read row by row"),
+        FileFormat::Csv => {
+            let mut reader = csv::Reader::from_reader(text.as_bytes());
+            let headers: Vec<String> = reader.headers().map_err(|e| source_error(&e))?
+                .iter().map(|h| h.trim().to_string()).collect();
+            let mut columns: Vec<Vec<String>> = vec![Vec::new(); headers.len()];
+            for record in reader.records() {
+                let record = record.map_err(|e| source_error(&e))?;
+                for (i, column) in columns.iter_mut().enumerate() {
+                    column.push(record.get(i).unwrap_or_default().to_string());
+                }
+            }
+            let typed: Vec<Vec<Value>> = columns.into_iter().map(infer_column).collect();
+            let rows = typed.first().map_or(0, Vec::len);
+            Ok(Value::Array((0..rows).map(|row| {
+                Value::Object(headers.iter().zip(&typed).map(|(h, col)| (h.clone(), col[row].clone())).collect())
+            }).collect()))
+        },
+    }
+}
+
+/// True when the source can hand over one row at a time (NDJSON files and SQLite queries).
+/// CSV column types depend on every row and JSON is a single document, so those are read whole.
+pub fn streams_rows(contract: &IOContract) -> bool {
+    contract.endpoint.starts_with(SQLITE_SCHEME) || matches!(file_format(contract), Ok(FileFormat::Ndjson))
+}
+
+/// Reads a row-wise source (see `streams_rows`), passing each row to `emit` until it returns false.
+/// Blocking; run it off the async executor.
+pub fn read_local_each(contract: &IOContract, emit: &mut dyn FnMut(Value) -> bool) -> Result<(), KernelError> {
+    if contract.endpoint.starts_with(SQLITE_SCHEME) {
+        return query_sqlite(contract, emit);
+    }
+    let path = local_path(&contract.endpoint, FILE_SCHEME);
+    let source_error = |e: &dyn std::fmt::Display| KernelError::Runtime(format!("Local Source Error ({}): {}", path, e));
+    if file_format(contract)? != FileFormat::Ndjson {
+        return Err(source_error(&"only NDJSON files can be read row by row"));
+    }
+    let file = std::fs::File::open(path).map_err(|e| source_error(&e))?;
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| source_error(&e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = serde_json::from_str(&line).map_err(|e| source_error(&format!("line {}: {}", n + 1, e)))?;
+        if !emit(row) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// The contract's `format`, or one guessed from the file extension
+fn file_format(contract: &IOContract) -> Result<FileFormat, KernelError> {
+    let path = local_path(&contract.endpoint, FILE_SCHEME);
+    match contract.format {
+        Some(format) => Ok(format),
+        None => match Path::new(path).extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("csv") => Ok(FileFormat::Csv),
+            Some("ndjson") | Some("jsonl") => Ok(FileFormat::Ndjson),
+            Some("json") => Ok(FileFormat::Json),
+            _ => Err(KernelError::Runtime(format!("Cannot tell the format of '{}'; set `format`", path))),
+        },
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+enum ColumnType {
+    Int,
+    Float,
+    Bool,
+    Text,
+}
+
+/// One type per column: the narrowest of int, float, bool, text that fits every non-empty cell.
+/// Empty cells become null.
+fn infer_column(cells: Vec<String>) -> Vec<Value> {
+    let kind = |cell: &str| {
+        if cell.parse::<i64>().is_ok() {
+            ColumnType::Int
+        } else if cell.parse::<f64>().is_ok_and(f64::is_finite) {
+            ColumnType::Float
+        } else if cell.eq_ignore_ascii_case("true") || cell.eq_ignore_ascii_case("false") {
+            ColumnType::Bool
+        } else {
+            ColumnType::Text
+        }
+    };
+    let column = cells.iter().map(|c| c.trim()).filter(|c| !c.is_empty()).fold(None, |acc: Option<ColumnType>, cell| {
+        let this = kind(cell);
+        Some(match acc {
+            None => this,
+            Some(prev) if prev == this => prev,
+            // Ints widen to floats; any other mix is text
+            Some(prev) if prev <= ColumnType::Float && this <= ColumnType::Float => ColumnType::Float,
+            Some(_) => ColumnType::Text,
+        })
+    });
+    cells.into_iter().map(|cell| {
+        let trimmed = cell.trim();
+        if trimmed.is_empty() {
+            return Value::Null;
+        }
+        match column {
+            Some(ColumnType::Int) => trimmed.parse::<i64>().map(Value::from).unwrap_or(Value::String(cell)),
+            Some(ColumnType::Float) => trimmed.parse::<f64>().map(Value::from).unwrap_or(Value::String(cell)),
+            Some(ColumnType::Bool) => Value::Bool(trimmed.eq_ignore_ascii_case("true")),
+            _ => Value::String(cell),
+        }
+    }).collect()
+}
+
+/// Runs the contract's `sql` against a local SQLite file, read-only.
+/// `sql_params` bind to `?` placeholders (`{{input}}`s in `sql` become `?`s when the contract is bound).
+fn query_sqlite(contract: &IOContract, emit: &mut dyn FnMut(Value) -> bool) -> Result<(), KernelError> {
+    let path = local_path(&contract.endpoint, SQLITE_SCHEME);
+    let sql = contract.sql.as_deref()
+        .ok_or_else(|| KernelError::Runtime(format!("SQLite source '{}' has no `sql`", path)))?;
+    let db_error = |e: rusqlite::Error| KernelError::Runtime(format!("SQLite Error ({}): {}", path, e));
+
+    let conn = rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(db_error)?;
+    let mut statement = conn.prepare(sql).map_err(db_error)?;
+    let columns: Vec<String> = statement.column_names().into_iter().map(str::to_string).collect();
+    let params: Vec<rusqlite::types::Value> = contract.sql_params.iter().map(to_sql).collect();
+
+    let mut rows = statement.query(rusqlite::params_from_iter(params)).map_err(db_error)?;
+    while let Some(row) = rows.next().map_err(db_error)? {
+        let mut record = Map::new();
+        for (i, name) in columns.iter().enumerate() {
+            let value = match row.get_ref(i).map_err(db_error)? {
+                rusqlite::types::ValueRef::Null => Value::Null,
+                rusqlite::types::ValueRef::Integer(n) => Value::from(n),
+                rusqlite::types::ValueRef::Real(f) => Value::from(f),
+                rusqlite::types::ValueRef::Text(t) => Value::String(String::from_utf8_lossy(t).to_string()),
+                rusqlite::types::ValueRef::Blob(b) => Value::String(b.iter().map(|byte| format!("{:02x}", byte)).collect()),
+            };
+            record.insert(name.clone(), value);
+        }
+        if !emit(Value::Object(record)) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn to_sql(value: &Value) -> rusqlite::types::Value {
+    match value {
+        Value::Null => rusqlite::types::Value::Null,
+        Value::Bool(b) => rusqlite::types::Value::Integer(*b as i64),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => rusqlite::types::Value::Integer(i),
+            None => rusqlite::types::Value::Real(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => rusqlite::types::Value::Text(s.clone()),
+        other => rusqlite::types::Value::Text(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn cells(raw: &[&str]) -> Vec<String> {
+        raw.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn local_paths_drop_the_scheme_and_localhost() {
+        assert_eq!(local_path("file:///srv/a.csv", FILE_SCHEME), "/srv/a.csv");
+        assert_eq!(local_path("file://localhost/srv/a.csv", FILE_SCHEME), "/srv/a.csv");
+        assert_eq!(local_path("file://data/a.csv", FILE_SCHEME), "data/a.csv");
+        assert_eq!(local_path("sqlite:///srv/grid.db", SQLITE_SCHEME), "/srv/grid.db");
+        assert!(is_local("sqlite:///srv/grid.db") && !is_local("https://example.com/a.csv"));
+    }
+
+    #[test]
+    fn columns_take_the_narrowest_type_that_fits() {
+        assert_eq!(infer_column(cells(&["1", " 2 ", ""])), vec![json!(1), json!(2), Value::Null]);
+        assert_eq!(infer_column(cells(&["1", "2.5"])), vec![json!(1.0), json!(2.5)]);
+        assert_eq!(infer_column(cells(&["TRUE", "false"])), vec![json!(true), json!(false)]);
+        // Mixed columns stay text, exactly as written
+        assert_eq!(infer_column(cells(&["1", "true"])), vec![json!("1"), json!("true")]);
+        assert_eq!(infer_column(cells(&["007", "x"])), vec![json!("007"), json!("x")]);
+        assert_eq!(infer_column(cells(&["inf", "1"])), vec![json!("inf"), json!("1")]);
+    }
+
+    #[test]
+    fn csv_files_read_as_typed_rows() {
+        let path = std::env::temp_dir().join(format!("aether-source-{}.csv", std::process::id()));
+        std::fs::write(&path, "station, pm25 ,ok\nKL,12,true\nPJ,,false\n").unwrap();
+        let contract: IOContract = serde_json::from_value(json!({
+            "endpoint": format!("file://{}", path.display()), "schema": {}, "sensitivity": 0
+        })).unwrap();
+        let rows = read_local(&contract);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(rows.unwrap(), json!([
+            {"station": "KL", "pm25": 12, "ok": true},
+            {"station": "PJ", "pm25": null, "ok": false},
+        ]));
+    }
+
+    #[test]
+    fn unknown_extensions_need_a_format() {
+        let contract: IOContract = serde_json::from_value(json!({"endpoint": "file:///srv/data.txt", "schema": {}, "sensitivity": 0})).unwrap();
+        assert!(read_local(&contract).unwrap_err().to_string().contains("set `format`"));
+        let contract = IOContract { format: Some(FileFormat::Csv), ..contract };
+        assert!(!read_local(&contract).unwrap_err().to_string().contains("set `format`"));
+    }
+}
@@ -0,0 +1,457 @@
+use crate::kernel::{AetherKernel, Claim, ExecutionOptions, IoMode, KernelError, Memo};
+use crate::source::FileFormat;
+use crate::{IOContract, LogicAtom};
+use futures::future::{self, BoxFuture};
+use futures::stream::{self, BoxStream, Stream, StreamExt, TryStreamExt};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Rows buffered between a source and its consumer; a full buffer pauses the source
+pub const STREAM_BUFFER: usize = 256;
+
+pub type RowStream = BoxStream<'static, Result<Value, KernelError>>;
+
+/// A streamed execution: the root's rows, and the warnings raised while they flow
+pub struct ExecutionStream {
+    pub rows: RowStream,
+    pub warnings: Arc<Mutex<Vec<String>>>,
+}
+
+/// State shared by every node of a single `execute_stream` call.
+struct StreamRun<'a> {
+    options: &'a ExecutionOptions,
+    // A stream has one consumer, so sub-graphs used twice (diamonds) are materialized once
+    shared: HashSet<String>,
+    memo: Memo,
+    warnings: Arc<Mutex<Vec<String>>>,
+}
+
+/// What a node hands its consumer: rows as they are produced, or a value computed whole
+enum NodeOutput {
+    Rows(RowStream),
+    Value(Value),
+}
+
+impl NodeOutput {
+    /// As rows; anything but a list is no rows, like the materialized list opcodes
+    fn into_rows(self) -> RowStream {
+        match self {
+            NodeOutput::Rows(rows) => rows,
+            NodeOutput::Value(Value::Array(items)) => stream::iter(items.into_iter().map(Ok)).boxed(),
+            NodeOutput::Value(_) => stream::empty().boxed(),
+        }
+    }
+
+    async fn collect(self) -> Result<Value, KernelError> {
+        match self {
+            NodeOutput::Rows(rows) => rows.try_collect().await.map(Value::Array),
+            NodeOutput::Value(value) => Ok(value),
+        }
+    }
+}
+
+impl AetherKernel {
+    /// Streaming Execution: IO, FILTER, MERGE, LIMIT and PROJECT pass rows through one at a time,
+    /// pulled by the consumer, so a large source is never held in memory. Other opcodes collect
+    /// their inputs and run as in `execute_with`. A scalar root yields a single row.
+    ///
+    /// The wall-clock budget runs until the last row; a node feeding several consumers is collected
+    /// within the row budget. The row and output budgets apply only to collected nodes; traces are
+    /// not recorded.
+    pub async fn execute_stream(&self, hash: &str, options: &ExecutionOptions) -> Result<ExecutionStream, KernelError> {
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(self.budget.timeout_ms);
+        let run = StreamRun {
+            options,
+            shared: self.shared_nodes(hash)?,
+            memo: Memo::default(),
+            warnings: Arc::new(Mutex::new(Vec::new())),
+        };
+        let output = tokio::time::timeout_at(deadline, self.stream_node(hash, &run)).await
+            .map_err(|_| KernelError::Timeout(self.budget.timeout_ms))??;
+        let rows = match output {
+            NodeOutput::Value(value) if !value.is_array() => stream::once(future::ready(Ok(value))).boxed(),
+            output => output.into_rows(),
+        };
+        Ok(ExecutionStream { rows: until_deadline(rows, deadline, self.budget.timeout_ms), warnings: run.warnings })
+    }
+
+    /// Nodes with more than one consumer in the graph under `root`
+    fn shared_nodes(&self, root: &str) -> Result<HashSet<String>, KernelError> {
+        let mut consumers: HashMap<String, usize> = HashMap::new();
+        let mut visited = HashSet::new();
+        let mut pending = vec![root.to_string()];
+        while let Some(hash) = pending.pop() {
+            if !visited.insert(hash.clone()) {
+                continue;
+            }
+            for input in self.vault.fetch(&hash)?.inputs {
+                *consumers.entry(input.clone()).or_default() += 1;
+                pending.push(input);
+            }
+        }
+        Ok(consumers.into_iter().filter(|(_, n)| *n > 1).map(|(hash, _)| hash).collect())
+    }
+
+    fn stream_node<'a>(&'a self, hash: &'a str, run: &'a StreamRun<'a>) -> BoxFuture<'a, Result<NodeOutput, KernelError>> {
+        Box::pin(async move {
+            if !run.shared.contains(hash) {
+                return self.stream_op_inputs(hash, run).await;
+            }
+            match run.memo.claim(hash) {
+                Claim::Run(sender) => {
+                    let value = match self.stream_op_inputs(hash, run).await {
+                        Ok(output) => self.collect_shared(hash, output, run).await,
+                        Err(e) => Err(e),
+                    };
+                    Memo::settle(sender, &value);
+                    value.map(NodeOutput::Value)
+                },
+                Claim::Wait(pending) => Memo::wait(hash, pending).await.map(NodeOutput::Value),
+            }
+        })
+    }
+
+    /// Collects the rows of a node with several consumers so each can read them, failing as soon
+    /// as they pass the row budget
+    async fn collect_shared(&self, hash: &str, output: NodeOutput, run: &StreamRun<'_>) -> Result<Value, KernelError> {
+        let limit = self.budget.max_rows_per_node;
+        let row_limit = |rows| KernelError::RowLimit { hash: hash.to_string(), rows, limit };
+        let NodeOutput::Rows(mut rows) = output else {
+            return output.collect().await;
+        };
+        let mut items = Vec::new();
+        while let Some(row) = rows.next().await {
+            items.push(row?);
+            if items.len() > limit {
+                return Err(row_limit(items.len()));
+            }
+        }
+        run.warnings.lock().unwrap().push(format!(
+            "Node {} feeds several consumers: its {} rows were held in memory instead of streamed", hash, items.len()
+        ));
+        Ok(Value::Array(items))
+    }
+
+    /// Streams a node's inputs into its op
+    async fn stream_op_inputs(&self, hash: &str, run: &StreamRun<'_>) -> Result<NodeOutput, KernelError> {
+        let atom = self.vault.fetch(hash)?;
+        let (params, data_inputs, failure) = self.partition_inputs(&atom, run.options);
+        if let Some(e) = failure {
+            return Err(e);
+        }
+        let inputs = future::try_join_all(data_inputs.into_iter().map(|h| self.stream_node(h, run))).await?;
+        self.stream_op(hash, &atom, inputs, &params, run).await
+    }
+
+    async fn stream_op(
+        &self,
+        hash: &str,
+        atom: &LogicAtom,
+        inputs: Vec<NodeOutput>,
+        params: &serde_json::Map<String, Value>,
+        run: &StreamRun<'_>,
+    ) -> Result<NodeOutput, KernelError> {
+        let first_rows = |inputs: Vec<NodeOutput>| match inputs.into_iter().next() {
+            Some(input) => input.into_rows(),
+            None => stream::empty().boxed(),
+        };
+        match atom.op_code {
+            2 => { // FILTER
+                let predicate: crate::predicate::Predicate = serde_json::from_value(self.resolve_bound(atom, params)?)
+                    .map_err(|e| KernelError::Runtime(format!("Filter Config Error: {}", e)))?;
+                let matcher = predicate.compile().map_err(KernelError::Runtime)?;
+                Ok(NodeOutput::Rows(first_rows(inputs).try_filter(move |row| future::ready(matcher.matches(row))).boxed()))
+            },
+            3 => { // MERGE / UNION: each input's rows in turn
+                Ok(NodeOutput::Rows(stream::iter(inputs.into_iter().map(NodeOutput::into_rows)).flatten().boxed()))
+            },
+            5 => { // LIMIT / OFFSET: stops pulling from its input once satisfied
+                let config: crate::pipeline::LimitConfig = self.resolve_config(atom, params)?;
+                let mut skipped = 0;
+                let rows = first_rows(inputs).try_filter(move |_| {
+                    let keep = skipped >= config.offset;
+                    skipped += 1;
+                    future::ready(keep)
+                });
+                Ok(NodeOutput::Rows(match config.limit {
+                    Some(n) => rows.take(n).boxed(),
+                    None => rows.boxed(),
+                }))
+            },
+            6 => { // PROJECT / RENAME
+                let config: crate::pipeline::ProjectConfig = self.resolve_config(atom, params)?;
+                Ok(NodeOutput::Rows(first_rows(inputs).map_ok(move |row| crate::pipeline::project_row(row, &config)).boxed()))
+            },
+            500 => self.stream_io(atom, params, run).await,
+            _ => {
+                let mut input_results = Vec::with_capacity(inputs.len());
+                for input in inputs {
+                    input_results.push(input.collect().await?);
+                }
+                let mut warnings = Vec::new();
+                let value = self.apply_op(hash, atom, input_results, params, run.options, &mut warnings).await;
+                run.warnings.lock().unwrap().extend(warnings);
+                let value = value?;
+                let rows = crate::kernel::row_count(&value);
+                if rows > self.budget.max_rows_per_node {
+                    return Err(KernelError::RowLimit { hash: hash.to_string(), rows, limit: self.budget.max_rows_per_node });
+                }
+                Ok(NodeOutput::Value(value))
+            },
+        }
+    }
+
+    /// Streams NDJSON files, SQLite queries and NDJSON HTTP responses row by row. Other sources,
+    /// recording, replay, `data_path` and whole-document schemas fall back to a full read.
+    async fn stream_io(&self, atom: &LogicAtom, params: &serde_json::Map<String, Value>, run: &StreamRun<'_>) -> Result<NodeOutput, KernelError> {
+        let contract: IOContract = serde_json::from_value(self.resolve_bound(atom, params)?)
+            .map_err(|e| KernelError::Runtime(format!("IO Contract Parse Error: {}", e)))?;
+        let row_schema = contract.schema.get("items").is_some()
+            || contract.schema.get("type").is_none_or(|t| t == "array");
+        let streamable = run.options.io_mode == IoMode::Live
+            && contract.data_path.is_none()
+            && row_schema
+            && (!contract.is_local() || crate::source::streams_rows(&contract));
+        if !streamable {
+            let mut warnings = Vec::new();
+            let value = self.fetch_io(atom, params, run.options.io_mode, &mut warnings).await;
+            run.warnings.lock().unwrap().extend(warnings);
+            return value.map(NodeOutput::Value);
+        }
+
+        let rows = if contract.is_local() {
+            let (sender, receiver) = tokio::sync::mpsc::channel(STREAM_BUFFER);
+            let source = contract.clone();
+            tokio::task::spawn_blocking(move || {
+                // A dropped receiver (e.g. a satisfied LIMIT) ends the read
+                let read = crate::source::read_local_each(&source, &mut |row| sender.blocking_send(Ok(row)).is_ok());
+                if let Err(e) = read {
+                    let _ = sender.blocking_send(Err(e));
+                }
+            });
+            stream::unfold(receiver, |mut receiver| async move {
+                receiver.recv().await.map(|row| (row, receiver))
+            }).boxed()
+        } else {
+            let response = self.send_http(&contract, true).await?;
+            let is_ndjson = contract.format == Some(FileFormat::Ndjson)
+                || response.headers().get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .is_some_and(|t| t.contains("ndjson") || t.contains("jsonlines"));
+            if !is_ndjson {
+                let body = response.json::<Value>().await
+                    .map_err(|e| KernelError::Runtime(format!("JSON Parse Error: {}", e)))?;
+                let (value, warnings) = contract.validate_response(body)?;
+                run.warnings.lock().unwrap().extend(warnings);
+                return Ok(NodeOutput::Value(value));
+            }
+            let endpoint = contract.endpoint.clone();
+            let timeout_ms = self.budget.io_timeout_ms;
+            ndjson_rows(response.bytes_stream(), move |e| crate::kernel::io_error(&endpoint, timeout_ms, e), contract.endpoint.clone())
+        };
+        validate_rows(&contract, rows, run.warnings.clone()).map(NodeOutput::Rows)
+    }
+}
+
+/// Ends `rows` with a timeout error once the execution's wall-clock budget runs out
+fn until_deadline(rows: RowStream, deadline: tokio::time::Instant, timeout_ms: u64) -> RowStream {
+    stream::unfold(Some(rows), move |rows| async move {
+        let mut rows = rows?;
+        match tokio::time::timeout_at(deadline, rows.next()).await {
+            Ok(Some(row)) => Some((row, Some(rows))),
+            Ok(None) => None,
+            Err(_) => Some((Err(KernelError::Timeout(timeout_ms)), None)),
+        }
+    }).boxed()
+}
+
+/// Applies the contract's `items` schema to each streamed row: rejects the stream at the first
+/// bad row, or drops it with a warning (`validation: drop_invalid`)
+fn validate_rows(contract: &IOContract, rows: RowStream, warnings: Arc<Mutex<Vec<String>>>) -> Result<RowStream, KernelError> {
+    let Some(validator) = contract.row_validator()? else {
+        return Ok(rows);
+    };
+    let endpoint = contract.endpoint.clone();
+    let drop_invalid = contract.validation == crate::io::ValidationMode::DropInvalid;
+    Ok(rows.enumerate().filter_map(move |(index, row)| future::ready(match row {
+        Ok(row) if validator.is_valid(&row) => Some(Ok(row)),
+        Ok(row) if drop_invalid => {
+            let violations = crate::io::violations(&validator, &row);
+            warnings.lock().unwrap().push(format!("IO '{}': dropped row {}: {}", endpoint, index, violations.join("; ")));
+            None
+        },
+        Ok(row) => Some(Err(KernelError::SchemaViolation {
+            endpoint: endpoint.clone(),
+            violations: validator.iter_errors(&row)
+                .take(crate::io::MAX_VIOLATIONS)
+                .map(|e| format!("/{}{}: {}", index, e.instance_path(), e))
+                .collect(),
+        })),
+        Err(e) => Some(Err(e)),
+    })).boxed())
+}
+
+/// Splits a streamed HTTP body into NDJSON rows without buffering more than one line
+fn ndjson_rows<B, E>(
+    body: impl Stream<Item = Result<B, E>> + Send + 'static,
+    to_error: impl Fn(E) -> KernelError + Send + 'static,
+    endpoint: String,
+) -> RowStream
+where
+    B: AsRef<[u8]> + Send + 'static,
+    E: Send + 'static,
+{
+    struct Lines<S, F> {
+        body: S,
+        to_error: F,
+        endpoint: String,
+        buffer: Vec<u8>,
+        line: usize,
+        done: bool,
+    }
+    let state = Lines { body: Box::pin(body), to_error, endpoint, buffer: Vec::new(), line: 0, done: false };
+    stream::unfold(state, |mut s| async move {
+        loop {
+            let line = match s.buffer.iter().position(|b| *b == b'\n') {
+                Some(end) => Some(s.buffer.drain(..=end).collect::<Vec<u8>>()),
+                None if s.done && !s.buffer.is_empty() => Some(std::mem::take(&mut s.buffer)),
+                None if s.done => return None,
+                None => None,
+            };
+            if let Some(line) = line {
+                s.line += 1;
+                if line.trim_ascii().is_empty() {
+                    continue;
+                }
+                let row = serde_json::from_slice(&line).map_err(|e| {
+                    KernelError::Runtime(format!("NDJSON Parse Error ({} line {}): {}", s.endpoint, s.line, e))
+                });
+                return Some((row, s));
+            }
+            match s.body.next().await {
+                Some(Ok(chunk)) => s.buffer.extend_from_slice(chunk.as_ref()),
+                Some(Err(e)) => {
+                    s.done = true;
+                    s.buffer.clear();
+                    let error = (s.to_error)(e);
+                    return Some((Err(error), s));
+                },
+                None => s.done = true,
+            }
+        }
+    }).boxed()
+}
+
+/// Encodes rows as NDJSON lines. The first error ends the stream with an `{"error": ...}` line;
+/// warnings follow the last line as one `{"warnings": [...]}` line.
+pub fn to_ndjson(execution: ExecutionStream) -> BoxStream<'static, String> {
+    let ExecutionStream { rows, warnings } = execution;
+    let lines = rows.scan(false, |failed, row| future::ready(match (*failed, row) {
+        (true, _) => None,
+        (false, Ok(row)) => Some(format!("{}\n", row)),
+        (false, Err(e)) => {
+            *failed = true;
+            Some(format!("{}\n", serde_json::json!({"error": e.to_string()})))
+        },
+    }));
+    let warnings = stream::once(async move {
+        let warnings = std::mem::take(&mut *warnings.lock().unwrap());
+        (!warnings.is_empty()).then(|| format!("{}\n", serde_json::json!({"warnings": warnings})))
+    }).filter_map(future::ready);
+    lines.chain(warnings).boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::ExecutionBudget;
+    use crate::AetherVault;
+    use serde_json::json;
+
+    fn atom(vault: &AetherVault, op_code: u16, config: Value, inputs: &[&str]) -> String {
+        let storage_ref = crate::write_blob(config.to_string().as_bytes()).unwrap();
+        vault.persist(&LogicAtom {
+            op_code,
+            inputs: inputs.iter().map(|h| h.to_string()).collect(),
+            storage_ref,
+            context_id: "global".to_string(),
+        }).unwrap()
+    }
+
+    /// MERGE of two FILTERs reading one NDJSON file of prices 1..=3
+    fn diamond(vault: &AetherVault, name: &str) -> (String, std::path::PathBuf) {
+        let path = std::env::temp_dir().join(format!("aether-stream-{}-{}.ndjson", name, std::process::id()));
+        std::fs::write(&path, "{\"price\": 1}\n{\"price\": 2}\n{\"price\": 3}\n").unwrap();
+        let endpoint = format!("file://{}", path.display());
+        let source = atom(vault, 500, json!({"endpoint": endpoint, "schema": {}, "sensitivity": 0}), &[]);
+        let low = atom(vault, 2, json!({"field": "price", "op": "<", "val": 2}), &[&source]);
+        let high = atom(vault, 2, json!({"field": "price", "op": ">=", "val": 2}), &[&source]);
+        (atom(vault, 3, json!({}), &[&low, &high]), path)
+    }
+
+    #[tokio::test]
+    async fn shared_nodes_are_collected_with_a_warning() {
+        let vault = AetherVault::temporary();
+        let (root, path) = diamond(&vault, "shared");
+        let kernel = AetherKernel::new(vault);
+        let execution = kernel.execute_stream(&root, &ExecutionOptions::default()).await.unwrap();
+        let rows: Vec<Value> = execution.rows.try_collect().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(rows, vec![json!({"price": 1}), json!({"price": 2}), json!({"price": 3})]);
+        let warnings = execution.warnings.lock().unwrap();
+        assert!(warnings.iter().any(|w| w.contains("held in memory")), "{:?}", warnings);
+    }
+
+    #[tokio::test]
+    async fn shared_nodes_count_against_the_row_budget() {
+        let vault = AetherVault::temporary();
+        let (root, path) = diamond(&vault, "limit");
+        let budget = ExecutionBudget { max_rows_per_node: 2, ..Default::default() };
+        let result = AetherKernel::with_budget(vault, budget).execute_stream(&root, &ExecutionOptions::default()).await;
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(KernelError::RowLimit { limit: 2, .. })));
+    }
+
+    #[tokio::test]
+    async fn rows_end_with_a_timeout_at_the_deadline() {
+        let deadline = tokio::time::Instant::now() + Duration::from_millis(10);
+        let rows = stream::once(future::ready(Ok(json!(1)))).chain(stream::pending()).boxed();
+        let rows: Vec<_> = until_deadline(rows, deadline, 10).collect().await;
+        assert_eq!(rows.len(), 2);
+        assert!(matches!(rows[1], Err(KernelError::Timeout(10))));
+    }
+
+    #[tokio::test]
+    async fn ndjson_ends_with_the_error_then_the_warnings() {
+        let rows = stream::iter([Ok(json!({"n": 1})), Err(KernelError::Timeout(5)), Ok(json!({"n": 2}))]).boxed();
+        let warnings = Arc::new(Mutex::new(vec!["IO 'x': dropped row 3".to_string()]));
+        let lines: Vec<String> = to_ndjson(ExecutionStream { rows, warnings }).collect().await;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "{\"n\":1}\n");
+        assert!(lines[1].starts_with("{\"error\":"), "{}", lines[1]);
+        assert_eq!(lines[2], "{\"warnings\":[\"IO 'x': dropped row 3\"]}\n");
+
+        let quiet = ExecutionStream { rows: stream::iter([Ok(json!(1))]).boxed(), warnings: Arc::default() };
+        assert_eq!(to_ndjson(quiet).collect::<Vec<_>>().await, vec!["1\n".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn streamed_rows_follow_schema_references() {
+        let contract: IOContract = serde_json::from_value(json!({
+            "endpoint": "file:///orders.ndjson",
+            "sensitivity": 0,
+            "validation": "drop_invalid",
+            "schema": {
+                "items": {"$ref": "#/definitions/order"},
+                "definitions": {"order": {"required": ["id"]}}
+            }
+        })).unwrap();
+        let rows = stream::iter([Ok(json!({"id": 1})), Ok(json!({}))]).boxed();
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let kept: Vec<Value> = validate_rows(&contract, rows, warnings.clone()).unwrap().try_collect().await.unwrap();
+        assert_eq!(kept, vec![json!({"id": 1})]);
+        assert_eq!(warnings.lock().unwrap().len(), 1);
+    }
+}
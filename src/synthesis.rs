@@ -0,0 +1,352 @@
+use crate::runs::unix_micros;
+use crate::{AetherGuard, AetherVault, LogicAtom, VaultError};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+pub const OP_SYNTHESIS: u16 = 600;
+
+#[derive(Error, Debug)]
+pub enum SynthesisError {
+    #[error("Vault error: {0}")]
+    Vault(#[from] VaultError),
+    #[error("Unknown synthesis gap '{0}'")]
+    NotFound(String),
+    #[error("Gap '{gap}' is already resolved by {by}")]
+    AlreadyResolved { gap: String, by: String },
+    #[error("Replacement is not concrete logic: {0}")]
+    NotConcrete(String),
+    #[error("Guard rejected {what}: {reason}")]
+    Rejected { what: String, reason: String },
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GapStatus {
+    Pending,
+    Resolved,
+}
+
+/// A SYNTHESIS_REQUIRED (Op 600) atom waiting for concrete logic, stored under `GAP:{hash}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Gap {
+    pub hash: String,
+    pub intent: String,  // What the Loom did not understand
+    pub context: String, // Context of the gap atom (the manifest's app name)
+    pub inputs: Vec<String>, // Dependencies the replacement inherits
+    pub status: GapStatus,
+    pub queued_at_us: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_by: Option<String>, // Hash of the replacement atom
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolved_at_us: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub relinked: Vec<Relink>,
+}
+
+/// A graph rebuilt on top of a gap's replacement
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Relink {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>, // None: a root passed in by the caller
+    pub old_root: String,
+    pub new_root: String,
+}
+
+/// A queued gap and the projects whose current root still contains it
+#[derive(Serialize, Debug, Clone)]
+pub struct GapEntry {
+    #[serde(flatten)]
+    pub gap: Gap,
+    pub blocks: Vec<String>,
+}
+
+impl Gap {
+    pub fn from_atom(hash: &str, atom: &LogicAtom) -> Gap {
+        let intent = crate::read_blob(&atom.storage_ref).unwrap_or_default();
+        Gap {
+            hash: hash.to_string(),
+            intent: String::from_utf8_lossy(&intent).to_string(),
+            context: atom.context_id.clone(),
+            inputs: atom.inputs.clone(),
+            status: GapStatus::Pending,
+            queued_at_us: unix_micros(),
+            resolved_by: None,
+            resolved_at_us: None,
+            relinked: Vec::new(),
+        }
+    }
+}
+
+/// The queue, oldest first, with the projects each gap blocks.
+/// `status` None lists every gap.
+pub fn list_gaps(vault: &AetherVault, status: Option<GapStatus>) -> Result<Vec<GapEntry>, SynthesisError> {
+    let projects = vault.list_projects()?;
+    let mut gaps: Vec<Gap> = vault.list_gaps()?.into_iter()
+        .filter(|gap| status.is_none_or(|s| gap.status == s))
+        .collect();
+    gaps.sort_by_key(|gap| gap.queued_at_us);
+    Ok(gaps.into_iter().map(|gap| {
+        let blocks = projects.iter()
+            .filter(|project| graph_contains(vault, &project.root_hash, &gap.hash))
+            .map(|project| project.name.clone())
+            .collect();
+        GapEntry { gap, blocks }
+    }).collect())
+}
+
+/// Replaces a gap with concrete logic. The replacement takes the gap's context and, when it
+/// declares no inputs, the gap's dependencies. Every graph containing the gap (project roots,
+/// then `extra_roots`) is rebuilt on top of it, Guard-checked under that project's manifest
+/// laws (`guard`'s for the others) and type-checked before any project root moves; the roots
+/// then move together with the gap's record.
+pub fn resolve_gap(
+    vault: &AetherVault,
+    guard: &AetherGuard,
+    gap_hash: &str,
+    mut replacement: LogicAtom,
+    extra_roots: &[String],
+) -> Result<Gap, SynthesisError> {
+    let mut gap = vault.get_gap(gap_hash).map_err(|e| match e {
+        VaultError::NotFound => SynthesisError::NotFound(gap_hash.to_string()),
+        e => SynthesisError::Vault(e),
+    })?;
+    if let Some(by) = &gap.resolved_by {
+        return Err(SynthesisError::AlreadyResolved { gap: gap.hash, by: by.clone() });
+    }
+    if replacement.op_code == OP_SYNTHESIS {
+        return Err(SynthesisError::NotConcrete("a SYNTHESIS_REQUIRED (Op 600) atom".to_string()));
+    }
+    replacement.context_id = gap.context.clone();
+    if replacement.inputs.is_empty() {
+        replacement.inputs = gap.inputs.clone();
+    }
+    let mut replacement_hash = None;
+    let mut relinked = Vec::new();
+    for project in vault.list_projects()? {
+        if graph_contains(vault, &project.root_hash, &gap.hash) {
+            let laws = match &project.laws {
+                Some(laws) => guard.with_laws(laws)
+                    .map_err(|e| SynthesisError::Rejected { what: format!("laws of '{}'", project.name), reason: e.to_string() })?,
+                None => guard.clone(),
+            };
+            let (hash, new_root) = rebuild(vault, &laws, &gap.hash, &replacement, &project.root_hash)?;
+            replacement_hash = Some(hash);
+            relinked.push(Relink { project: Some(project.name), old_root: project.root_hash, new_root });
+        }
+    }
+    for root in extra_roots {
+        if !relinked.iter().any(|r| &r.old_root == root) {
+            let (hash, new_root) = rebuild(vault, guard, &gap.hash, &replacement, root)?;
+            replacement_hash = Some(hash);
+            relinked.push(Relink { project: None, old_root: root.clone(), new_root });
+        }
+    }
+
+    // Each graph checked the replacement under its laws; one in no graph answers to `guard`
+    let replacement_hash = match replacement_hash {
+        Some(hash) => hash,
+        None => verify_replacement(vault, guard, &replacement)?,
+    };
+    gap.status = GapStatus::Resolved;
+    gap.resolved_by = Some(replacement_hash);
+    gap.resolved_at_us = Some(unix_micros());
+    gap.relinked = relinked;
+    vault.commit_resolution(&gap)?;
+    Ok(gap)
+}
+
+/// `root` rebuilt on top of the gap's replacement, every changed node held to `guard`, and
+/// the new graph type-checked. Returns the replacement's hash and the new root.
+fn rebuild(
+    vault: &AetherVault,
+    guard: &AetherGuard,
+    gap_hash: &str,
+    replacement: &LogicAtom,
+    root: &str,
+) -> Result<(String, String), SynthesisError> {
+    let replacement_hash = verify_replacement(vault, guard, replacement)?;
+    let mut rebuilt = HashMap::from([(gap_hash.to_string(), replacement_hash.clone())]);
+    let new_root = relink(vault, guard, root, &mut rebuilt)?;
+    crate::types::check_graph(vault, [&new_root], &HashMap::new())
+        .map_err(|e| SynthesisError::Rejected { what: format!("relinked root {}", new_root), reason: e.to_string() })?;
+    Ok((replacement_hash, new_root))
+}
+
+fn verify_replacement(vault: &AetherVault, guard: &AetherGuard, replacement: &LogicAtom) -> Result<String, SynthesisError> {
+    vault.persist_verified(replacement, guard)
+        .map_err(|e| SynthesisError::Rejected { what: format!("replacement (Op {})", replacement.op_code), reason: e.to_string() })
+}
+
+/// Rebuilds `hash` with its inputs swapped for their rebuilt versions. Atoms are content
+/// addressed, so each changed node is persisted (and Guard-checked) again under a new hash.
+fn relink(
+    vault: &AetherVault,
+    guard: &AetherGuard,
+    hash: &str,
+    rebuilt: &mut HashMap<String, String>,
+) -> Result<String, SynthesisError> {
+    if let Some(new_hash) = rebuilt.get(hash) {
+        return Ok(new_hash.clone());
+    }
+    let mut atom = vault.fetch(hash)?;
+    let mut changed = false;
+    for input in atom.inputs.iter_mut() {
+        let new_input = relink(vault, guard, input, rebuilt)?;
+        changed |= new_input != *input;
+        *input = new_input;
+    }
+    let new_hash = match changed {
+        true => vault.persist_verified(&atom, guard)
+            .map_err(|e| SynthesisError::Rejected { what: format!("dependent {} (Op {})", hash, atom.op_code), reason: e.to_string() })?,
+        false => hash.to_string(),
+    };
+    rebuilt.insert(hash.to_string(), new_hash.clone());
+    Ok(new_hash)
+}
+
+/// Whether `target` is `root` or one of its (transitive) inputs
+fn graph_contains(vault: &AetherVault, root: &str, target: &str) -> bool {
+    let mut visited = HashSet::new();
+    let mut pending = vec![root.to_string()];
+    while let Some(hash) = pending.pop() {
+        if hash == target {
+            return true;
+        }
+        if visited.insert(hash.clone()) && let Ok(atom) = vault.fetch(&hash) {
+            pending.extend(atom.inputs);
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProjectAtom, ProjectStatus};
+    use serde_json::{json, Value};
+
+    fn atom(vault: &AetherVault, op_code: u16, config: Value, inputs: &[&str]) -> String {
+        vault.persist(&concrete(op_code, config, inputs)).unwrap()
+    }
+
+    fn concrete(op_code: u16, config: Value, inputs: &[&str]) -> LogicAtom {
+        LogicAtom {
+            op_code,
+            inputs: inputs.iter().map(|h| h.to_string()).collect(),
+            storage_ref: crate::write_blob(config.to_string().as_bytes()).unwrap(),
+            context_id: "shop".to_string(),
+        }
+    }
+
+    fn gap(vault: &AetherVault, intent: &str) -> String {
+        let atom = LogicAtom {
+            op_code: OP_SYNTHESIS,
+            inputs: vec![],
+            storage_ref: crate::write_blob(intent.as_bytes()).unwrap(),
+            context_id: "shop".to_string(),
+        };
+        let hash = vault.persist(&atom).unwrap();
+        vault.queue_gap(&hash, &atom).unwrap();
+        hash
+    }
+
+    fn project(vault: &AetherVault, name: &str, root_hash: &str, laws: Option<&[&str]>) {
+        vault.persist_project(&ProjectAtom {
+            name: name.to_string(),
+            root_hash: root_hash.to_string(),
+            org_hash: "global".to_string(),
+            status: ProjectStatus::Active,
+            created_at: 0,
+            budget: None,
+            laws: laws.map(|laws| laws.iter().map(|law| law.to_string()).collect()),
+        }).unwrap();
+    }
+
+    fn source(vault: &AetherVault, name: &str) -> String {
+        atom(vault, 500, json!({"endpoint": format!("https://api.example.com/{}", name), "schema": {}, "sensitivity": 0}), &[])
+    }
+
+    /// A zakat at `rate`; the `zakat` law only allows the prescribed rates (3% is not one)
+    fn zakat(rate: &str) -> LogicAtom {
+        concrete(101, json!({"balance": "5000", "rate": rate, "nisab": {"fixed": "4000"}}), &[])
+    }
+
+    #[test]
+    fn graphs_contain_their_transitive_inputs() {
+        let vault = AetherVault::temporary();
+        let source = atom(&vault, 50, json!([{"price": 1}]), &[]);
+        let left = atom(&vault, 5, json!({"limit": 1}), &[&source]);
+        let right = atom(&vault, 5, json!({"limit": 2}), &[&source]);
+        let root = atom(&vault, 3, json!({}), &[&left, &right]);
+        let other = atom(&vault, 50, json!([]), &[]);
+
+        assert!(graph_contains(&vault, &root, &source));
+        assert!(graph_contains(&vault, &root, &root));
+        assert!(!graph_contains(&vault, &root, &other));
+        assert!(!graph_contains(&vault, &left, &right));
+    }
+
+    #[test]
+    fn relink_rebuilds_only_the_dependents_of_what_changed() {
+        let vault = AetherVault::temporary();
+        let guard = AetherGuard::new();
+        let old = source(&vault, "old");
+        let kept = source(&vault, "kept");
+        let limited = atom(&vault, 5, json!({"limit": 1}), &[&old]);
+        let root = atom(&vault, 3, json!({}), &[&limited, &kept]);
+        let new = source(&vault, "new");
+
+        let mut rebuilt = HashMap::from([(old.clone(), new.clone())]);
+        let new_root = relink(&vault, &guard, &root, &mut rebuilt).unwrap();
+        assert_ne!(new_root, root);
+        assert_eq!(rebuilt[&kept], kept);
+        let new_limited = vault.fetch(&rebuilt[&limited]).unwrap();
+        assert_eq!(new_limited.inputs, vec![new.clone()]);
+        assert_eq!(vault.fetch(&new_root).unwrap().inputs, vec![rebuilt[&limited].clone(), kept]);
+        // The old graph is untouched
+        assert_eq!(vault.fetch(&limited).unwrap().inputs, vec![old]);
+    }
+
+    #[test]
+    fn projects_are_relinked_under_their_own_laws() {
+        let vault = AetherVault::temporary();
+        let missing = gap(&vault, "work out the zakat");
+        let root = atom(&vault, 50, json!({}), &[&missing]);
+        project(&vault, "lenient", &root, Some(&["no_riba"]));
+
+        let resolved = resolve_gap(&vault, &AetherGuard::new(), &missing, zakat("0.03"), &[]).unwrap();
+        assert_eq!(resolved.status, GapStatus::Resolved);
+        let [relink] = resolved.relinked.as_slice() else { panic!("{:?}", resolved.relinked) };
+        assert_eq!(vault.get_project("lenient").unwrap().root_hash, relink.new_root);
+        assert_eq!(vault.get_gap(&missing).unwrap().resolved_by, resolved.resolved_by);
+    }
+
+    #[test]
+    fn one_rejected_graph_moves_no_root() {
+        let vault = AetherVault::temporary();
+        let missing = gap(&vault, "work out the zakat");
+        let root = atom(&vault, 50, json!({}), &[&missing]);
+        project(&vault, "lenient", &root, Some(&["no_riba"]));
+        project(&vault, "strict", &root, None);
+
+        let err = resolve_gap(&vault, &AetherGuard::new(), &missing, zakat("0.03"), &[]).unwrap_err();
+        assert!(matches!(err, SynthesisError::Rejected { .. }), "{}", err);
+        assert_eq!(vault.get_project("lenient").unwrap().root_hash, root);
+        assert_eq!(vault.get_project("strict").unwrap().root_hash, root);
+        assert_eq!(vault.get_gap(&missing).unwrap().status, GapStatus::Pending);
+    }
+
+    #[test]
+    fn relinked_graphs_are_type_checked() {
+        let vault = AetherVault::temporary();
+        let missing = gap(&vault, "list the orders");
+        let root = atom(&vault, 4, json!({"keys": [{"field": "price"}]}), &[&missing]);
+        project(&vault, "shop", &root, None);
+
+        // A zakat summary is a record, not the list of rows SORT needs
+        let err = resolve_gap(&vault, &AetherGuard::new(), &missing, zakat("0.025"), &[]).unwrap_err();
+        assert!(err.to_string().contains("cannot consume"), "{}", err);
+        assert_eq!(vault.get_project("shop").unwrap().root_hash, root);
+    }
+}
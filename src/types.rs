@@ -0,0 +1,626 @@
+use crate::expr::{BinOp, ComputeConfig, Expr};
+use crate::pipeline::{AggregateConfig, AggregateFn, JoinConfig, JoinKey, JoinKind, ProjectConfig, SortConfig};
+use crate::predicate::Predicate;
+use crate::{AetherVault, LogicAtom, VaultError};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use thiserror::Error;
+
+/// Static type of a node's output. `Unknown` is what the graph cannot tell before it runs
+/// (an IO without a schema, a synthesis gap, a `$N` read); every check against it passes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DataType {
+    Unknown,
+    Int,
+    Decimal,
+    Text,
+    Bool,
+    Record(RecordShape),
+    List(Box<DataType>),
+    Envelope, // GATEWAY (Op 800) output
+}
+
+/// Fields of a record. An open record may carry fields beyond the listed ones.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RecordShape {
+    pub fields: BTreeMap<String, DataType>,
+    pub open: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum TypeError {
+    #[error("Node '{node}' {usage} field `{field}` that does not exist in the upstream schema of {upstream}: rows are {shape}")]
+    MissingField { node: String, usage: String, field: String, upstream: String, shape: String },
+    #[error("Node '{node}' ({op}) cannot consume {found} from '{upstream}': expected {expected}")]
+    Mismatch { node: String, op: String, upstream: String, expected: String, found: String },
+    #[error("Vault error: {0}")]
+    Vault(#[from] VaultError),
+}
+
+impl DataType {
+    /// Type described by a JSON Schema (e.g. `IOContract.schema`). Objects that list their
+    /// `properties` are closed unless `additionalProperties` allows more.
+    pub fn from_schema(schema: &Value) -> DataType {
+        let declared = match schema.get("type") {
+            Some(Value::String(t)) => Some(t.as_str()),
+            // ["number", "null"]: nullable number
+            Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).find(|t| *t != "null"),
+            _ => None,
+        };
+        let declared = declared.or_else(|| {
+            if schema.get("properties").is_some() { Some("object") } else if schema.get("items").is_some() { Some("array") } else { None }
+        });
+        match declared {
+            Some("integer") => DataType::Int,
+            Some("number") => DataType::Decimal,
+            Some("string") => DataType::Text,
+            Some("boolean") => DataType::Bool,
+            Some("array") => DataType::List(Box::new(schema.get("items").map(DataType::from_schema).unwrap_or(DataType::Unknown))),
+            Some("object") => match schema.get("properties").and_then(Value::as_object) {
+                Some(properties) => DataType::Record(RecordShape {
+                    fields: properties.iter().map(|(name, field)| (name.clone(), DataType::from_schema(field))).collect(),
+                    open: !matches!(schema.get("additionalProperties"), Some(Value::Bool(false)) | None),
+                }),
+                None => DataType::Record(RecordShape { fields: BTreeMap::new(), open: true }),
+            },
+            _ => DataType::Unknown,
+        }
+    }
+
+    /// Type of `field` on this value, resolved like `predicate::lookup` (exact key, then dotted
+    /// path). None when the field cannot exist.
+    pub fn field(&self, field: &str) -> Option<DataType> {
+        if let DataType::Record(shape) = self
+            && let Some(found) = shape.fields.get(field) {
+            return Some(found.clone());
+        }
+        let mut current = self.clone();
+        for segment in field.split('.') {
+            current = match current {
+                DataType::Unknown => return Some(DataType::Unknown),
+                DataType::Record(shape) => match shape.fields.get(segment) {
+                    Some(found) => found.clone(),
+                    None if shape.open => return Some(DataType::Unknown),
+                    None => return None,
+                },
+                DataType::List(item) if segment.parse::<usize>().is_ok() => *item,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Type of a value that is either `a` or `b` (e.g. rows of a MERGE)
+    pub fn union(a: &DataType, b: &DataType) -> DataType {
+        match (a, b) {
+            (a, b) if a == b => a.clone(),
+            (DataType::Int, DataType::Decimal) | (DataType::Decimal, DataType::Int) => DataType::Decimal,
+            (DataType::List(a), DataType::List(b)) => DataType::List(Box::new(DataType::union(a, b))),
+            (DataType::Record(a), DataType::Record(b)) => {
+                let mut fields = a.fields.clone();
+                for (name, field) in &b.fields {
+                    let merged = fields.get(name).map(|existing| DataType::union(existing, field)).unwrap_or_else(|| field.clone());
+                    fields.insert(name.clone(), merged);
+                }
+                DataType::Record(RecordShape { fields, open: a.open || b.open })
+            },
+            _ => DataType::Unknown,
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(self, DataType::Int | DataType::Decimal)
+    }
+
+    /// The record type of a list's rows; Unknown when the list's rows are not known
+    fn rows(&self) -> DataType {
+        match self {
+            DataType::List(item) => (**item).clone(),
+            _ => DataType::Unknown,
+        }
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataType::Unknown => write!(f, "Unknown"),
+            DataType::Int => write!(f, "Int"),
+            DataType::Decimal => write!(f, "Decimal"),
+            DataType::Text => write!(f, "Text"),
+            DataType::Bool => write!(f, "Bool"),
+            DataType::Envelope => write!(f, "Envelope"),
+            DataType::List(item) => write!(f, "List<{}>", item),
+            DataType::Record(shape) => {
+                write!(f, "Record{{")?;
+                for (i, (name, field)) in shape.fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", name, field)?;
+                }
+                match (shape.open, shape.fields.is_empty()) {
+                    (true, true) => write!(f, "..}}"),
+                    (true, false) => write!(f, ", ..}}"),
+                    (false, _) => write!(f, "}}"),
+                }
+            },
+        }
+    }
+}
+
+/// What an opcode accepts on a data input (PARAM inputs are bound separately and never count)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputKind {
+    Any,
+    List,       // MERGE, LIMIT: any list
+    RecordList, // List<Record>
+}
+
+/// The declared input types of an opcode, by data input position
+pub fn input_kind(op_code: u16, index: usize) -> InputKind {
+    match (op_code, index) {
+        (2 | 4 | 6 | 7, 0) | (8, 0 | 1) => InputKind::RecordList,
+        (3, _) | (5, 0) => InputKind::List,
+        _ => InputKind::Any,
+    }
+}
+
+impl InputKind {
+    fn accepts(&self, found: &DataType) -> bool {
+        match (self, found) {
+            (InputKind::Any, _) | (_, DataType::Unknown) => true,
+            (InputKind::List, found) => matches!(found, DataType::List(_)),
+            (InputKind::RecordList, DataType::List(item)) => matches!(**item, DataType::Record(_) | DataType::Unknown),
+            (InputKind::RecordList, _) => false,
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            InputKind::Any => "any value",
+            InputKind::List => "a list",
+            InputKind::RecordList => "a list of records",
+        }
+    }
+}
+
+/// Infers the output type of every node reachable from the given hashes and checks each node
+/// against its inputs' types. `names` maps hashes to manifest node names for error messages;
+/// other atoms (e.g. imported ones) are named by hash.
+pub fn check_graph<'a>(
+    vault: &AetherVault,
+    hashes: impl IntoIterator<Item = &'a String>,
+    names: &HashMap<String, String>,
+) -> Result<HashMap<String, DataType>, TypeError> {
+    let mut checker = TypeChecker { vault, names, inferred: HashMap::new() };
+    for hash in hashes {
+        checker.infer(hash)?;
+    }
+    Ok(checker.inferred.into_iter().map(|(hash, typed)| (hash, typed.data_type)).collect())
+}
+
+/// A node's output type and the node its row shape comes from (for pass-through ops, the
+/// nearest upstream node that defined it)
+#[derive(Clone)]
+struct Typed {
+    data_type: DataType,
+    origin: String,
+}
+
+struct TypeChecker<'a> {
+    vault: &'a AetherVault,
+    names: &'a HashMap<String, String>,
+    inferred: HashMap<String, Typed>,
+}
+
+/// A data input as the node under check sees it
+struct Upstream {
+    name: String,
+    typed: Typed,
+}
+
+impl TypeChecker<'_> {
+    fn name(&self, hash: &str) -> String {
+        self.names.get(hash).cloned().unwrap_or_else(|| hash.chars().take(12).collect())
+    }
+
+    fn infer(&mut self, hash: &str) -> Result<Typed, TypeError> {
+        if let Some(typed) = self.inferred.get(hash) {
+            return Ok(typed.clone());
+        }
+        let atom = self.vault.fetch(hash)?;
+        let mut upstream = Vec::new();
+        for input in &atom.inputs {
+            let typed = self.infer(input)?;
+            if self.vault.fetch(input).map(|a| a.op_code != crate::OP_PARAM).unwrap_or(true) {
+                upstream.push(Upstream { name: self.name(input), typed });
+            }
+        }
+
+        let node = self.name(hash);
+        for (index, input) in upstream.iter().enumerate() {
+            let kind = input_kind(atom.op_code, index);
+            if !kind.accepts(&input.typed.data_type) {
+                return Err(TypeError::Mismatch {
+                    node,
+                    op: format!("Op {}", atom.op_code),
+                    upstream: input.name.clone(),
+                    expected: kind.describe().to_string(),
+                    found: input.typed.data_type.to_string(),
+                });
+            }
+        }
+
+        let (data_type, passes_shape) = NodeCheck { node: &node, upstream: &upstream }.output(&atom)?;
+        let origin = match (passes_shape, upstream.first()) {
+            (true, Some(input)) => input.typed.origin.clone(),
+            _ => node,
+        };
+        let typed = Typed { data_type, origin };
+        self.inferred.insert(hash.to_string(), typed.clone());
+        Ok(typed)
+    }
+}
+
+struct NodeCheck<'a> {
+    node: &'a str,
+    upstream: &'a [Upstream],
+}
+
+impl NodeCheck<'_> {
+    /// The node's output type, and whether it keeps the row shape of input 0
+    fn output(&self, atom: &LogicAtom) -> Result<(DataType, bool), TypeError> {
+        let blob = crate::read_blob(&atom.storage_ref).unwrap_or_default();
+        let input = |index: usize| self.upstream.get(index).map(|u| u.typed.data_type.clone()).unwrap_or(DataType::Unknown);
+        let as_list = |data_type: DataType| match data_type {
+            DataType::List(_) => data_type,
+            _ => DataType::List(Box::new(DataType::Unknown)),
+        };
+
+        Ok(match atom.op_code {
+            1 => (DataType::Int, false), // ADD
+            2 => { // FILTER
+                if let Ok(predicate) = serde_json::from_slice::<Predicate>(&blob) {
+                    let mut fields = Vec::new();
+                    predicate_fields(&predicate, &mut fields);
+                    for field in fields {
+                        self.require_field(0, field, "filters on")?;
+                    }
+                }
+                (as_list(input(0)), true)
+            },
+            3 => { // MERGE
+                let merged = self.upstream.iter()
+                    .map(|u| u.typed.data_type.clone())
+                    .reduce(|a, b| DataType::union(&a, &b))
+                    .unwrap_or(DataType::List(Box::new(DataType::Unknown)));
+                (as_list(merged), false)
+            },
+            4 => { // SORT
+                if let Ok(config) = serde_json::from_slice::<SortConfig>(&blob) {
+                    for key in &config.keys {
+                        self.require_field(0, &key.field, "sorts on")?;
+                    }
+                }
+                (as_list(input(0)), true)
+            },
+            5 => (as_list(input(0)), true), // LIMIT
+            6 => { // PROJECT
+                let Ok(config) = serde_json::from_slice::<ProjectConfig>(&blob) else {
+                    return Ok((as_list(DataType::Unknown), false));
+                };
+                let rows = input(0).rows();
+                let mut shape = RecordShape { fields: BTreeMap::new(), open: config.keep_others };
+                for spec in &config.fields {
+                    let field = self.require_field(0, &spec.field, "projects")?;
+                    shape.fields.insert(spec.output_name().to_string(), field);
+                }
+                if config.keep_others {
+                    match &rows {
+                        DataType::Record(source) => {
+                            for (name, field) in &source.fields {
+                                if !config.fields.iter().any(|spec| &spec.field == name) && !shape.fields.contains_key(name) {
+                                    shape.fields.insert(name.clone(), field.clone());
+                                }
+                            }
+                            shape.open = source.open;
+                        },
+                        _ => shape.open = true,
+                    }
+                }
+                (DataType::List(Box::new(DataType::Record(shape))), false)
+            },
+            7 => { // AGGREGATE
+                let Ok(config) = serde_json::from_slice::<AggregateConfig>(&blob) else {
+                    return Ok((as_list(DataType::Unknown), false));
+                };
+                let mut shape = RecordShape::default();
+                for field in &config.group_by {
+                    shape.fields.insert(field.clone(), self.require_field(0, field, "groups by")?);
+                }
+                for agg in &config.aggregates {
+                    let source = match &agg.field {
+                        Some(field) => self.require_field(0, field, &format!("aggregates ({})", agg.func.name()))?,
+                        None => DataType::Unknown,
+                    };
+                    let result = match agg.func {
+                        AggregateFn::Count | AggregateFn::DistinctCount => DataType::Int,
+                        AggregateFn::Sum if source == DataType::Int => DataType::Int,
+                        AggregateFn::Sum | AggregateFn::Avg => DataType::Decimal,
+                        AggregateFn::Min | AggregateFn::Max => source,
+                    };
+                    shape.fields.insert(agg.output_name(), result);
+                }
+                (DataType::List(Box::new(DataType::Record(shape))), false)
+            },
+            8 => { // JOIN
+                let Ok(config) = serde_json::from_slice::<JoinConfig>(&blob) else {
+                    return Ok((as_list(DataType::Unknown), false));
+                };
+                for key in &config.on {
+                    self.require_field(0, key.left(), "joins on")?;
+                    self.require_field(1, key.right(), "joins on")?;
+                }
+                if config.kind == JoinKind::Anti {
+                    return Ok((as_list(input(0)), true));
+                }
+                let joined = match (input(0).rows(), input(1).rows()) {
+                    (DataType::Record(left), DataType::Record(right)) => {
+                        let mut shape = left.clone();
+                        for (name, field) in &right.fields {
+                            if config.on.iter().any(|k| matches!(k, JoinKey::Same(f) if f == name)) {
+                                continue;
+                            }
+                            let name = match left.fields.contains_key(name) {
+                                true => format!("{}{}", config.prefix, name),
+                                false => name.clone(),
+                            };
+                            shape.fields.insert(name, field.clone());
+                        }
+                        shape.open = left.open || right.open;
+                        DataType::Record(shape)
+                    },
+                    _ => DataType::Unknown,
+                };
+                (DataType::List(Box::new(joined)), false)
+            },
+            9 => { // COMPUTE
+                let Ok(config) = serde_json::from_slice::<ComputeConfig>(&blob) else {
+                    return Ok((DataType::Unknown, false));
+                };
+                let Ok(expr) = Expr::parse(&config.expr) else {
+                    return Ok((DataType::Unknown, false));
+                };
+                let value = self.expr_type(&expr)?;
+                match input(0) {
+                    DataType::List(item) => match *item {
+                        DataType::Record(mut shape) => {
+                            shape.fields.insert(config.output_name().to_string(), value);
+                            (DataType::List(Box::new(DataType::Record(shape))), false)
+                        },
+                        _ => (as_list(DataType::Unknown), false),
+                    },
+                    _ => (value, false),
+                }
+            },
+            20 => { // PARAM
+                let data_type = match serde_json::from_slice::<crate::InputSchema>(&blob).map(|s| s.input_type) {
+                    Ok(t) if t == "number" => DataType::Decimal,
+                    Ok(t) if t == "boolean" => DataType::Bool,
+                    Ok(_) => DataType::Text,
+                    Err(_) => DataType::Unknown,
+                };
+                (data_type, false)
+            },
+            // REACTIVE_TRIGGER and FINANCIAL pass input 0 through
+            50 | 100 if !self.upstream.is_empty() => (input(0), true),
+            100 => (record(&[("status", DataType::Text)]), false),
+            101 => { // ZAKAT
+                if let Ok(config) = serde_json::from_slice::<crate::zakat::ZakatConfig>(&blob)
+                    && config.balance.is_none() && !self.upstream.is_empty() {
+                    self.require_field(0, &config.amount_field, "reads zakat amounts from")?;
+                }
+                let line = record(&[("class", DataType::Text), ("amount", DataType::Decimal), ("zakat_due", DataType::Decimal)]);
+                (record(&[
+                    ("zakat_due", DataType::Decimal),
+                    ("total_wealth", DataType::Decimal),
+                    ("nisab", DataType::Decimal),
+                    ("rate", DataType::Decimal),
+                    ("eligible", DataType::Bool),
+                    ("breakdown", DataType::List(Box::new(line))),
+                ]), false)
+            },
+            500 => { // IO: the contract's schema describes the (extracted) data
+                let data_type = serde_json::from_slice::<crate::IOContract>(&blob)
+                    .map(|contract| DataType::from_schema(&contract.schema))
+                    .unwrap_or(DataType::Unknown);
+                (data_type, false)
+            },
+            800 => (DataType::Envelope, false),
+            _ => (DataType::Unknown, false),
+        })
+    }
+
+    /// Type of `field` on the rows (or record) of data input `index`. Placeholder names are
+    /// only known once bound, so they always pass.
+    fn require_field(&self, index: usize, field: &str, usage: &str) -> Result<DataType, TypeError> {
+        let Some(input) = self.upstream.get(index) else {
+            return Ok(DataType::Unknown);
+        };
+        if field.contains("{{") {
+            return Ok(DataType::Unknown);
+        }
+        let rows = match &input.typed.data_type {
+            DataType::List(item) => (**item).clone(),
+            other => other.clone(),
+        };
+        rows.field(field).ok_or_else(|| TypeError::MissingField {
+            node: self.node.to_string(),
+            usage: usage.to_string(),
+            field: field.to_string(),
+            upstream: match input.typed.origin == input.name {
+                true => format!("'{}'", input.name),
+                false => format!("'{}' (via '{}')", input.typed.origin, input.name),
+            },
+            shape: rows.to_string(),
+        })
+    }
+
+    /// Type of a COMPUTE expression over the rows (or record) of input 0, checking the fields it reads
+    fn expr_type(&self, expr: &Expr) -> Result<DataType, TypeError> {
+        Ok(match expr {
+            Expr::Literal(Value::Bool(_)) => DataType::Bool,
+            Expr::Literal(Value::Number(n)) if n.is_i64() || n.is_u64() => DataType::Int,
+            Expr::Literal(Value::Number(_)) => DataType::Decimal,
+            Expr::Literal(Value::String(_)) => DataType::Text,
+            Expr::Literal(_) | Expr::Input(_) | Expr::Param(_) => DataType::Unknown,
+            Expr::Field(path) => self.require_field(0, path, "computes with")?,
+            Expr::Neg(inner) => match self.expr_type(inner)? {
+                found if found.is_numeric() => found,
+                _ => DataType::Unknown,
+            },
+            Expr::Not(inner) => {
+                self.expr_type(inner)?;
+                DataType::Bool
+            },
+            Expr::Binary(left, op, right) => {
+                let (left, right) = (self.expr_type(left)?, self.expr_type(right)?);
+                match op {
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Rem if left == DataType::Int && right == DataType::Int => DataType::Int,
+                    BinOp::Add if left == DataType::Text && right == DataType::Text => DataType::Text,
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem if left.is_numeric() && right.is_numeric() => DataType::Decimal,
+                    BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Rem => DataType::Unknown,
+                    _ => DataType::Bool,
+                }
+            },
+        })
+    }
+}
+
+/// A closed record with the given fields
+fn record(fields: &[(&str, DataType)]) -> DataType {
+    DataType::Record(RecordShape {
+        fields: fields.iter().map(|(name, field)| (name.to_string(), field.clone())).collect(),
+        open: false,
+    })
+}
+
+fn predicate_fields<'a>(predicate: &'a Predicate, out: &mut Vec<&'a str>) {
+    match predicate {
+        Predicate::And { and: items } | Predicate::Or { or: items } => items.iter().for_each(|p| predicate_fields(p, out)),
+        Predicate::Not { not } => predicate_fields(not, out),
+        Predicate::Compare { field, .. } => out.push(field),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn io(vault: &AetherVault, schema: Value) -> String {
+        let contract = json!({"endpoint": "https://api.example.com/orders", "schema": schema, "sensitivity": 0});
+        atom(vault, 500, contract, &[])
+    }
+
+    fn atom(vault: &AetherVault, op_code: u16, config: Value, inputs: &[&str]) -> String {
+        let storage_ref = crate::write_blob(config.to_string().as_bytes()).unwrap();
+        vault.persist(&LogicAtom {
+            op_code,
+            inputs: inputs.iter().map(|h| h.to_string()).collect(),
+            storage_ref,
+            context_id: "global".to_string(),
+        }).unwrap()
+    }
+
+    fn orders() -> Value {
+        json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "total": {"type": ["number", "null"]},
+                    "customer": {"properties": {"name": {"type": "string"}}, "additionalProperties": false}
+                },
+                "additionalProperties": false
+            }
+        })
+    }
+
+    #[test]
+    fn schemas_become_types() {
+        assert_eq!(
+            DataType::from_schema(&orders()).to_string(),
+            "List<Record{customer: Record{name: Text}, id: Int, total: Decimal}>"
+        );
+        // Listed properties are all there is unless more are allowed
+        assert_eq!(DataType::from_schema(&json!({"properties": {"ok": {"type": "boolean"}}})).to_string(), "Record{ok: Bool}");
+        let open = json!({"properties": {"ok": {"type": "boolean"}}, "additionalProperties": true});
+        assert_eq!(DataType::from_schema(&open).to_string(), "Record{ok: Bool, ..}");
+        assert_eq!(DataType::from_schema(&json!({"type": "object"})).to_string(), "Record{..}");
+        assert_eq!(DataType::from_schema(&json!({"items": {}})), DataType::List(Box::new(DataType::Unknown)));
+        assert_eq!(DataType::from_schema(&json!({})), DataType::Unknown);
+    }
+
+    #[test]
+    fn fields_resolve_dotted_paths() {
+        let row = DataType::from_schema(&orders()).rows();
+        assert_eq!(row.field("customer.name"), Some(DataType::Text));
+        assert_eq!(row.field("customer.email"), None);
+        assert_eq!(row.field("id.value"), None);
+
+        let open = DataType::from_schema(&json!({"properties": {"tags": {"items": {"type": "string"}}}, "additionalProperties": true}));
+        assert_eq!(open.field("tags.0"), Some(DataType::Text));
+        assert_eq!(open.field("anything.else"), Some(DataType::Unknown));
+
+        // An exact key wins over a dotted path
+        let flat = record(&[("customer.name", DataType::Int)]);
+        assert_eq!(flat.field("customer.name"), Some(DataType::Int));
+    }
+
+    #[test]
+    fn unions_widen_numbers_and_merge_records() {
+        assert_eq!(DataType::union(&DataType::Int, &DataType::Decimal), DataType::Decimal);
+        assert_eq!(DataType::union(&DataType::Int, &DataType::Text), DataType::Unknown);
+
+        let a = record(&[("id", DataType::Int), ("name", DataType::Text)]);
+        let b = DataType::Record(RecordShape {
+            fields: [("id".to_string(), DataType::Decimal)].into_iter().collect(),
+            open: true,
+        });
+        let merged = DataType::union(&DataType::List(Box::new(a)), &DataType::List(Box::new(b)));
+        assert_eq!(merged.to_string(), "List<Record{id: Decimal, name: Text, ..}>");
+    }
+
+    #[test]
+    fn missing_fields_name_the_manifest_nodes() {
+        let vault = AetherVault::temporary();
+        let source = io(&vault, orders());
+        let recent = atom(&vault, 5, json!({"limit": 10}), &[&source]);
+        let sorted = atom(&vault, 4, json!({"keys": [{"field": "created_at"}]}), &[&recent]);
+        let names: HashMap<String, String> = [
+            (source.clone(), "orders".to_string()),
+            (recent.clone(), "recent".to_string()),
+            (sorted.clone(), "by_date".to_string()),
+        ].into_iter().collect();
+
+        let err = check_graph(&vault, [&sorted], &names).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "Node 'by_date' sorts on field `created_at` that does not exist in the upstream schema of 'orders' (via 'recent'): \
+             rows are Record{customer: Record{name: Text}, id: Int, total: Decimal}"
+        );
+    }
+
+    #[test]
+    fn inputs_of_the_wrong_kind_are_rejected() {
+        let vault = AetherVault::temporary();
+        let count = atom(&vault, 1, json!({}), &[]);
+        let filter = atom(&vault, 2, json!({"field": "id", "op": "==", "val": 1}), &[&count]);
+        let err = check_graph(&vault, [&filter], &HashMap::new()).unwrap_err();
+        assert!(matches!(err, TypeError::Mismatch { ref expected, ref found, .. } if expected == "a list of records" && found == "Int"));
+    }
+}
@@ -0,0 +1,262 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Nisab weights in grams, per the classical 20 mithqal / 200 dirham measures
+pub const GOLD_NISAB_GRAMS: Decimal = Decimal::from_parts(85, 0, 0, false, 0);
+pub const SILVER_NISAB_GRAMS: Decimal = Decimal::from_parts(595, 0, 0, false, 0);
+
+/// Rates the Guard accepts: 2.5% (wealth), 5% / 10% (irrigated / rain-fed harvest), 20% (rikaz)
+pub fn allowed_rates() -> [Decimal; 4] {
+    [Decimal::new(25, 3), Decimal::new(5, 2), Decimal::new(1, 1), Decimal::new(2, 1)]
+}
+
+/// ZAKAT (Op 101) config blob. Amounts are exact decimals (serialized as strings).
+/// Wealth is either a fixed `balance`, or asset records from input 0 grouped by `class_field`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ZakatConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub balance: Option<Decimal>,
+    #[serde(default = "default_amount_field")]
+    pub amount_field: String,
+    #[serde(default = "default_class_field")]
+    pub class_field: String,
+    #[serde(default = "default_rate")]
+    pub rate: Decimal,
+    pub nisab: Nisab,
+}
+
+impl ZakatConfig {
+    /// Whether `input_count` data inputs are enough: asset records at input 0 unless there is a
+    /// balance, and after them a price input for a gold/silver nisab without a declared price
+    pub fn check_inputs(&self, input_count: usize) -> Result<(), String> {
+        let first = usize::from(self.balance.is_none());
+        if let Nisab::Metal { price: None, price_input, .. } = &self.nisab {
+            if price_input.is_some_and(|i| i < first) {
+                return Err("Zakat (Op 101) price input 0 is the asset list; read the price from a later input".to_string());
+            }
+            let wanted = price_input.map(|i| i + 1).unwrap_or(first + 1);
+            if input_count < wanted {
+                return Err("Zakat (Op 101) gold/silver nisab needs a price input; add one, declare a price or use a fixed nisab".to_string());
+            }
+        }
+        if input_count < first {
+            return Err("Zakat (Op 101) needs a balance or an input of asset records".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn default_amount_field() -> String {
+    "amount".to_string()
+}
+
+fn default_class_field() -> String {
+    "class".to_string()
+}
+
+fn default_rate() -> Decimal {
+    Decimal::new(25, 3)
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Nisab {
+    /// A fixed threshold in the wealth's currency
+    Fixed { fixed: Decimal },
+    /// `grams` of `metal` at a declared per-gram `price`, or at the price read from an input
+    /// (e.g. a local gold price endpoint)
+    Metal {
+        metal: Metal,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        grams: Option<Decimal>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        price: Option<Decimal>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        price_input: Option<usize>, // Defaults to the last input; never input 0 when it holds the assets
+        #[serde(default = "default_price_field")]
+        price_field: String,
+    },
+}
+
+fn default_price_field() -> String {
+    "price".to_string()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Metal {
+    Gold,
+    Silver,
+}
+
+impl Nisab {
+    pub fn needs_input(&self) -> bool {
+        matches!(self, Nisab::Metal { price: None, .. })
+    }
+}
+
+impl Metal {
+    pub fn standard_grams(&self) -> Decimal {
+        match self {
+            Metal::Gold => GOLD_NISAB_GRAMS,
+            Metal::Silver => SILVER_NISAB_GRAMS,
+        }
+    }
+}
+
+/// Reads a JSON number or numeric string as an exact decimal
+pub fn to_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(n) => Decimal::from_str(&n.to_string())
+            .or_else(|_| Decimal::from_scientific(&n.to_string()))
+            .ok(),
+        Value::String(s) => Decimal::from_str(s.trim()).ok(),
+        _ => None,
+    }
+}
+
+/// The nisab in the wealth's currency. With `assets`, input 0 holds the asset records and is
+/// never read as the price.
+fn nisab_value(nisab: &Nisab, inputs: &[Value], assets: bool) -> Result<Decimal, String> {
+    match nisab {
+        Nisab::Fixed { fixed } => Ok(*fixed),
+        Nisab::Metal { metal, grams, price: Some(price), .. } => {
+            grams.unwrap_or_else(|| metal.standard_grams()).checked_mul(*price).ok_or_else(|| "nisab overflow".to_string())
+        },
+        Nisab::Metal { metal, grams, price: None, price_input, price_field } => {
+            let first = usize::from(assets);
+            let index = price_input.or(inputs.len().checked_sub(1))
+                .filter(|index| *index >= first)
+                .ok_or("gold/silver nisab needs a price input besides the asset records")?;
+            let source = inputs.get(index)
+                .ok_or_else(|| format!("price input {} is missing", index))?;
+            let record = match source {
+                Value::Array(items) => items.first().unwrap_or(&Value::Null),
+                other => other,
+            };
+            let price = to_decimal(record)
+                .or_else(|| crate::predicate::lookup(record, price_field).and_then(to_decimal))
+                .ok_or_else(|| format!("no numeric '{}' in price input {}", price_field, index))?;
+            let grams = grams.unwrap_or_else(|| metal.standard_grams());
+            grams.checked_mul(price).ok_or_else(|| "nisab overflow".to_string())
+        },
+    }
+}
+
+/// Computes zakat: `rate` of the total wealth when it reaches the nisab, broken down per asset class.
+/// Negative amounts (debts) reduce the total and appear as negative class lines.
+pub fn calculate(config: &ZakatConfig, inputs: &[Value]) -> Result<Value, String> {
+    let mut classes: BTreeMap<String, Decimal> = BTreeMap::new();
+    if let Some(balance) = config.balance {
+        classes.insert("balance".to_string(), balance);
+    } else {
+        let rows = inputs.first().and_then(Value::as_array).cloned().unwrap_or_default();
+        for row in &rows {
+            let Some(raw) = crate::predicate::lookup(row, &config.amount_field).filter(|v| !v.is_null()) else {
+                continue;
+            };
+            let amount = to_decimal(raw)
+                .ok_or_else(|| format!("'{}' is not a decimal amount: {}", config.amount_field, raw))?;
+            let class = match crate::predicate::lookup(row, &config.class_field) {
+                Some(Value::String(s)) => s.clone(),
+                Some(v) if !v.is_null() => v.to_string(),
+                _ => "unclassified".to_string(),
+            };
+            let total = classes.entry(class).or_default();
+            *total = total.checked_add(amount).ok_or("wealth overflow")?;
+        }
+    }
+
+    let total = classes.values().try_fold(Decimal::ZERO, |acc, v| acc.checked_add(*v))
+        .ok_or("wealth overflow")?;
+    let nisab = nisab_value(&config.nisab, inputs, config.balance.is_none())?;
+    let eligible = total >= nisab && total > Decimal::ZERO;
+    let due_on = |amount: Decimal| -> Result<Decimal, String> {
+        if !eligible {
+            return Ok(Decimal::ZERO);
+        }
+        amount.checked_mul(config.rate).map(|d| d.normalize()).ok_or_else(|| "zakat overflow".to_string())
+    };
+
+    let mut breakdown = Vec::new();
+    for (class, amount) in &classes {
+        breakdown.push(json!({
+            "class": class,
+            "amount": amount.normalize(),
+            "zakat_due": due_on(*amount)?,
+        }));
+    }
+
+    Ok(json!({
+        "zakat_due": due_on(total)?,
+        "total_wealth": total.normalize(),
+        "nisab": nisab.normalize(),
+        "rate": config.rate.normalize(),
+        "eligible": eligible,
+        "breakdown": breakdown,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gold(price_input: Option<usize>) -> Nisab {
+        serde_json::from_value(json!({"metal": "gold", "price_input": price_input})).unwrap()
+    }
+
+    #[test]
+    fn fixed_nisab_ignores_inputs() {
+        let nisab = Nisab::Fixed { fixed: Decimal::from(500) };
+        assert_eq!(nisab_value(&nisab, &[], true), Ok(Decimal::from(500)));
+    }
+
+    #[test]
+    fn declared_price_needs_no_input() {
+        let nisab: Nisab = serde_json::from_value(json!({"metal": "silver", "price": "0.8"})).unwrap();
+        assert!(!nisab.needs_input());
+        assert_eq!(nisab_value(&nisab, &[], true), Ok(Decimal::from(476)));
+    }
+
+    #[test]
+    fn price_comes_from_the_last_input() {
+        let inputs = [json!([{"amount": 9000}]), json!([{"price": "65.5"}])];
+        assert_eq!(nisab_value(&gold(None), &inputs, true), Ok(Decimal::from_str("5567.5").unwrap()));
+        let bare = [json!([]), json!(70)];
+        assert_eq!(nisab_value(&gold(None), &bare, true), Ok(Decimal::from(5950)));
+    }
+
+    #[test]
+    fn asset_records_are_never_read_as_the_price() {
+        let inputs = [json!([{"price": 1}])];
+        assert!(nisab_value(&gold(None), &inputs, true).is_err());
+        assert!(nisab_value(&gold(Some(0)), &inputs, true).is_err());
+        assert_eq!(nisab_value(&gold(Some(0)), &inputs, false), Ok(Decimal::from(85)));
+    }
+
+    #[test]
+    fn inputs_must_hold_the_assets_and_the_price() {
+        let config = |json: Value| -> ZakatConfig { serde_json::from_value(json).unwrap() };
+        let fixed = config(json!({"nisab": {"fixed": "4000"}}));
+        assert!(fixed.check_inputs(0).is_err());
+        assert_eq!(fixed.check_inputs(1), Ok(()));
+        let priced = config(json!({"nisab": {"metal": "gold"}}));
+        assert!(priced.check_inputs(1).is_err());
+        assert_eq!(priced.check_inputs(2), Ok(()));
+        assert!(config(json!({"nisab": {"metal": "gold", "price_input": 0}})).check_inputs(2).is_err());
+        assert_eq!(config(json!({"balance": "5000", "nisab": {"metal": "gold"}})).check_inputs(1), Ok(()));
+    }
+
+    #[test]
+    fn price_field_and_grams_override() {
+        let nisab: Nisab = serde_json::from_value(json!({
+            "metal": "gold", "grams": 87.48, "price_field": "quote.gram"
+        })).unwrap();
+        let inputs = [json!([]), json!({"quote": {"gram": 2}})];
+        assert_eq!(nisab_value(&nisab, &inputs, true), Ok(Decimal::from_str("174.96").unwrap()));
+        assert!(nisab_value(&nisab, &[json!([]), json!({"price": 2})], true).is_err());
+    }
+}